@@ -0,0 +1,137 @@
+use std::{fs, io, path::Path};
+
+/// A verified score.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub seed: u64,
+    pub name: String,
+    pub distance: f32,
+}
+
+/// Every player's best verified distance on every seed.
+#[derive(Debug, Default)]
+pub struct Leaderboard {
+    entries: Vec<Entry>,
+}
+
+impl Leaderboard {
+    /// Loads the scores saved at `path`, starting empty if there are none.
+    /// Lines that don't parse are skipped.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(error),
+        };
+
+        let entries = contents
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                Some(Entry {
+                    seed: fields.next()?.parse().ok()?,
+                    distance: fields.next()?.parse().ok()?,
+                    name: fields.next()?.to_string(),
+                })
+            })
+            .collect();
+
+        Ok(Self { entries })
+    }
+
+    /// Writes next to `path` first and renames, so a crash never leaves a
+    /// half-written file.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents: String = self
+            .entries
+            .iter()
+            .map(|entry| format!("{}\t{}\t{}\n", entry.seed, entry.distance, entry.name))
+            .collect();
+
+        let temporary_path = path.with_extension("tmp");
+        fs::write(&temporary_path, contents)?;
+        fs::rename(&temporary_path, path)
+    }
+
+    /// Records a score, keeping only each player's best on a seed. Returns
+    /// the player's rank on that seed, from 1.
+    pub fn submit(&mut self, entry: Entry) -> usize {
+        let existing = self
+            .entries
+            .iter_mut()
+            .find(|existing| existing.seed == entry.seed && existing.name == entry.name);
+
+        match existing {
+            Some(existing) => existing.distance = existing.distance.max(entry.distance),
+            None => self.entries.push(entry.clone()),
+        }
+
+        self.top(entry.seed, usize::MAX)
+            .iter()
+            .position(|ranked| ranked.name == entry.name)
+            .map_or(0, |index| index + 1)
+    }
+
+    /// The furthest runs on a seed, best first.
+    pub fn top(&self, seed: u64, limit: usize) -> Vec<&Entry> {
+        let mut entries: Vec<&Entry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.seed == seed)
+            .collect();
+        entries.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+        entries.truncate(limit);
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(seed: u64, name: &str, distance: f32) -> Entry {
+        Entry {
+            seed,
+            name: name.to_string(),
+            distance,
+        }
+    }
+
+    #[test]
+    fn ranks_runs_on_the_same_seed() {
+        let mut board = Leaderboard::default();
+
+        assert_eq!(board.submit(entry(1, "a", 10.0)), 1);
+        assert_eq!(board.submit(entry(1, "b", 20.0)), 1);
+        assert_eq!(board.submit(entry(1, "c", 15.0)), 2);
+        assert_eq!(board.submit(entry(2, "d", 5.0)), 1);
+
+        let names: Vec<_> = board.top(1, 2).iter().map(|entry| &entry.name).collect();
+        assert_eq!(names, ["b", "c"]);
+    }
+
+    #[test]
+    fn keeps_each_players_best() {
+        let mut board = Leaderboard::default();
+
+        board.submit(entry(1, "a", 30.0));
+        board.submit(entry(1, "a", 10.0));
+
+        assert_eq!(board.top(1, 10), vec![&entry(1, "a", 30.0)]);
+    }
+
+    #[test]
+    fn survives_a_save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("leaderboard-board-test-{}.txt", std::process::id()));
+
+        let mut board = Leaderboard::default();
+        board.submit(entry(7, "name with spaces", 12.5));
+        board.save(&path).unwrap();
+
+        let loaded = Leaderboard::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.top(7, 10), vec![&entry(7, "name with spaces", 12.5)]);
+    }
+}
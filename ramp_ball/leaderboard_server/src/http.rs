@@ -0,0 +1,155 @@
+//! Just enough HTTP/1.1 for the leaderboard: one request per connection,
+//! bodies sized by `Content-Length`.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+};
+
+/// Submissions are a few kilobytes of replay; anything far bigger is refused.
+const MAX_BODY_BYTES: usize = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: body.into(),
+        }
+    }
+
+    pub fn write_to(&self, mut writer: impl Write) -> std::io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            201 => "Created",
+            400 => "Bad Request",
+            404 => "Not Found",
+            413 => "Payload Too Large",
+            422 => "Unprocessable Entity",
+            _ => "Internal Server Error",
+        };
+
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason,
+            self.body.len(),
+            self.body
+        )?;
+        writer.flush()
+    }
+}
+
+/// Reads one request. Errors are the response to send back.
+pub fn read_request(mut reader: impl BufRead) -> Result<Request, Response> {
+    let bad_request = |message: &str| Response::new(400, message);
+
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|_| bad_request("unreadable request"))?;
+
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(bad_request("malformed request line"));
+    };
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader
+            .read_line(&mut header)
+            .map_err(|_| bad_request("unreadable header"))?;
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| bad_request("malformed Content-Length"))?;
+            }
+        }
+    }
+
+    if content_length > MAX_BODY_BYTES {
+        return Err(Response::new(413, "request body too large"));
+    }
+
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|_| bad_request("request body cut short"))?;
+
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query,
+        body: String::from_utf8(body).map_err(|_| bad_request("body is not UTF-8"))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_query_and_body() {
+        let raw =
+            "POST /scores?seed=7&limit=3 HTTP/1.1\r\nHost: x\r\nContent-Length: 5\r\n\r\nhello";
+
+        let request = read_request(raw.as_bytes()).unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/scores");
+        assert_eq!(request.query["seed"], "7");
+        assert_eq!(request.query["limit"], "3");
+        assert_eq!(request.body, "hello");
+    }
+
+    #[test]
+    fn refuses_oversized_bodies() {
+        let raw = format!(
+            "POST /scores HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_BYTES + 1
+        );
+
+        assert_eq!(read_request(raw.as_bytes()).unwrap_err().status, 413);
+    }
+
+    #[test]
+    fn writes_a_complete_response() {
+        let mut bytes = Vec::new();
+        Response::new(200, "hi").write_to(&mut bytes).unwrap();
+
+        let text = String::from_utf8(bytes).unwrap();
+        assert!(text.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(text.contains("Content-Length: 2\r\n"));
+        assert!(text.ends_with("\r\n\r\nhi"));
+    }
+}
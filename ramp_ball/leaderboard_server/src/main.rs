@@ -0,0 +1,410 @@
+//! Leaderboard for `ramp_ball`. Every submitted run is re-simulated by the
+//! game before its score is accepted.
+//!
+//! Usage: `leaderboard_server [--address ADDR] [--game PATH] [--assets DIR] [--data FILE]`
+//!
+//! - `GET /scores?seed=S&limit=N` lists the best runs on a seed, one
+//!   `<distance>\t<name>` line each.
+//! - `POST /scores` takes `name`, `seed`, `distance` and `replay` (hex) lines
+//!   of `<key> <value>`, and answers `rank <n>` once the replay checks out.
+
+mod board;
+mod http;
+mod verify;
+
+use std::{
+    io::BufReader,
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use board::{Entry, Leaderboard};
+use http::{read_request, Request, Response};
+use verify::{check_claim, GameVerifier, Outcome};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:7879";
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 100;
+const MAX_NAME_CHARS: usize = 16;
+
+/// Slow or stalled clients are dropped after this long.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Verify = dyn Fn(&[u8]) -> Result<Outcome, String> + Send + Sync;
+
+struct Config {
+    address: String,
+    game: PathBuf,
+    asset_root: Option<PathBuf>,
+    data: PathBuf,
+}
+
+impl Config {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        // By default the game is expected next to the server, as it is in
+        // the workspace's target directory.
+        let game = std::env::current_exe()
+            .ok()
+            .and_then(|exe| Some(exe.parent()?.join("ramp_ball")))
+            .unwrap_or_else(|| PathBuf::from("ramp_ball"));
+
+        let mut config = Config {
+            address: DEFAULT_ADDRESS.to_string(),
+            game,
+            asset_root: None,
+            data: PathBuf::from("leaderboard.txt"),
+        };
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+            match arg.as_str() {
+                "--address" => config.address = value("--address")?,
+                "--game" => config.game = value("--game")?.into(),
+                "--assets" => config.asset_root = Some(value("--assets")?.into()),
+                "--data" => config.data = value("--data")?.into(),
+                other => return Err(format!("unknown argument '{}'", other)),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+fn main() {
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!(
+                "usage: leaderboard_server [--address ADDR] [--game PATH] [--assets DIR] [--data FILE]"
+            );
+            std::process::exit(2);
+        }
+    };
+
+    let board = match Leaderboard::load(&config.data) {
+        Ok(board) => board,
+        Err(error) => {
+            eprintln!("could not read {}: {}", config.data.display(), error);
+            std::process::exit(1);
+        }
+    };
+
+    let listener = match TcpListener::bind(&config.address) {
+        Ok(listener) => listener,
+        Err(error) => {
+            eprintln!("could not listen on {}: {}", config.address, error);
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "leaderboard listening on {}, verifying with {}",
+        config.address,
+        config.game.display()
+    );
+
+    let verifier = GameVerifier {
+        game: config.game,
+        asset_root: config.asset_root,
+    };
+
+    serve(
+        listener,
+        Arc::new(Server {
+            board: Mutex::new(board),
+            data: Some(config.data),
+            verify: Box::new(move |replay| verifier.verify(replay)),
+        }),
+    );
+}
+
+struct Server {
+    board: Mutex<Leaderboard>,
+    /// Where the board is saved after every accepted score.
+    data: Option<PathBuf>,
+    verify: Box<Verify>,
+}
+
+fn serve(listener: TcpListener, server: Arc<Server>) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let server = server.clone();
+        thread::spawn(move || handle_connection(stream, &server));
+    }
+}
+
+fn handle_connection(stream: TcpStream, server: &Server) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let Ok(read_half) = stream.try_clone() else {
+        return;
+    };
+
+    let response = match read_request(BufReader::new(read_half)) {
+        Ok(request) => handle(server, request),
+        Err(response) => response,
+    };
+
+    let _ = response.write_to(&stream);
+}
+
+fn handle(server: &Server, request: Request) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/scores") => top_scores(server, &request),
+        ("POST", "/scores") => submit_score(server, &request),
+        _ => Response::new(404, "not found"),
+    }
+}
+
+fn top_scores(server: &Server, request: &Request) -> Response {
+    let Some(Ok(seed)) = request.query.get("seed").map(|seed| seed.parse::<u64>()) else {
+        return Response::new(400, "seed is required");
+    };
+
+    let limit = request
+        .query
+        .get("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(DEFAULT_LIMIT)
+        .min(MAX_LIMIT);
+
+    let board = server.board.lock().unwrap();
+    let body: String = board
+        .top(seed, limit)
+        .iter()
+        .map(|entry| format!("{}\t{}\n", entry.distance, entry.name))
+        .collect();
+
+    Response::new(200, body)
+}
+
+struct Submission {
+    name: String,
+    seed: u64,
+    distance: f32,
+    replay: Vec<u8>,
+}
+
+impl Submission {
+    fn parse(body: &str) -> Result<Self, String> {
+        let field = |key: &str| {
+            body.lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(' '))
+                .ok_or(format!("missing {}", key))
+        };
+
+        let name: String = field("name")?
+            .chars()
+            .filter(|c| !c.is_control())
+            .take(MAX_NAME_CHARS)
+            .collect();
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err("name is empty".to_string());
+        }
+
+        let seed = field("seed")?
+            .parse()
+            .map_err(|_| "malformed seed".to_string())?;
+        let distance: f32 = field("distance")?
+            .parse()
+            .map_err(|_| "malformed distance".to_string())?;
+        if !distance.is_finite() || distance < 0.0 {
+            return Err("malformed distance".to_string());
+        }
+
+        let replay = decode_hex(field("replay")?).ok_or("malformed replay".to_string())?;
+
+        Ok(Self {
+            name,
+            seed,
+            distance,
+            replay,
+        })
+    }
+}
+
+fn submit_score(server: &Server, request: &Request) -> Response {
+    let submission = match Submission::parse(&request.body) {
+        Ok(submission) => submission,
+        Err(error) => return Response::new(400, error),
+    };
+
+    // Re-simulating takes a while, so the board isn't locked meanwhile.
+    let verified = (server.verify)(&submission.replay)
+        .and_then(|outcome| check_claim(submission.seed, submission.distance, outcome));
+
+    if let Err(reason) = verified {
+        println!(
+            "rejected {}m on seed {} from {}: {}",
+            submission.distance, submission.seed, submission.name, reason
+        );
+        return Response::new(422, reason);
+    }
+
+    let mut board = server.board.lock().unwrap();
+    let rank = board.submit(Entry {
+        seed: submission.seed,
+        name: submission.name.clone(),
+        distance: submission.distance,
+    });
+
+    if let Some(data) = &server.data {
+        if let Err(error) = board.save(data) {
+            eprintln!("could not save {}: {}", data.display(), error);
+        }
+    }
+
+    println!(
+        "accepted {}m on seed {} from {}, rank {}",
+        submission.distance, submission.seed, submission.name, rank
+    );
+    Response::new(201, format!("rank {}", rank))
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        io::{Read, Write},
+    };
+
+    use super::*;
+
+    /// A server whose "game" says every replay went 42m on seed 7.
+    fn server() -> Server {
+        Server {
+            board: Mutex::new(Leaderboard::default()),
+            data: None,
+            verify: Box::new(|replay| {
+                if replay.is_empty() {
+                    Err("not a replay file".to_string())
+                } else {
+                    Ok(Outcome {
+                        seed: 7,
+                        distance: 42.0,
+                    })
+                }
+            }),
+        }
+    }
+
+    fn get(path: &str, query: &[(&str, &str)]) -> Request {
+        Request {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            query: query
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            body: String::new(),
+        }
+    }
+
+    fn post(body: &str) -> Request {
+        Request {
+            method: "POST".to_string(),
+            path: "/scores".to_string(),
+            query: HashMap::new(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn verified_scores_make_the_board() {
+        let server = server();
+
+        let response = handle(
+            &server,
+            post("name ada\nseed 7\ndistance 42\nreplay 52425250\n"),
+        );
+        assert_eq!(response, Response::new(201, "rank 1"));
+
+        let response = handle(&server, get("/scores", &[("seed", "7")]));
+        assert_eq!(response, Response::new(200, "42\tada\n"));
+    }
+
+    #[test]
+    fn scores_the_replay_does_not_back_up_are_rejected() {
+        let server = server();
+
+        let response = handle(
+            &server,
+            post("name ada\nseed 7\ndistance 99\nreplay 52425250\n"),
+        );
+        assert_eq!(response.status, 422);
+
+        let response = handle(
+            &server,
+            post("name ada\nseed 8\ndistance 42\nreplay 52425250\n"),
+        );
+        assert_eq!(response.status, 422);
+
+        let response = handle(&server, get("/scores", &[("seed", "7")]));
+        assert_eq!(response.body, "");
+    }
+
+    #[test]
+    fn malformed_submissions_are_bad_requests() {
+        let server = server();
+
+        for body in [
+            "seed 7\ndistance 42\nreplay 00\n",
+            "name ada\nseed x\ndistance 42\nreplay 00\n",
+            "name ada\nseed 7\ndistance NaN\nreplay 00\n",
+            "name ada\nseed 7\ndistance 42\nreplay 0g\n",
+        ] {
+            assert_eq!(handle(&server, post(body)).status, 400, "{}", body);
+        }
+
+        assert_eq!(handle(&server, get("/scores", &[])).status, 400);
+        assert_eq!(handle(&server, get("/elsewhere", &[])).status, 404);
+    }
+
+    #[test]
+    fn answers_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, Arc::new(server())));
+
+        let body = "name ada\nseed 7\ndistance 42\nreplay 52425250\n";
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "POST /scores HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 201 Created\r\n"));
+        assert!(response.ends_with("rank 1"));
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        assert_eq!(decode_hex("00ff7A"), Some(vec![0x00, 0xff, 0x7a]));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Claimed distances may differ from the re-simulated one by this much, to
+/// allow for rounding on the way over the wire.
+const DISTANCE_TOLERANCE: f32 = 0.01;
+
+/// How a replay ended when the game re-simulated it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outcome {
+    pub seed: u64,
+    pub distance: f32,
+}
+
+/// Reads the line printed by `ramp_ball --verify-replay`:
+/// `seed <seed> distance <meters> ticks <ticks>`.
+pub fn parse_outcome(line: &str) -> Option<Outcome> {
+    let mut seed = None;
+    let mut distance = None;
+
+    let words: Vec<&str> = line.split_whitespace().collect();
+    for pair in words.chunks(2) {
+        match pair {
+            ["seed", value] => seed = value.parse().ok(),
+            ["distance", value] => distance = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    Some(Outcome {
+        seed: seed?,
+        distance: distance?,
+    })
+}
+
+/// Checks a claimed score against what actually happened.
+pub fn check_claim(seed: u64, distance: f32, outcome: Outcome) -> Result<(), String> {
+    if outcome.seed != seed {
+        return Err(format!("replay is of seed {}, not {}", outcome.seed, seed));
+    }
+
+    if (outcome.distance - distance).abs() > DISTANCE_TOLERANCE {
+        return Err(format!(
+            "replay ends at {:.2}m, not {:.2}m",
+            outcome.distance, distance
+        ));
+    }
+
+    Ok(())
+}
+
+/// Runs the game headlessly to re-simulate replays.
+pub struct GameVerifier {
+    pub game: PathBuf,
+    /// Directory holding the game's `assets`, if the game can't find them
+    /// on its own.
+    pub asset_root: Option<PathBuf>,
+}
+
+impl GameVerifier {
+    pub fn verify(&self, replay: &[u8]) -> Result<Outcome, String> {
+        static RUNS: AtomicU64 = AtomicU64::new(0);
+
+        // Each run gets a scratch directory to keep the game's save files
+        // out of the way.
+        let directory = std::env::temp_dir().join(format!(
+            "leaderboard-verify-{}-{}",
+            std::process::id(),
+            RUNS.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&directory).map_err(|error| error.to_string())?;
+
+        let result = self.run(&directory, replay);
+        let _ = fs::remove_dir_all(&directory);
+        result
+    }
+
+    fn run(&self, directory: &Path, replay: &[u8]) -> Result<Outcome, String> {
+        let replay_path = directory.join("submitted.replay");
+        fs::write(&replay_path, replay).map_err(|error| error.to_string())?;
+
+        let mut command = Command::new(&self.game);
+        command
+            .arg("--verify-replay")
+            .arg(&replay_path)
+            .current_dir(directory);
+        if let Some(asset_root) = &self.asset_root {
+            command.env("BEVY_ASSET_ROOT", asset_root);
+        }
+
+        let output = command
+            .output()
+            .map_err(|error| format!("can't run {}: {}", self.game.display(), error))?;
+
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout
+            .lines()
+            .find_map(parse_outcome)
+            .ok_or_else(|| "game printed no outcome".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_games_outcome_line() {
+        assert_eq!(
+            parse_outcome("seed 42 distance 17.25 ticks 900"),
+            Some(Outcome {
+                seed: 42,
+                distance: 17.25
+            })
+        );
+        assert_eq!(parse_outcome("seed 42"), None);
+        assert_eq!(parse_outcome("warning: something"), None);
+    }
+
+    #[test]
+    fn claims_must_match_the_outcome() {
+        let outcome = Outcome {
+            seed: 1,
+            distance: 50.0,
+        };
+
+        assert!(check_claim(1, 50.0, outcome).is_ok());
+        assert!(check_claim(1, 50.005, outcome).is_ok());
+        assert!(check_claim(1, 60.0, outcome).is_err());
+        assert!(check_claim(2, 50.0, outcome).is_err());
+    }
+}
@@ -0,0 +1,368 @@
+//! Messages exchanged between the game and the race server.
+//!
+//! Every message is one line of text: a keyword followed by its fields,
+//! separated by spaces. Free text such as player names always comes last, so
+//! it may contain spaces itself. A session can be followed, or faked, with
+//! `nc 127.0.0.1 7878`.
+
+use std::{fmt, str::FromStr};
+
+/// Bumped whenever a message changes shape.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Where the server listens unless told otherwise.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+
+/// Handed out by the server, unique within a race.
+pub type PlayerId = u32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientMessage {
+    Join {
+        version: u32,
+        name: String,
+    },
+    /// The dive input changed at the given fixed tick of the run.
+    Input {
+        tick: u32,
+        diving: bool,
+    },
+    Position {
+        tick: u32,
+        x: f32,
+        y: f32,
+    },
+    /// The ball died, having travelled `distance` meters.
+    Dead {
+        tick: u32,
+        distance: f32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerMessage {
+    Welcome {
+        id: PlayerId,
+        seed: u64,
+        players_needed: u32,
+    },
+    Joined {
+        id: PlayerId,
+        name: String,
+    },
+    Left {
+        id: PlayerId,
+    },
+    /// Everyone is in: drop the balls.
+    Start,
+    Opponent {
+        id: PlayerId,
+        tick: u32,
+        x: f32,
+        y: f32,
+    },
+    OpponentOut {
+        id: PlayerId,
+        distance: f32,
+    },
+    /// Every ball is out. `None` if nobody finished the race.
+    RaceOver {
+        winner: Option<PlayerId>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    Empty,
+    UnknownMessage(String),
+    MissingField(&'static str),
+    BadField(&'static str),
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Empty => write!(f, "empty message"),
+            ProtocolError::UnknownMessage(keyword) => write!(f, "unknown message '{}'", keyword),
+            ProtocolError::MissingField(field) => write!(f, "missing field '{}'", field),
+            ProtocolError::BadField(field) => write!(f, "malformed field '{}'", field),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+impl fmt::Display for ClientMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientMessage::Join { version, name } => write!(f, "JOIN {} {}", version, name),
+            ClientMessage::Input { tick, diving } => {
+                write!(f, "INPUT {} {}", tick, u8::from(*diving))
+            }
+            ClientMessage::Position { tick, x, y } => write!(f, "POSITION {} {} {}", tick, x, y),
+            ClientMessage::Dead { tick, distance } => write!(f, "DEAD {} {}", tick, distance),
+        }
+    }
+}
+
+impl FromStr for ClientMessage {
+    type Err = ProtocolError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut fields = Fields::new(line)?;
+
+        let message = match fields.keyword {
+            "JOIN" => ClientMessage::Join {
+                version: fields.next("version")?,
+                name: fields.rest("name")?,
+            },
+            "INPUT" => ClientMessage::Input {
+                tick: fields.next("tick")?,
+                diving: fields.flag("diving")?,
+            },
+            "POSITION" => ClientMessage::Position {
+                tick: fields.next("tick")?,
+                x: fields.next("x")?,
+                y: fields.next("y")?,
+            },
+            "DEAD" => ClientMessage::Dead {
+                tick: fields.next("tick")?,
+                distance: fields.next("distance")?,
+            },
+            keyword => return Err(ProtocolError::UnknownMessage(keyword.to_string())),
+        };
+
+        fields.finish()?;
+        Ok(message)
+    }
+}
+
+impl fmt::Display for ServerMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerMessage::Welcome {
+                id,
+                seed,
+                players_needed,
+            } => write!(f, "WELCOME {} {} {}", id, seed, players_needed),
+            ServerMessage::Joined { id, name } => write!(f, "JOINED {} {}", id, name),
+            ServerMessage::Left { id } => write!(f, "LEFT {}", id),
+            ServerMessage::Start => write!(f, "START"),
+            ServerMessage::Opponent { id, tick, x, y } => {
+                write!(f, "OPPONENT {} {} {} {}", id, tick, x, y)
+            }
+            ServerMessage::OpponentOut { id, distance } => write!(f, "OUT {} {}", id, distance),
+            ServerMessage::RaceOver { winner: Some(id) } => write!(f, "OVER {}", id),
+            ServerMessage::RaceOver { winner: None } => write!(f, "OVER -"),
+            ServerMessage::Error { message } => write!(f, "ERROR {}", message),
+        }
+    }
+}
+
+impl FromStr for ServerMessage {
+    type Err = ProtocolError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut fields = Fields::new(line)?;
+
+        let message = match fields.keyword {
+            "WELCOME" => ServerMessage::Welcome {
+                id: fields.next("id")?,
+                seed: fields.next("seed")?,
+                players_needed: fields.next("players_needed")?,
+            },
+            "JOINED" => ServerMessage::Joined {
+                id: fields.next("id")?,
+                name: fields.rest("name")?,
+            },
+            "LEFT" => ServerMessage::Left {
+                id: fields.next("id")?,
+            },
+            "START" => ServerMessage::Start,
+            "OPPONENT" => ServerMessage::Opponent {
+                id: fields.next("id")?,
+                tick: fields.next("tick")?,
+                x: fields.next("x")?,
+                y: fields.next("y")?,
+            },
+            "OUT" => ServerMessage::OpponentOut {
+                id: fields.next("id")?,
+                distance: fields.next("distance")?,
+            },
+            "OVER" => ServerMessage::RaceOver {
+                winner: match fields.next::<String>("winner")?.as_str() {
+                    "-" => None,
+                    id => Some(id.parse().map_err(|_| ProtocolError::BadField("winner"))?),
+                },
+            },
+            "ERROR" => ServerMessage::Error {
+                message: fields.rest("message")?,
+            },
+            keyword => return Err(ProtocolError::UnknownMessage(keyword.to_string())),
+        };
+
+        fields.finish()?;
+        Ok(message)
+    }
+}
+
+/// Names go at the end of a line, so they only need to lose line breaks.
+pub fn sanitize_name(name: &str) -> String {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        "Player".to_string()
+    } else {
+        name
+    }
+}
+
+struct Fields<'a> {
+    keyword: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> Fields<'a> {
+    fn new(line: &'a str) -> Result<Self, ProtocolError> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Err(ProtocolError::Empty);
+        }
+
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        Ok(Self { keyword, rest })
+    }
+
+    fn next<T: FromStr>(&mut self, name: &'static str) -> Result<T, ProtocolError> {
+        let rest = self.rest.trim_start();
+        if rest.is_empty() {
+            return Err(ProtocolError::MissingField(name));
+        }
+
+        let (field, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+        self.rest = rest;
+        field.parse().map_err(|_| ProtocolError::BadField(name))
+    }
+
+    fn flag(&mut self, name: &'static str) -> Result<bool, ProtocolError> {
+        match self.next::<u8>(name)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ProtocolError::BadField(name)),
+        }
+    }
+
+    fn rest(&mut self, name: &'static str) -> Result<String, ProtocolError> {
+        let rest = std::mem::take(&mut self.rest).trim();
+        if rest.is_empty() {
+            Err(ProtocolError::MissingField(name))
+        } else {
+            Ok(rest.to_string())
+        }
+    }
+
+    fn finish(self) -> Result<(), ProtocolError> {
+        if self.rest.trim().is_empty() {
+            Ok(())
+        } else {
+            Err(ProtocolError::BadField("trailing"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_messages_round_trip() {
+        let messages = [
+            ClientMessage::Join {
+                version: PROTOCOL_VERSION,
+                name: "Ada Lovelace".to_string(),
+            },
+            ClientMessage::Input {
+                tick: 120,
+                diving: true,
+            },
+            ClientMessage::Position {
+                tick: 124,
+                x: 1503.25,
+                y: -87.5,
+            },
+            ClientMessage::Dead {
+                tick: 900,
+                distance: 42.75,
+            },
+        ];
+
+        for message in messages {
+            assert_eq!(message.to_string().parse::<ClientMessage>(), Ok(message));
+        }
+    }
+
+    #[test]
+    fn server_messages_round_trip() {
+        let messages = [
+            ServerMessage::Welcome {
+                id: 3,
+                seed: u64::MAX,
+                players_needed: 2,
+            },
+            ServerMessage::Joined {
+                id: 1,
+                name: "Grace".to_string(),
+            },
+            ServerMessage::Left { id: 1 },
+            ServerMessage::Start,
+            ServerMessage::Opponent {
+                id: 2,
+                tick: 64,
+                x: 10.0,
+                y: 20.5,
+            },
+            ServerMessage::OpponentOut {
+                id: 2,
+                distance: 12.5,
+            },
+            ServerMessage::RaceOver { winner: Some(2) },
+            ServerMessage::RaceOver { winner: None },
+            ServerMessage::Error {
+                message: "race already started".to_string(),
+            },
+        ];
+
+        for message in messages {
+            assert_eq!(message.to_string().parse::<ServerMessage>(), Ok(message));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert_eq!("".parse::<ClientMessage>(), Err(ProtocolError::Empty));
+        assert_eq!(
+            "HELLO".parse::<ClientMessage>(),
+            Err(ProtocolError::UnknownMessage("HELLO".to_string()))
+        );
+        assert_eq!(
+            "INPUT 5".parse::<ClientMessage>(),
+            Err(ProtocolError::MissingField("diving"))
+        );
+        assert_eq!(
+            "INPUT 5 2".parse::<ClientMessage>(),
+            Err(ProtocolError::BadField("diving"))
+        );
+        assert_eq!(
+            "DEAD 5 1.0 extra".parse::<ClientMessage>(),
+            Err(ProtocolError::BadField("trailing"))
+        );
+    }
+
+    #[test]
+    fn names_stay_on_one_line() {
+        assert_eq!(sanitize_name("  two\nlines "), "two lines");
+        assert_eq!(sanitize_name("\t"), "Player");
+    }
+}
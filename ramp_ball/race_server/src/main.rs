@@ -0,0 +1,350 @@
+//! Authoritative server for online races.
+//!
+//! Usage: `race_server [--address ADDR] [--players N] [--seed SEED]`
+
+mod race;
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use race::{Outgoing, Race, RaceSummary};
+use race_protocol::{ClientMessage, PlayerId, ServerMessage, DEFAULT_ADDRESS, PROTOCOL_VERSION};
+
+struct Config {
+    address: String,
+    players: u32,
+    seed: u64,
+}
+
+impl Config {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut config = Config {
+            address: DEFAULT_ADDRESS.to_string(),
+            players: 2,
+            seed: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_nanos() as u64),
+        };
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+            match arg.as_str() {
+                "--address" => config.address = value("--address")?,
+                "--players" => {
+                    config.players = value("--players")?
+                        .parse()
+                        .map_err(|_| "--players must be a number".to_string())?
+                }
+                "--seed" => {
+                    config.seed = value("--seed")?
+                        .parse()
+                        .map_err(|_| "--seed must be a number".to_string())?
+                }
+                other => return Err(format!("unknown argument '{}'", other)),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+fn main() {
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("usage: race_server [--address ADDR] [--players N] [--seed SEED]");
+            std::process::exit(2);
+        }
+    };
+
+    let listener = match TcpListener::bind(&config.address) {
+        Ok(listener) => listener,
+        Err(error) => {
+            eprintln!("could not listen on {}: {}", config.address, error);
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "race server listening on {}, {} players per race",
+        config.address, config.players
+    );
+
+    serve(listener, Race::new(config.seed, config.players));
+}
+
+/// The race and a write half of every connected player's socket.
+struct Server {
+    race: Race,
+    clients: HashMap<PlayerId, TcpStream>,
+}
+
+impl Server {
+    fn deliver(&mut self, outgoing: Vec<Outgoing>, summary: Option<RaceSummary>) {
+        for Outgoing { to, message } in outgoing {
+            for id in to {
+                if let Some(stream) = self.clients.get_mut(&id) {
+                    // A failed write shows up as a disconnect on the reading side.
+                    let _ = writeln!(stream, "{}", message);
+                }
+            }
+        }
+
+        if let Some(summary) = summary {
+            log_summary(&summary);
+        }
+
+        // Players of a finished race are done; hang up on them.
+        let race = &self.race;
+        self.clients.retain(|id, stream| {
+            let keep = race.contains(*id);
+            if !keep {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+            keep
+        });
+    }
+}
+
+fn log_summary(summary: &RaceSummary) {
+    println!(
+        "race on seed {} over, winner {}",
+        summary.seed,
+        summary
+            .winner
+            .map_or("nobody".to_string(), |id| id.to_string())
+    );
+    for (name, distance, inputs) in &summary.racers {
+        println!("  {}: {:.1}m, {} input changes", name, distance, inputs);
+    }
+}
+
+fn serve(listener: TcpListener, race: Race) {
+    let server = Arc::new(Mutex::new(Server {
+        race,
+        clients: HashMap::new(),
+    }));
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let server = server.clone();
+        thread::spawn(move || handle_client(stream, server));
+    }
+}
+
+fn send(mut stream: &TcpStream, message: ServerMessage) {
+    let _ = writeln!(stream, "{}", message);
+}
+
+fn handle_client(stream: TcpStream, server: Arc<Mutex<Server>>) {
+    let _ = stream.set_nodelay(true);
+    let Ok(read_half) = stream.try_clone() else {
+        return;
+    };
+    let mut lines = BufReader::new(read_half).lines();
+
+    let Some(Ok(line)) = lines.next() else {
+        return;
+    };
+
+    let name = match line.parse::<ClientMessage>() {
+        Ok(ClientMessage::Join { version, name }) if version == PROTOCOL_VERSION => name,
+        Ok(ClientMessage::Join { version, .. }) => {
+            let message = format!(
+                "protocol version {} is not supported, the server speaks {}",
+                version, PROTOCOL_VERSION
+            );
+            return send(&stream, ServerMessage::Error { message });
+        }
+        Ok(_) => {
+            let message = "join the race first".to_string();
+            return send(&stream, ServerMessage::Error { message });
+        }
+        Err(error) => {
+            let message = error.to_string();
+            return send(&stream, ServerMessage::Error { message });
+        }
+    };
+
+    let id = {
+        let mut server = server.lock().unwrap();
+        match server.race.join(&name) {
+            Ok((id, outgoing)) => {
+                let Ok(write_half) = stream.try_clone() else {
+                    return;
+                };
+                server.clients.insert(id, write_half);
+                server.deliver(outgoing, None);
+                id
+            }
+            Err(message) => return send(&stream, ServerMessage::Error { message }),
+        }
+    };
+
+    for line in lines {
+        let Ok(line) = line else {
+            break;
+        };
+
+        match line.parse::<ClientMessage>() {
+            Ok(message) => {
+                let mut server = server.lock().unwrap();
+                let (outgoing, summary) = server.race.handle(id, message);
+                server.deliver(outgoing, summary);
+            }
+            Err(error) => {
+                let message = error.to_string();
+                send(&stream, ServerMessage::Error { message });
+            }
+        }
+    }
+
+    let mut server = server.lock().unwrap();
+    server.clients.remove(&id);
+    let (outgoing, summary) = server.race.leave(id);
+    server.deliver(outgoing, summary);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Lines, Write},
+        net::TcpStream,
+        time::Duration,
+    };
+
+    use super::*;
+
+    struct Client {
+        stream: TcpStream,
+        lines: Lines<BufReader<TcpStream>>,
+    }
+
+    impl Client {
+        fn connect(address: &str, name: &str) -> Self {
+            let stream = TcpStream::connect(address).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let lines = BufReader::new(stream.try_clone().unwrap()).lines();
+            let mut client = Client { stream, lines };
+            client.send(ClientMessage::Join {
+                version: PROTOCOL_VERSION,
+                name: name.to_string(),
+            });
+            client
+        }
+
+        fn send(&mut self, message: ClientMessage) {
+            writeln!(self.stream, "{}", message).unwrap();
+        }
+
+        fn receive(&mut self) -> ServerMessage {
+            self.lines.next().unwrap().unwrap().parse().unwrap()
+        }
+
+        /// Reads until a message matching `wanted` arrives.
+        fn expect(&mut self, wanted: impl Fn(&ServerMessage) -> bool) -> ServerMessage {
+            loop {
+                let message = self.receive();
+                if wanted(&message) {
+                    return message;
+                }
+            }
+        }
+    }
+
+    fn start_server(players: u32) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || serve(listener, Race::new(42, players)));
+        address
+    }
+
+    #[test]
+    fn two_clients_race_over_loopback() {
+        let address = start_server(2);
+
+        let mut first = Client::connect(&address, "first");
+        let ServerMessage::Welcome {
+            id: first_id, seed, ..
+        } = first.receive()
+        else {
+            panic!("expected a welcome");
+        };
+        assert_eq!(seed, 42);
+
+        let mut second = Client::connect(&address, "second");
+        let ServerMessage::Welcome {
+            id: second_id,
+            seed,
+            ..
+        } = second.expect(|message| matches!(message, ServerMessage::Welcome { .. }))
+        else {
+            unreachable!();
+        };
+        assert_eq!(seed, 42);
+
+        first.expect(|message| *message == ServerMessage::Start);
+        second.expect(|message| *message == ServerMessage::Start);
+
+        first.send(ClientMessage::Input {
+            tick: 10,
+            diving: true,
+        });
+        first.send(ClientMessage::Position {
+            tick: 12,
+            x: 3.0,
+            y: -1.0,
+        });
+        assert_eq!(
+            second.receive(),
+            ServerMessage::Opponent {
+                id: first_id,
+                tick: 12,
+                x: 3.0,
+                y: -1.0
+            }
+        );
+
+        first.send(ClientMessage::Dead {
+            tick: 50,
+            distance: 20.0,
+        });
+        second.send(ClientMessage::Dead {
+            tick: 60,
+            distance: 25.0,
+        });
+
+        assert_eq!(
+            first.expect(|message| matches!(message, ServerMessage::RaceOver { .. })),
+            ServerMessage::RaceOver {
+                winner: Some(second_id)
+            }
+        );
+    }
+
+    #[test]
+    fn mismatched_versions_are_turned_away() {
+        let address = start_server(2);
+
+        let stream = TcpStream::connect(&address).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut lines = BufReader::new(stream.try_clone().unwrap()).lines();
+        writeln!(&stream, "JOIN 999 old").unwrap();
+
+        let reply: ServerMessage = lines.next().unwrap().unwrap().parse().unwrap();
+        assert!(matches!(reply, ServerMessage::Error { .. }));
+    }
+}
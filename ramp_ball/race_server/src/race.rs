@@ -0,0 +1,492 @@
+use race_protocol::{sanitize_name, ClientMessage, PlayerId, ServerMessage};
+
+/// State of the race being run, kept apart from the sockets so it can be
+/// tested on its own.
+///
+/// The server is authoritative over who is in the race, the seed, when it
+/// starts and who won. Clients simulate their own ball and report in.
+pub struct Race {
+    seed: u64,
+    players_needed: u32,
+    started: bool,
+    racers: Vec<Racer>,
+    next_id: PlayerId,
+}
+
+struct Racer {
+    id: PlayerId,
+    name: String,
+    /// Fixed ticks at which the dive input changed, and to what.
+    inputs: Vec<(u32, bool)>,
+    out: Option<f32>,
+}
+
+/// A message and the players it goes to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outgoing {
+    pub to: Vec<PlayerId>,
+    pub message: ServerMessage,
+}
+
+/// How a finished race went, for the server log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaceSummary {
+    pub seed: u64,
+    pub winner: Option<PlayerId>,
+    /// Name, distance and number of input changes of every racer.
+    pub racers: Vec<(String, f32, usize)>,
+}
+
+impl Race {
+    pub fn new(seed: u64, players_needed: u32) -> Self {
+        Self {
+            seed,
+            players_needed: players_needed.max(1),
+            started: false,
+            racers: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn contains(&self, id: PlayerId) -> bool {
+        self.racers.iter().any(|racer| racer.id == id)
+    }
+
+    pub fn join(&mut self, name: &str) -> Result<(PlayerId, Vec<Outgoing>), String> {
+        if self.started {
+            return Err("race already started, try again shortly".to_string());
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let name = sanitize_name(name);
+
+        let mut outgoing = vec![Outgoing {
+            to: vec![id],
+            message: ServerMessage::Welcome {
+                id,
+                seed: self.seed,
+                players_needed: self.players_needed,
+            },
+        }];
+
+        for racer in &self.racers {
+            outgoing.push(Outgoing {
+                to: vec![id],
+                message: ServerMessage::Joined {
+                    id: racer.id,
+                    name: racer.name.clone(),
+                },
+            });
+        }
+
+        outgoing.push(Outgoing {
+            to: self.ids(),
+            message: ServerMessage::Joined {
+                id,
+                name: name.clone(),
+            },
+        });
+
+        self.racers.push(Racer {
+            id,
+            name,
+            inputs: Vec::new(),
+            out: None,
+        });
+
+        if self.racers.len() as u32 >= self.players_needed {
+            self.started = true;
+            outgoing.push(Outgoing {
+                to: self.ids(),
+                message: ServerMessage::Start,
+            });
+        }
+
+        Ok((id, outgoing))
+    }
+
+    /// Handles a message from a player who has joined. Messages from
+    /// players of earlier races are ignored.
+    pub fn handle(
+        &mut self,
+        id: PlayerId,
+        message: ClientMessage,
+    ) -> (Vec<Outgoing>, Option<RaceSummary>) {
+        let started = self.started;
+        let others = self.others(id);
+
+        let Some(racer) = self.racers.iter_mut().find(|racer| racer.id == id) else {
+            return (Vec::new(), None);
+        };
+
+        let error = |message: &str| {
+            vec![Outgoing {
+                to: vec![id],
+                message: ServerMessage::Error {
+                    message: message.to_string(),
+                },
+            }]
+        };
+
+        if !started || racer.out.is_some() {
+            return match message {
+                ClientMessage::Join { .. } => (error("already joined"), None),
+                _ => (Vec::new(), None),
+            };
+        }
+
+        match message {
+            ClientMessage::Join { .. } => (error("already joined"), None),
+            ClientMessage::Input { tick, diving } => {
+                if racer.inputs.last().is_some_and(|(last, _)| tick < *last) {
+                    return (error("input went back in time"), None);
+                }
+                racer.inputs.push((tick, diving));
+                (Vec::new(), None)
+            }
+            ClientMessage::Position { tick, x, y } => (
+                vec![Outgoing {
+                    to: others,
+                    message: ServerMessage::Opponent { id, tick, x, y },
+                }],
+                None,
+            ),
+            ClientMessage::Dead { distance, .. } => {
+                racer.out = Some(distance);
+                let mut outgoing = vec![Outgoing {
+                    to: others,
+                    message: ServerMessage::OpponentOut { id, distance },
+                }];
+                let summary = self.finish_if_over(&mut outgoing);
+                (outgoing, summary)
+            }
+        }
+    }
+
+    /// A player disconnected. Before the start they simply leave the lobby;
+    /// during the race they forfeit, unless already out, in which case their
+    /// distance still counts.
+    pub fn leave(&mut self, id: PlayerId) -> (Vec<Outgoing>, Option<RaceSummary>) {
+        let Some(racer) = self.racers.iter().find(|racer| racer.id == id) else {
+            return (Vec::new(), None);
+        };
+
+        if self.started && racer.out.is_some() {
+            return (Vec::new(), None);
+        }
+
+        self.racers.retain(|racer| racer.id != id);
+
+        let mut outgoing = vec![Outgoing {
+            to: self.ids(),
+            message: ServerMessage::Left { id },
+        }];
+
+        let summary = if self.started {
+            self.finish_if_over(&mut outgoing)
+        } else {
+            None
+        };
+
+        (outgoing, summary)
+    }
+
+    /// Ends the race once every ball is out, and opens the lobby for the
+    /// next one on a fresh seed.
+    fn finish_if_over(&mut self, outgoing: &mut Vec<Outgoing>) -> Option<RaceSummary> {
+        if self.racers.iter().any(|racer| racer.out.is_none()) {
+            return None;
+        }
+
+        let winner = self
+            .racers
+            .iter()
+            .filter_map(|racer| racer.out.map(|distance| (racer.id, distance)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(id, _)| id);
+
+        outgoing.push(Outgoing {
+            to: self.ids(),
+            message: ServerMessage::RaceOver { winner },
+        });
+
+        let summary = RaceSummary {
+            seed: self.seed,
+            winner,
+            racers: self
+                .racers
+                .iter()
+                .map(|racer| {
+                    (
+                        racer.name.clone(),
+                        racer.out.unwrap_or_default(),
+                        racer.inputs.len(),
+                    )
+                })
+                .collect(),
+        };
+
+        self.seed = next_seed(self.seed);
+        self.started = false;
+        self.racers.clear();
+
+        Some(summary)
+    }
+
+    fn ids(&self) -> Vec<PlayerId> {
+        self.racers.iter().map(|racer| racer.id).collect()
+    }
+
+    fn others(&self, id: PlayerId) -> Vec<PlayerId> {
+        self.racers
+            .iter()
+            .map(|racer| racer.id)
+            .filter(|other| *other != id)
+            .collect()
+    }
+}
+
+/// SplitMix64 step, so each race gets a new but reproducible course.
+pub fn next_seed(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages_to(outgoing: &[Outgoing], id: PlayerId) -> Vec<ServerMessage> {
+        outgoing
+            .iter()
+            .filter(|outgoing| outgoing.to.contains(&id))
+            .map(|outgoing| outgoing.message.clone())
+            .collect()
+    }
+
+    fn started_race() -> Race {
+        let mut race = Race::new(7, 2);
+        race.join("a").unwrap();
+        race.join("b").unwrap();
+        race
+    }
+
+    #[test]
+    fn joining_players_share_the_seed_and_start_together() {
+        let mut race = Race::new(7, 2);
+
+        let (first, outgoing) = race.join("first").unwrap();
+        assert_eq!(
+            messages_to(&outgoing, first),
+            vec![ServerMessage::Welcome {
+                id: first,
+                seed: 7,
+                players_needed: 2
+            }]
+        );
+
+        let (second, outgoing) = race.join("second").unwrap();
+        assert!(
+            messages_to(&outgoing, second).contains(&ServerMessage::Welcome {
+                id: second,
+                seed: 7,
+                players_needed: 2
+            })
+        );
+        assert!(
+            messages_to(&outgoing, first).contains(&ServerMessage::Joined {
+                id: second,
+                name: "second".to_string()
+            })
+        );
+        assert!(messages_to(&outgoing, first).contains(&ServerMessage::Start));
+        assert!(messages_to(&outgoing, second).contains(&ServerMessage::Start));
+    }
+
+    #[test]
+    fn nobody_joins_a_running_race() {
+        let mut race = started_race();
+        assert!(race.join("late").is_err());
+    }
+
+    #[test]
+    fn positions_go_to_everyone_else() {
+        let mut race = started_race();
+
+        let (outgoing, _) = race.handle(
+            1,
+            ClientMessage::Position {
+                tick: 4,
+                x: 1.0,
+                y: 2.0,
+            },
+        );
+
+        assert_eq!(
+            outgoing,
+            vec![Outgoing {
+                to: vec![2],
+                message: ServerMessage::Opponent {
+                    id: 1,
+                    tick: 4,
+                    x: 1.0,
+                    y: 2.0
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn positions_are_not_relayed_before_the_start() {
+        let mut race = Race::new(7, 2);
+        let (id, _) = race.join("early").unwrap();
+
+        let (outgoing, _) = race.handle(
+            id,
+            ClientMessage::Position {
+                tick: 0,
+                x: 0.0,
+                y: 0.0,
+            },
+        );
+
+        assert!(outgoing.is_empty());
+    }
+
+    #[test]
+    fn input_must_move_forward_in_time() {
+        let mut race = started_race();
+
+        race.handle(
+            1,
+            ClientMessage::Input {
+                tick: 10,
+                diving: true,
+            },
+        );
+        let (outgoing, _) = race.handle(
+            1,
+            ClientMessage::Input {
+                tick: 5,
+                diving: false,
+            },
+        );
+
+        assert!(matches!(
+            messages_to(&outgoing, 1).as_slice(),
+            [ServerMessage::Error { .. }]
+        ));
+    }
+
+    #[test]
+    fn furthest_ball_wins_once_all_are_out() {
+        let mut race = started_race();
+        race.handle(
+            1,
+            ClientMessage::Input {
+                tick: 3,
+                diving: true,
+            },
+        );
+
+        let (outgoing, summary) = race.handle(
+            2,
+            ClientMessage::Dead {
+                tick: 90,
+                distance: 30.0,
+            },
+        );
+        assert!(summary.is_none());
+        assert_eq!(
+            messages_to(&outgoing, 1),
+            vec![ServerMessage::OpponentOut {
+                id: 2,
+                distance: 30.0
+            }]
+        );
+
+        let (outgoing, summary) = race.handle(
+            1,
+            ClientMessage::Dead {
+                tick: 99,
+                distance: 45.0,
+            },
+        );
+        assert!(messages_to(&outgoing, 2).contains(&ServerMessage::RaceOver { winner: Some(1) }));
+
+        let summary = summary.unwrap();
+        assert_eq!(summary.winner, Some(1));
+        assert_eq!(summary.racers[0], ("a".to_string(), 45.0, 1));
+    }
+
+    #[test]
+    fn a_finished_race_opens_a_new_lobby_on_a_new_seed() {
+        let mut race = started_race();
+        race.handle(
+            1,
+            ClientMessage::Dead {
+                tick: 1,
+                distance: 1.0,
+            },
+        );
+        race.handle(
+            2,
+            ClientMessage::Dead {
+                tick: 1,
+                distance: 2.0,
+            },
+        );
+
+        let (id, outgoing) = race.join("next").unwrap();
+
+        assert!(!race.contains(1));
+        assert!(matches!(
+            messages_to(&outgoing, id).as_slice(),
+            [ServerMessage::Welcome { seed, .. }] if *seed == next_seed(7)
+        ));
+    }
+
+    #[test]
+    fn leaving_mid_race_forfeits() {
+        let mut race = started_race();
+        race.handle(
+            2,
+            ClientMessage::Dead {
+                tick: 1,
+                distance: 5.0,
+            },
+        );
+
+        let (outgoing, summary) = race.leave(1);
+
+        assert!(messages_to(&outgoing, 2).contains(&ServerMessage::Left { id: 1 }));
+        assert!(messages_to(&outgoing, 2).contains(&ServerMessage::RaceOver { winner: Some(2) }));
+        assert!(summary.is_some());
+    }
+
+    #[test]
+    fn leaving_after_going_out_keeps_the_distance() {
+        let mut race = started_race();
+        race.handle(
+            1,
+            ClientMessage::Dead {
+                tick: 1,
+                distance: 50.0,
+            },
+        );
+        race.leave(1);
+
+        let (outgoing, _) = race.handle(
+            2,
+            ClientMessage::Dead {
+                tick: 2,
+                distance: 10.0,
+            },
+        );
+
+        assert!(messages_to(&outgoing, 2).contains(&ServerMessage::RaceOver { winner: Some(1) }));
+    }
+}
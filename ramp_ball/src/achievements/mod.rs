@@ -0,0 +1,541 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::EditedRun,
+    events::{
+        CoinCollected, DeathCause, DiveEnded, DiveStarted, PlayerLanded, PlayerTookOff, RunEnded,
+        RunStarted,
+    },
+    player::{Player, PlayerState, TravelDistanceMeters},
+    replay::ReplayPlayback,
+    save::{self, SaveDirectory},
+    spikes::Spikes,
+    tuning::Tuning,
+    versus::Versus,
+    GameState,
+};
+
+/// Achievements, unlocked from gameplay events. Each one is only a
+/// definition in [`ACHIEVEMENTS`]; the tracker turns what happens in a run
+/// into [`Happening`]s that every definition's [`Goal`] reads.
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        let saves = save::directory(app);
+
+        app.insert_resource(saves.load::<AchievementRecord>(ACHIEVEMENTS_FILE))
+            .init_resource::<HappeningTracker>()
+            .add_event::<Happening>()
+            .add_event::<AchievementUnlocked>()
+            // Progress is counted per fixed tick, so it doesn't depend on
+            // the frame rate. The fixed loop runs before Update, which
+            // records it in the same frame.
+            .add_systems(
+                FixedUpdate,
+                track_run_progress
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Alive))
+                    .run_if(not(resource_exists::<ReplayPlayback>))
+                    .run_if(not(resource_exists::<EditedRun>))
+                    .run_if(not(resource_exists::<Versus>)),
+            )
+            .add_systems(
+                Update,
+                (track_run_events, record_achievements)
+                    .chain()
+                    .run_if(not(resource_exists::<ReplayPlayback>))
+                    .run_if(not(resource_exists::<EditedRun>))
+                    .run_if(not(resource_exists::<Versus>)),
+            );
+    }
+}
+
+const ACHIEVEMENTS_FILE: &str = "achievements.ron";
+
+pub struct Achievement {
+    /// Saved in the record, so it must never change once released.
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub goal: Goal,
+}
+
+pub const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "first_drop",
+        name: "First Drop",
+        description: "Play a run",
+        goal: Goal::RunsPlayed(1),
+    },
+    Achievement {
+        id: "regular",
+        name: "Regular",
+        description: "Play 100 runs",
+        goal: Goal::RunsPlayed(100),
+    },
+    Achievement {
+        id: "kilometer",
+        name: "Kilometer",
+        description: "Reach 1000m in one run",
+        goal: Goal::Distance(1000.0),
+    },
+    Achievement {
+        id: "long_haul",
+        name: "Long Haul",
+        description: "Reach 5000m in one run",
+        goal: Goal::Distance(5000.0),
+    },
+    Achievement {
+        id: "pocket_money",
+        name: "Pocket Money",
+        description: "Collect 25 coins in one run",
+        goal: Goal::CoinsInRun(25),
+    },
+    Achievement {
+        id: "marathon",
+        name: "Marathon",
+        description: "Travel 42195m across all runs",
+        goal: Goal::TotalDistance(42_195.0),
+    },
+    Achievement {
+        id: "close_call",
+        name: "Close Call",
+        description: "Survive 60s with the spikes within 200px",
+        goal: Goal::NearSpikes {
+            seconds: 60.0,
+            within: 200.0,
+        },
+    },
+    Achievement {
+        id: "diver",
+        name: "Diver",
+        description: "Land 10 dives in a row",
+        goal: Goal::DiveStreak(10),
+    },
+];
+
+/// What an achievement asks for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Goal {
+    /// Meters travelled in one run.
+    Distance(f32),
+    /// Meters travelled across every run.
+    TotalDistance(f32),
+    RunsPlayed(u32),
+    /// Seconds in a row, in one run, with the spike wall at most `within`
+    /// pixels behind the ball.
+    NearSpikes {
+        seconds: f32,
+        within: f32,
+    },
+    /// Landings in a row that came down from a dive.
+    DiveStreak(u32),
+    CoinsInRun(u32),
+    /// Runs ended by `cause` at least `after` meters in.
+    DiedTo {
+        cause: DeathCause,
+        after: f32,
+    },
+}
+
+/// Something the tracker saw, fed to every goal in turn. Anything with
+/// goals of its own reads these instead of the gameplay events.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum Happening {
+    RunStarted,
+    RunEnded {
+        distance: f32,
+        cause: DeathCause,
+    },
+    /// The run has got `distance` meters in, `gained` of them new.
+    Travelled {
+        distance: f32,
+        gained: f32,
+    },
+    /// Time passed while alive, with the gap from the spike wall to the ball.
+    Survived {
+        seconds: f32,
+        spike_gap: f32,
+    },
+    Landed {
+        dived: bool,
+    },
+    CoinCollected,
+}
+
+impl Goal {
+    pub fn target(self) -> f32 {
+        match self {
+            Goal::Distance(meters) | Goal::TotalDistance(meters) => meters,
+            Goal::RunsPlayed(runs) => runs as f32,
+            Goal::NearSpikes { seconds, .. } => seconds,
+            Goal::DiveStreak(landings) => landings as f32,
+            Goal::CoinsInRun(coins) => coins as f32,
+            Goal::DiedTo { .. } => 1.0,
+        }
+    }
+
+    /// Whether progress starts over with every run.
+    pub fn per_run(self) -> bool {
+        matches!(
+            self,
+            Goal::Distance(_) | Goal::NearSpikes { .. } | Goal::DiveStreak(_) | Goal::CoinsInRun(_)
+        )
+    }
+
+    fn advance(self, progress: f32, happening: Happening) -> f32 {
+        match (self, happening) {
+            (Goal::RunsPlayed(_), Happening::RunStarted) => progress + 1.0,
+            (_, Happening::RunStarted) if self.per_run() => 0.0,
+            (Goal::Distance(_), Happening::Travelled { distance, .. }) => progress.max(distance),
+            (Goal::TotalDistance(_), Happening::Travelled { gained, .. }) => progress + gained,
+            (Goal::NearSpikes { within, .. }, Happening::Survived { seconds, spike_gap }) => {
+                if spike_gap <= within {
+                    progress + seconds
+                } else {
+                    0.0
+                }
+            }
+            (Goal::DiveStreak(_), Happening::Landed { dived }) => {
+                if dived {
+                    progress + 1.0
+                } else {
+                    0.0
+                }
+            }
+            (Goal::CoinsInRun(_), Happening::CoinCollected) => progress + 1.0,
+            (
+                Goal::DiedTo { cause, after },
+                Happening::RunEnded {
+                    distance,
+                    cause: ended_by,
+                },
+            ) if ended_by == cause && distance >= after => progress + 1.0,
+            _ => progress,
+        }
+    }
+}
+
+/// Sent once, the moment an achievement is unlocked.
+#[derive(Event, Clone, Copy)]
+pub struct AchievementUnlocked(pub &'static Achievement);
+
+/// Progress towards a set of goals, by id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GoalProgress {
+    /// Best progress for per-run goals, current progress for the others.
+    saved: BTreeMap<String, f32>,
+    /// Progress of per-run goals in the current run.
+    #[serde(skip)]
+    this_run: HashMap<&'static str, f32>,
+}
+
+impl GoalProgress {
+    /// The furthest a goal has got.
+    pub fn best(&self, id: &str) -> f32 {
+        self.saved.get(id).copied().unwrap_or(0.0)
+    }
+
+    /// How far a goal has got in this run, or overall if it isn't per run.
+    pub fn current(&self, id: &str, goal: Goal) -> f32 {
+        if goal.per_run() {
+            self.this_run.get(id).copied().unwrap_or(0.0)
+        } else {
+            self.best(id)
+        }
+    }
+
+    /// Moves a goal along, returning whether it has now been reached.
+    pub fn advance(&mut self, id: &'static str, goal: Goal, happening: Happening) -> bool {
+        let progress = goal.advance(self.current(id, goal), happening);
+        if goal.per_run() {
+            self.this_run.insert(id, progress);
+        }
+
+        let saved = self.saved.entry(id.to_string()).or_default();
+        *saved = if goal.per_run() {
+            saved.max(progress)
+        } else {
+            progress
+        };
+
+        progress >= goal.target()
+    }
+}
+
+/// Unlocked achievements and the furthest each one has got, saved locally.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AchievementRecord {
+    unlocked: BTreeSet<String>,
+    progress: GoalProgress,
+}
+
+impl AchievementRecord {
+    pub fn is_unlocked(&self, achievement: &Achievement) -> bool {
+        self.unlocked.contains(achievement.id)
+    }
+
+    pub fn progress(&self, achievement: &Achievement) -> f32 {
+        self.progress.best(achievement.id)
+    }
+
+    /// Moves every goal along, returning the achievements this unlocked.
+    fn observe(&mut self, happening: Happening) -> Vec<&'static Achievement> {
+        let mut unlocked = Vec::new();
+
+        for achievement in ACHIEVEMENTS {
+            let reached = self
+                .progress
+                .advance(achievement.id, achievement.goal, happening);
+
+            if reached && self.unlocked.insert(achievement.id.to_string()) {
+                unlocked.push(achievement);
+            }
+        }
+
+        unlocked
+    }
+
+    /// One line per achievement, for the achievements screen.
+    pub fn describe(&self) -> String {
+        ACHIEVEMENTS
+            .iter()
+            .map(|achievement| {
+                let status = if self.is_unlocked(achievement) {
+                    "Unlocked".to_string()
+                } else {
+                    format!(
+                        "{:.0}/{:.0}",
+                        self.progress(achievement).min(achievement.goal.target()),
+                        achievement.goal.target()
+                    )
+                };
+                format!(
+                    "{} - {}  {}",
+                    achievement.name, achievement.description, status
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// What the tracker remembers between frames to turn gameplay events into
+/// [`Happening`]s.
+#[derive(Resource, Debug, Default)]
+struct HappeningTracker {
+    /// Distance already reported, so a rewound run only gains what it
+    /// travels past it.
+    counted_distance: f32,
+    diving: bool,
+    /// Whether the ball has dived since it last took off.
+    dived_in_air: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn track_run_events(
+    mut run_started: EventReader<RunStarted>,
+    mut dive_started: EventReader<DiveStarted>,
+    mut dive_ended: EventReader<DiveEnded>,
+    mut took_off: EventReader<PlayerTookOff>,
+    mut landed: EventReader<PlayerLanded>,
+    mut coin_collected: EventReader<CoinCollected>,
+    mut run_ended: EventReader<RunEnded>,
+    mut tracker: ResMut<HappeningTracker>,
+    mut happenings: EventWriter<Happening>,
+) {
+    for _ in run_started.read() {
+        *tracker = HappeningTracker::default();
+        happenings.send(Happening::RunStarted);
+    }
+
+    for _ in dive_started.read() {
+        tracker.diving = true;
+        tracker.dived_in_air = true;
+    }
+
+    for _ in dive_ended.read() {
+        tracker.diving = false;
+    }
+
+    for _ in took_off.read() {
+        tracker.dived_in_air = tracker.diving;
+    }
+
+    for _ in landed.read() {
+        let dived = tracker.dived_in_air;
+        tracker.dived_in_air = tracker.diving;
+        happenings.send(Happening::Landed { dived });
+    }
+
+    for _ in coin_collected.read() {
+        happenings.send(Happening::CoinCollected);
+    }
+
+    for ended in run_ended.read() {
+        happenings.send(Happening::RunEnded {
+            distance: ended.distance,
+            cause: ended.cause,
+        });
+    }
+}
+
+fn track_run_progress(
+    time: Res<Time>,
+    distance: Res<TravelDistanceMeters>,
+    players: Query<&Transform, With<Player>>,
+    spikes: Query<&Transform, With<Spikes>>,
+    tuning: Res<Tuning>,
+    mut tracker: ResMut<HappeningTracker>,
+    mut happenings: EventWriter<Happening>,
+) {
+    let gained = (distance.0 - tracker.counted_distance).max(0.0);
+    tracker.counted_distance = tracker.counted_distance.max(distance.0);
+    happenings.send(Happening::Travelled {
+        distance: distance.0,
+        gained,
+    });
+
+    // The wall's spikes point out of its right-hand edge.
+    if let (Ok(player), Ok(spikes)) = (players.get_single(), spikes.get_single()) {
+        let wall_edge = spikes.translation.x + tuning.course_width / 2.0;
+        happenings.send(Happening::Survived {
+            seconds: time.delta_seconds(),
+            spike_gap: player.translation.x - wall_edge,
+        });
+    }
+}
+
+fn record_achievements(
+    mut happenings: EventReader<Happening>,
+    mut record: ResMut<AchievementRecord>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+    saves: Res<SaveDirectory>,
+) {
+    let mut changed = false;
+
+    for happening in happenings.read() {
+        for achievement in record.observe(*happening) {
+            info!("Achievement unlocked: {}", achievement.name);
+            unlocked.send(AchievementUnlocked(achievement));
+            changed = true;
+        }
+
+        // Progress is saved at the end of every run, unlocks straight away.
+        changed |= matches!(happening, Happening::RunEnded { .. });
+    }
+
+    if changed {
+        saves.store(ACHIEVEMENTS_FILE, &*record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(id: &str) -> &'static Achievement {
+        ACHIEVEMENTS
+            .iter()
+            .find(|achievement| achievement.id == id)
+            .unwrap()
+    }
+
+    #[test]
+    fn achievement_ids_are_unique() {
+        let ids: BTreeSet<_> = ACHIEVEMENTS
+            .iter()
+            .map(|achievement| achievement.id)
+            .collect();
+        assert_eq!(ids.len(), ACHIEVEMENTS.len());
+    }
+
+    #[test]
+    fn per_run_goals_start_over_and_lifetime_goals_carry_on() {
+        let mut record = AchievementRecord::default();
+
+        record.observe(Happening::RunStarted);
+        record.observe(Happening::Travelled {
+            distance: 800.0,
+            gained: 800.0,
+        });
+        record.observe(Happening::RunStarted);
+        let unlocked = record.observe(Happening::Travelled {
+            distance: 300.0,
+            gained: 300.0,
+        });
+
+        assert!(unlocked.is_empty());
+        assert_eq!(record.progress(find("kilometer")), 800.0);
+        assert_eq!(record.progress(find("marathon")), 1100.0);
+        assert_eq!(record.progress(find("regular")), 2.0);
+        assert!(record.is_unlocked(find("first_drop")));
+    }
+
+    #[test]
+    fn achievements_unlock_once() {
+        let mut record = AchievementRecord::default();
+
+        let first = record.observe(Happening::Travelled {
+            distance: 1000.0,
+            gained: 1000.0,
+        });
+        let second = record.observe(Happening::Travelled {
+            distance: 1200.0,
+            gained: 200.0,
+        });
+
+        assert_eq!(
+            first
+                .iter()
+                .map(|achievement| achievement.id)
+                .collect::<Vec<_>>(),
+            vec!["kilometer"]
+        );
+        assert!(second.is_empty());
+    }
+
+    #[test]
+    fn a_plain_landing_breaks_the_dive_streak() {
+        let mut record = AchievementRecord::default();
+
+        for _ in 0..9 {
+            record.observe(Happening::Landed { dived: true });
+        }
+        record.observe(Happening::Landed { dived: false });
+        for _ in 0..9 {
+            record.observe(Happening::Landed { dived: true });
+        }
+        assert!(!record.is_unlocked(find("diver")));
+
+        let unlocked = record.observe(Happening::Landed { dived: true });
+        assert_eq!(unlocked.len(), 1);
+        assert_eq!(unlocked[0].id, "diver");
+    }
+
+    #[test]
+    fn pulling_away_from_the_spikes_restarts_the_close_call_clock() {
+        let mut record = AchievementRecord::default();
+        let near = Happening::Survived {
+            seconds: 40.0,
+            spike_gap: 150.0,
+        };
+
+        record.observe(near);
+        record.observe(Happening::Survived {
+            seconds: 0.1,
+            spike_gap: 500.0,
+        });
+        record.observe(near);
+        assert!(!record.is_unlocked(find("close_call")));
+
+        record.observe(near);
+        assert!(record.is_unlocked(find("close_call")));
+    }
+}
@@ -0,0 +1,363 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{input::InputSystem, prelude::*, ui::UiSystem};
+use serde::{Deserialize, Serialize};
+
+use crate::save::{self, SaveDirectory};
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        let saves = save::directory(app);
+
+        app.insert_resource(saves.load::<ActionBindings>(BINDINGS_FILE).with_defaults())
+            .init_resource::<ActionState>()
+            .init_resource::<PendingRebind>()
+            .add_systems(
+                PreUpdate,
+                (
+                    capture_rebind
+                        .after(UiSystem::Focus)
+                        .run_if(|pending: Res<PendingRebind>| pending.0.is_some()),
+                    update_action_state,
+                )
+                    .chain()
+                    .in_set(ActionSet)
+                    .after(InputSystem),
+            );
+    }
+}
+
+/// Turns device input into [`ActionState`] each frame.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActionSet;
+
+const BINDINGS_FILE: &str = "bindings.ron";
+
+/// Things the player can do, independent of the device used to do them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Dive,
+    Pause,
+    Confirm,
+    Back,
+    /// Dive for the player on the left of a split screen.
+    LeftDive,
+    /// Dive for the player on the right of a split screen.
+    RightDive,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::Dive,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+        Action::LeftDive,
+        Action::RightDive,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+    /// A finger anywhere on the screen.
+    Touch,
+    /// A finger on the left half of the screen.
+    TouchLeft,
+    /// A finger on the right half of the screen.
+    TouchRight,
+}
+
+impl Binding {
+    fn same_device(&self, other: &Binding) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// Which bindings trigger each action. Saved whenever it is remapped.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct ActionBindings {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl Default for ActionBindings {
+    fn default() -> Self {
+        Self {
+            bindings: HashMap::from([
+                (
+                    Action::Dive,
+                    vec![
+                        Binding::Key(KeyCode::ArrowDown),
+                        Binding::Key(KeyCode::KeyS),
+                        Binding::Mouse(MouseButton::Left),
+                        Binding::Gamepad(GamepadButtonType::South),
+                        Binding::Touch,
+                    ],
+                ),
+                (
+                    Action::Pause,
+                    vec![
+                        Binding::Key(KeyCode::Escape),
+                        Binding::Gamepad(GamepadButtonType::Start),
+                    ],
+                ),
+                (
+                    Action::Confirm,
+                    vec![
+                        Binding::Key(KeyCode::Enter),
+                        Binding::Gamepad(GamepadButtonType::South),
+                    ],
+                ),
+                (
+                    Action::Back,
+                    vec![
+                        Binding::Key(KeyCode::Backspace),
+                        Binding::Gamepad(GamepadButtonType::East),
+                    ],
+                ),
+                (
+                    Action::LeftDive,
+                    vec![Binding::Key(KeyCode::KeyS), Binding::TouchLeft],
+                ),
+                (
+                    Action::RightDive,
+                    vec![Binding::Key(KeyCode::ArrowDown), Binding::TouchRight],
+                ),
+            ]),
+        }
+    }
+}
+
+impl ActionBindings {
+    /// Fills in actions added since the bindings were saved.
+    fn with_defaults(mut self) -> Self {
+        for (action, bindings) in ActionBindings::default().bindings {
+            self.bindings.entry(action).or_insert(bindings);
+        }
+        self
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Replaces the action's binding on the same kind of device, so rebinding
+    /// the keyboard key keeps the gamepad and touch bindings.
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.retain(|existing| !existing.same_device(&binding));
+        bindings.push(binding);
+    }
+
+    pub fn describe(&self, action: Action) -> String {
+        self.bindings(action)
+            .iter()
+            .map(|binding| match binding {
+                Binding::Key(key) => format!("{:?}", key),
+                Binding::Mouse(button) => format!("Mouse {:?}", button),
+                Binding::Gamepad(button) => format!("Pad {:?}", button),
+                Binding::Touch => "Touch".to_string(),
+                Binding::TouchLeft => "Touch left".to_string(),
+                Binding::TouchRight => "Touch right".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// The actions held, pressed or released this frame.
+#[derive(Resource, Debug, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    fn set(&mut self, action: Action, pressed: bool) {
+        let was_pressed = self.pressed.contains(&action);
+
+        if pressed && !was_pressed {
+            self.pressed.insert(action);
+            self.just_pressed.insert(action);
+        } else if !pressed && was_pressed {
+            self.pressed.remove(&action);
+            self.just_released.insert(action);
+        }
+    }
+}
+
+/// Set to an action to bind the next button pressed on any device to it.
+#[derive(Resource, Default)]
+pub struct PendingRebind(pub Option<Action>);
+
+fn binding_pressed(
+    binding: &Binding,
+    keys: &ButtonInput<KeyCode>,
+    mouse_buttons: &ButtonInput<MouseButton>,
+    gamepad_buttons: &ButtonInput<GamepadButton>,
+    gamepads: &Gamepads,
+    touches: &Touches,
+    screen_width: f32,
+) -> bool {
+    match binding {
+        Binding::Key(key) => keys.pressed(*key),
+        Binding::Mouse(button) => mouse_buttons.pressed(*button),
+        Binding::Gamepad(button_type) => gamepads
+            .iter()
+            .any(|gamepad| gamepad_buttons.pressed(GamepadButton::new(gamepad, *button_type))),
+        Binding::Touch => touches.iter().next().is_some(),
+        Binding::TouchLeft => touches
+            .iter()
+            .any(|touch| touch.position().x < screen_width / 2.0),
+        Binding::TouchRight => touches
+            .iter()
+            .any(|touch| touch.position().x >= screen_width / 2.0),
+    }
+}
+
+fn update_action_state(
+    bindings: Res<ActionBindings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    touches: Res<Touches>,
+    windows: Query<&Window>,
+    mut state: ResMut<ActionState>,
+) {
+    state.just_pressed.clear();
+    state.just_released.clear();
+
+    let screen_width = windows.get_single().map_or(0.0, |window| window.width());
+
+    for action in Action::ALL {
+        let pressed = bindings.bindings(action).iter().any(|binding| {
+            binding_pressed(
+                binding,
+                &keys,
+                &mouse_buttons,
+                &gamepad_buttons,
+                &gamepads,
+                &touches,
+                screen_width,
+            )
+        });
+        state.set(action, pressed);
+    }
+}
+
+fn capture_rebind(
+    mut pending: ResMut<PendingRebind>,
+    mut bindings: ResMut<ActionBindings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    interactions: Query<&Interaction>,
+    saves: Res<SaveDirectory>,
+) {
+    let Some(action) = pending.0 else {
+        return;
+    };
+
+    // A click on a menu button is meant for the button, not as a binding.
+    let clicked_ui = interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+
+    let binding = keys
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+            mouse_buttons
+                .get_just_pressed()
+                .find(|_| !clicked_ui)
+                .map(|button| Binding::Mouse(*button))
+        })
+        .or_else(|| {
+            gamepad_buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Gamepad(button.button_type))
+        });
+
+    if let Some(binding) = binding {
+        bindings.rebind(action, binding);
+        pending.0 = None;
+        saves.store(BINDINGS_FILE, &*bindings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rebinding(action: Action) -> App {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<ButtonInput<MouseButton>>()
+            .init_resource::<ButtonInput<GamepadButton>>()
+            .init_resource::<ActionBindings>()
+            .insert_resource(PendingRebind(Some(action)))
+            .add_systems(Update, capture_rebind);
+        app
+    }
+
+    fn click(app: &mut App, button: MouseButton) {
+        app.world_mut()
+            .resource_mut::<ButtonInput<MouseButton>>()
+            .press(button);
+        app.update();
+    }
+
+    #[test]
+    fn clicking_a_menu_button_does_not_bind_the_mouse() {
+        let mut app = rebinding(Action::Pause);
+        app.world_mut().spawn(Interaction::Pressed);
+
+        click(&mut app, MouseButton::Left);
+
+        assert_eq!(
+            app.world().resource::<PendingRebind>().0,
+            Some(Action::Pause)
+        );
+        assert_eq!(
+            app.world()
+                .resource::<ActionBindings>()
+                .bindings(Action::Pause),
+            ActionBindings::default().bindings(Action::Pause)
+        );
+    }
+
+    #[test]
+    fn a_click_away_from_the_menu_is_bound() {
+        let mut app = rebinding(Action::Pause);
+        app.world_mut().spawn(Interaction::Hovered);
+
+        click(&mut app, MouseButton::Right);
+
+        assert_eq!(app.world().resource::<PendingRebind>().0, None);
+        assert!(app
+            .world()
+            .resource::<ActionBindings>()
+            .bindings(Action::Pause)
+            .contains(&Binding::Mouse(MouseButton::Right)));
+    }
+}
@@ -0,0 +1,109 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    editor::EditedRun, online::OnlineRace, player::Player, replay::ReplayPlayback,
+    save::SaveDirectory, versus::Versus, wallet::Wallet, GameState,
+};
+
+/// Starting boosts bought in the shop: a push forward as the ball drops.
+pub struct BoostPlugin;
+
+impl Plugin for BoostPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UseBoosts>()
+            .init_resource::<RunBoosted>()
+            .add_systems(OnEnter(GameState::Playing), boost_run);
+    }
+}
+
+/// Speed a boost starts the ball off with, in pixels per second.
+const BOOST_SPEED: f32 = 400.0;
+
+/// Whether runs start with a boost while there are any left. Toggled from
+/// the main menu.
+#[derive(Resource, Debug, Default)]
+pub struct UseBoosts(pub bool);
+
+/// Whether the current run started with a boost, so its replay can too.
+#[derive(Resource, Debug, Default)]
+pub struct RunBoosted(pub bool);
+
+#[allow(clippy::too_many_arguments)]
+fn boost_run(
+    playback: Option<Res<ReplayPlayback>>,
+    versus: Option<Res<Versus>>,
+    online_race: Option<Res<OnlineRace>>,
+    edited_run: Option<Res<EditedRun>>,
+    use_boosts: Res<UseBoosts>,
+    mut wallet: ResMut<Wallet>,
+    saves: Res<SaveDirectory>,
+    mut boosted: ResMut<RunBoosted>,
+    players: Query<Entity, With<Player>>,
+    mut commands: Commands,
+) {
+    boosted.0 = match playback {
+        Some(playback) => playback.replay.boosted,
+        // Bought boosts would be an unfair edge against other players.
+        None => {
+            use_boosts.0
+                && versus.is_none()
+                && online_race.is_none()
+                && edited_run.is_none()
+                && wallet.use_boost(&saves)
+        }
+    };
+
+    if boosted.0 {
+        for player in players.iter() {
+            commands
+                .entity(player)
+                .insert(LinearVelocity(Vec2::new(BOOST_SPEED, 0.0)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        events::RunEnded,
+        harness::{GameHarness, SPIKES_CATCH_IDLE_PLAYER_SECONDS},
+        player::PlayerState,
+        replay::RunRecorded,
+        tuning::tuning_settled,
+        verify::verify_replay,
+        wallet::Item,
+    };
+
+    #[test]
+    fn a_boost_starts_the_run_moving_and_its_replay_verifies() {
+        let mut harness = GameHarness::new();
+        harness.record::<RunRecorded>();
+        harness.run_until(5.0, |harness| tuning_settled(harness.world()));
+
+        let saves = harness.world().resource::<SaveDirectory>().clone();
+        harness
+            .world_mut()
+            .resource_mut::<Wallet>()
+            .buy(&Item::Boost, 0, &saves)
+            .unwrap();
+        harness.world_mut().resource_mut::<UseBoosts>().0 = true;
+
+        harness.start_run();
+        let player = harness.player().unwrap();
+        let velocity = harness.world().get::<LinearVelocity>(player).unwrap();
+        assert!(velocity.x > 100.0, "{:?}", velocity);
+        assert_eq!(harness.world().resource::<Wallet>().boosts(), 0);
+
+        let died = harness.run_until(SPIKES_CATCH_IDLE_PLAYER_SECONDS, |harness| {
+            harness.player_state() == PlayerState::Dead
+        });
+        assert!(died);
+
+        let replay = harness.recorded::<RunRecorded>()[0].0.clone();
+        assert!(replay.boosted);
+        let outcome = verify_replay(&replay.encode()).unwrap();
+        assert_eq!(outcome.distance, harness.recorded::<RunEnded>()[0].distance);
+    }
+}
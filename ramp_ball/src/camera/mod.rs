@@ -0,0 +1,292 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::player::{Player, Seat};
+
+pub struct GameCameraPlugin;
+
+impl Plugin for GameCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraSettings>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(Update, camera_follow_player.in_set(CameraFollowSet));
+    }
+}
+
+#[derive(Component)]
+pub struct Camera;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CameraFollowSet;
+
+/// Tuning for how the camera chases the ball.
+///
+/// Damping values are response rates per second: higher values catch up
+/// faster.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct CameraSettings {
+    pub horizontal_damping: f32,
+    pub vertical_damping: f32,
+    pub zoom_damping: f32,
+    /// Seconds of horizontal velocity the camera leads the ball by.
+    pub look_ahead_time: f32,
+    pub max_look_ahead: f32,
+    /// Half-height of the band around the camera centre in which the ball can
+    /// move vertically without the camera following.
+    pub vertical_dead_zone: f32,
+    /// Height treated as altitude zero when zooming.
+    pub ground_height: f32,
+    pub zoom_per_speed: f32,
+    pub zoom_per_altitude: f32,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            horizontal_damping: 6.0,
+            vertical_damping: 3.0,
+            zoom_damping: 1.5,
+            look_ahead_time: 0.4,
+            max_look_ahead: 400.0,
+            vertical_dead_zone: 150.0,
+            ground_height: 0.0,
+            zoom_per_speed: 0.0005,
+            zoom_per_altitude: 0.001,
+            min_zoom: 1.0,
+            max_zoom: 2.5,
+        }
+    }
+}
+
+/// Where the camera is looking and how far it is zoomed out.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct CameraRig {
+    pub focus: Vec2,
+    pub zoom: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            focus: Vec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
+/// Fraction of the remaining distance covered in `delta_seconds` when easing
+/// at `rate` per second. Independent of frame rate.
+fn smoothing(rate: f32, delta_seconds: f32) -> f32 {
+    1.0 - (-rate * delta_seconds).exp()
+}
+
+/// Advances the rig one frame towards a target moving at `velocity`.
+pub fn step_camera_rig(
+    settings: &CameraSettings,
+    rig: CameraRig,
+    target: Vec2,
+    velocity: Vec2,
+    delta_seconds: f32,
+) -> CameraRig {
+    let look_ahead = (velocity.x * settings.look_ahead_time)
+        .clamp(-settings.max_look_ahead, settings.max_look_ahead);
+    let desired_x = target.x + look_ahead;
+
+    let vertical_offset = target.y - rig.focus.y;
+    let desired_y = if vertical_offset.abs() > settings.vertical_dead_zone {
+        target.y - vertical_offset.signum() * settings.vertical_dead_zone
+    } else {
+        rig.focus.y
+    };
+
+    let altitude = (target.y - settings.ground_height).max(0.0);
+    let desired_zoom =
+        (1.0 + velocity.length() * settings.zoom_per_speed + altitude * settings.zoom_per_altitude)
+            .clamp(settings.min_zoom, settings.max_zoom);
+
+    CameraRig {
+        focus: Vec2::new(
+            rig.focus.x
+                + (desired_x - rig.focus.x) * smoothing(settings.horizontal_damping, delta_seconds),
+            rig.focus.y
+                + (desired_y - rig.focus.y) * smoothing(settings.vertical_damping, delta_seconds),
+        ),
+        zoom: rig.zoom
+            + (desired_zoom - rig.zoom) * smoothing(settings.zoom_damping, delta_seconds),
+    }
+}
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera,
+        Seat::One,
+        CameraRig::default(),
+        Camera2dBundle::default(),
+    ));
+}
+
+fn camera_follow_player(
+    settings: Res<CameraSettings>,
+    time: Res<Time>,
+    mut camera_query: Query<
+        (
+            &Seat,
+            &mut CameraRig,
+            &mut Transform,
+            &mut OrthographicProjection,
+        ),
+        With<Camera>,
+    >,
+    player_query: Query<
+        (&Seat, &Transform, Option<&LinearVelocity>),
+        (With<Player>, Without<Camera>),
+    >,
+) {
+    for (camera_seat, mut rig, mut camera_transform, mut projection) in camera_query.iter_mut() {
+        let Some((_, player_transform, velocity)) = player_query
+            .iter()
+            .find(|(player_seat, _, _)| *player_seat == camera_seat)
+        else {
+            continue;
+        };
+
+        *rig = step_camera_rig(
+            &settings,
+            *rig,
+            player_transform.translation.truncate(),
+            velocity.map_or(Vec2::ZERO, |velocity| velocity.0),
+            time.delta_seconds(),
+        );
+
+        camera_transform.translation = rig.focus.extend(5.0);
+        projection.scale = rig.zoom;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    /// Runs the rig along a synthetic trajectory sampled once per frame.
+    fn follow(
+        settings: &CameraSettings,
+        frames: usize,
+        trajectory: impl Fn(f32) -> (Vec2, Vec2),
+    ) -> Vec<(CameraRig, Vec2)> {
+        let mut rig = CameraRig::default();
+
+        (0..frames)
+            .map(|frame| {
+                let (target, velocity) = trajectory(frame as f32 * FRAME);
+                rig = step_camera_rig(settings, rig, target, velocity, FRAME);
+                (rig, target)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn leads_the_ball_in_its_direction_of_travel() {
+        let settings = CameraSettings::default();
+        let velocity = Vec2::new(300.0, 0.0);
+
+        let (rig, target) = *follow(&settings, 600, |time| (velocity * time, velocity))
+            .last()
+            .unwrap();
+
+        let expected = velocity.x * settings.look_ahead_time;
+        assert!(rig.focus.x > target.x);
+        assert!((rig.focus.x - target.x - expected).abs() < 60.0);
+
+        let (rig, target) = *follow(&settings, 600, |time| (-velocity * time, -velocity))
+            .last()
+            .unwrap();
+        assert!(rig.focus.x < target.x);
+    }
+
+    #[test]
+    fn look_ahead_is_capped() {
+        let settings = CameraSettings::default();
+        let velocity = Vec2::new(5000.0, 0.0);
+
+        let (rig, target) = *follow(&settings, 600, |time| (velocity * time, velocity))
+            .last()
+            .unwrap();
+
+        assert!(rig.focus.x - target.x <= settings.max_look_ahead + 1.0);
+    }
+
+    #[test]
+    fn does_not_snap_to_sudden_moves() {
+        let settings = CameraSettings::default();
+
+        let rig = step_camera_rig(
+            &settings,
+            CameraRig::default(),
+            Vec2::new(1000.0, 0.0),
+            Vec2::ZERO,
+            FRAME,
+        );
+
+        assert!(rig.focus.x > 0.0);
+        assert!(rig.focus.x < 1000.0);
+    }
+
+    #[test]
+    fn ignores_vertical_bobbing_inside_the_dead_zone() {
+        let settings = CameraSettings::default();
+        let amplitude = settings.vertical_dead_zone * 0.8;
+
+        let samples = follow(&settings, 600, |time| {
+            (
+                Vec2::new(0.0, amplitude * (time * 4.0).sin()),
+                Vec2::new(0.0, amplitude * 4.0 * (time * 4.0).cos()),
+            )
+        });
+
+        assert!(samples.iter().all(|(rig, _)| rig.focus.y == 0.0));
+    }
+
+    #[test]
+    fn follows_big_jumps_vertically() {
+        let settings = CameraSettings::default();
+
+        let samples = follow(&settings, 600, |time| {
+            let height = 1500.0 * (time * 0.5).min(1.0);
+            (Vec2::new(0.0, height), Vec2::ZERO)
+        });
+
+        let (rig, target) = samples.last().unwrap();
+        assert!((target.y - rig.focus.y).abs() <= settings.vertical_dead_zone + 1.0);
+    }
+
+    #[test]
+    fn zooms_out_with_speed_and_altitude() {
+        let settings = CameraSettings::default();
+
+        let resting = follow(&settings, 600, |_| (Vec2::ZERO, Vec2::ZERO));
+        let fast = follow(&settings, 600, |_| (Vec2::ZERO, Vec2::new(1200.0, 0.0)));
+        let high = follow(&settings, 600, |_| (Vec2::new(0.0, 800.0), Vec2::ZERO));
+
+        let resting_zoom = resting.last().unwrap().0.zoom;
+        assert!((resting_zoom - settings.min_zoom).abs() < 1e-3);
+        assert!(fast.last().unwrap().0.zoom > resting_zoom);
+        assert!(high.last().unwrap().0.zoom > resting_zoom);
+    }
+
+    #[test]
+    fn zoom_is_clamped() {
+        let settings = CameraSettings::default();
+
+        let samples = follow(&settings, 1200, |_| {
+            (Vec2::new(0.0, 100_000.0), Vec2::new(100_000.0, 0.0))
+        });
+
+        assert!(samples
+            .iter()
+            .all(|(rig, _)| rig.zoom <= settings.max_zoom + 1e-3));
+    }
+}
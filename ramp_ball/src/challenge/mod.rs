@@ -0,0 +1,465 @@
+use std::{fmt, str::FromStr};
+
+use arboard::Clipboard;
+use bevy::prelude::*;
+
+use crate::{
+    game_mode::{GameMode, SelectGameMode},
+    seed::CourseSeed,
+    GameState,
+};
+
+/// Challenge codes: short text codes that carry a course and a distance to
+/// beat, so a friend can play the same course with the same target.
+pub struct ChallengePlugin;
+
+impl Plugin for ChallengePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_non_send_resource(SystemClipboard::default())
+            .add_event::<PlayChallenge>()
+            .add_event::<EndChallenge>()
+            .add_systems(
+                Update,
+                (start_challenge, end_challenge, hold_challenge_seed)
+                    .chain()
+                    .run_if(in_state(GameState::MainMenu)),
+            );
+    }
+}
+
+/// Bumped whenever the layout of a code changes.
+const CODE_VERSION: u8 = 1;
+
+/// Version, game version, mode, seed, target and checksum.
+const CODE_LENGTH: usize = 1 + 3 + 1 + 8 + 2 + 2;
+
+/// Crockford's base32, which leaves out letters that look like digits.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+const GROUP_LENGTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Challenge {
+    /// Major, minor and patch version of the game the code was made with.
+    pub game_version: [u8; 3],
+    pub mode: GameMode,
+    pub seed: u64,
+    /// Distance to beat, in meters.
+    pub target: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChallengeCodeError {
+    InvalidCharacter(char),
+    TooShort,
+    ChecksumMismatch,
+    UnsupportedVersion(u8),
+    WrongLength,
+    UnknownMode(u8),
+}
+
+impl fmt::Display for ChallengeCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChallengeCodeError::InvalidCharacter(character) => {
+                write!(f, "'{}' can't be part of a challenge code", character)
+            }
+            ChallengeCodeError::TooShort => write!(f, "the code is too short"),
+            ChallengeCodeError::ChecksumMismatch => {
+                write!(f, "the code has a typo or was cut off")
+            }
+            ChallengeCodeError::UnsupportedVersion(version) => write!(
+                f,
+                "the code is version {}, this game reads version {}",
+                version, CODE_VERSION
+            ),
+            ChallengeCodeError::WrongLength => write!(f, "the code is the wrong length"),
+            ChallengeCodeError::UnknownMode(mode) => write!(f, "unknown game mode {}", mode),
+        }
+    }
+}
+
+impl Challenge {
+    /// A challenge to beat `target` meters on the current course, made with
+    /// this version of the game.
+    pub fn new(mode: GameMode, seed: CourseSeed, target: f32) -> Self {
+        Self {
+            game_version: game_version(),
+            mode,
+            seed: seed.0,
+            target: target.clamp(0.0, u16::MAX as f32) as u16,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mode_index = GameMode::ALL
+            .iter()
+            .position(|mode| *mode == self.mode)
+            .unwrap_or(0);
+
+        let mut bytes = Vec::with_capacity(CODE_LENGTH);
+        bytes.push(CODE_VERSION);
+        bytes.extend_from_slice(&self.game_version);
+        bytes.push(mode_index as u8);
+        bytes.extend_from_slice(&self.seed.to_be_bytes());
+        bytes.extend_from_slice(&self.target.to_be_bytes());
+        bytes.extend_from_slice(&checksum(&bytes).to_be_bytes());
+
+        let characters = to_base32(&bytes);
+        characters
+            .chunks(GROUP_LENGTH)
+            .map(|group| group.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    /// Whether the code was made with a different version of the game, whose
+    /// courses may not match this one's.
+    pub fn from_other_version(&self) -> bool {
+        self.game_version != game_version()
+    }
+}
+
+impl FromStr for Challenge {
+    type Err = ChallengeCodeError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let bytes = from_base32(code)?;
+
+        let Some((payload, sum)) = bytes.split_last_chunk::<2>() else {
+            return Err(ChallengeCodeError::TooShort);
+        };
+        if payload.is_empty() {
+            return Err(ChallengeCodeError::TooShort);
+        }
+        if checksum(payload) != u16::from_be_bytes(*sum) {
+            return Err(ChallengeCodeError::ChecksumMismatch);
+        }
+
+        if payload[0] != CODE_VERSION {
+            return Err(ChallengeCodeError::UnsupportedVersion(payload[0]));
+        }
+        if bytes.len() != CODE_LENGTH {
+            return Err(ChallengeCodeError::WrongLength);
+        }
+
+        let mode = GameMode::ALL
+            .get(payload[4] as usize)
+            .copied()
+            .ok_or(ChallengeCodeError::UnknownMode(payload[4]))?;
+
+        Ok(Self {
+            game_version: [payload[1], payload[2], payload[3]],
+            mode,
+            seed: u64::from_be_bytes(payload[5..13].try_into().unwrap()),
+            target: u16::from_be_bytes(payload[13..15].try_into().unwrap()),
+        })
+    }
+}
+
+fn game_version() -> [u8; 3] {
+    let mut parts = env!("CARGO_PKG_VERSION")
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0));
+    [
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+    ]
+}
+
+/// CRC-16/CCITT-FALSE.
+fn checksum(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+fn to_base32(bytes: &[u8]) -> Vec<char> {
+    let mut characters = Vec::with_capacity((bytes.len() * 8).div_ceil(5));
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            characters.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        characters.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+
+    characters
+}
+
+/// Reads base32 leniently: any case, dashes and spaces between groups, and
+/// letters people mistake for digits.
+fn from_base32(code: &str) -> Result<Vec<u8>, ChallengeCodeError> {
+    let mut bytes = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for character in code.chars() {
+        if character == '-' || character.is_whitespace() {
+            continue;
+        }
+
+        let normalized = match character.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            other => other,
+        };
+        let value = ALPHABET
+            .iter()
+            .position(|symbol| *symbol as char == normalized)
+            .ok_or(ChallengeCodeError::InvalidCharacter(character))?;
+
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+
+    // Encoding pads the last character with zeros, so anything else there
+    // is a typo the checksum can't see.
+    if buffer & ((1 << bits) - 1) != 0 {
+        return Err(ChallengeCodeError::ChecksumMismatch);
+    }
+
+    Ok(bytes)
+}
+
+/// The system clipboard, opened on first use and then kept open. On Linux the
+/// copied text is served by the `Clipboard` that set it, so it's gone as soon
+/// as that is dropped.
+#[derive(Default)]
+pub struct SystemClipboard(Option<Clipboard>);
+
+impl SystemClipboard {
+    pub fn copy(&mut self, text: &str) -> Result<(), String> {
+        self.open()?
+            .set_text(text)
+            .map_err(|error| error.to_string())
+    }
+
+    pub fn paste(&mut self) -> Result<String, String> {
+        self.open()?.get_text().map_err(|error| error.to_string())
+    }
+
+    fn open(&mut self) -> Result<&mut Clipboard, String> {
+        let clipboard = match self.0.take() {
+            Some(clipboard) => clipboard,
+            None => Clipboard::new().map_err(|error| error.to_string())?,
+        };
+        Ok(self.0.insert(clipboard))
+    }
+}
+
+/// Sent by the challenge screen to play a friend's code.
+#[derive(Event)]
+pub struct PlayChallenge(pub Challenge);
+
+/// Sent by the challenge screen to go back to playing without a target.
+#[derive(Event)]
+pub struct EndChallenge;
+
+/// Present while a challenge is being played, from the main menu on.
+#[derive(Resource, Debug)]
+pub struct ActiveChallenge {
+    pub challenge: Challenge,
+    previous_seed: CourseSeed,
+}
+
+/// Switches to the challenge's mode and course. Daily challenges are played
+/// as endless runs on that day's seed, since the daily mode always picks
+/// today's.
+fn start_challenge(
+    mut events: EventReader<PlayChallenge>,
+    active: Option<Res<ActiveChallenge>>,
+    mut mode: ResMut<GameMode>,
+    seed: Res<CourseSeed>,
+    mut commands: Commands,
+) {
+    let Some(PlayChallenge(challenge)) = events.read().last() else {
+        return;
+    };
+
+    let challenge_mode = match challenge.mode {
+        GameMode::Daily => GameMode::Endless,
+        other => other,
+    };
+    if *mode != challenge_mode {
+        *mode = challenge_mode;
+    }
+
+    commands.insert_resource(ActiveChallenge {
+        challenge: *challenge,
+        previous_seed: active.map_or(*seed, |active| active.previous_seed),
+    });
+}
+
+/// Ends the challenge when asked to, or when another mode is picked.
+fn end_challenge(
+    mut end_events: EventReader<EndChallenge>,
+    mut mode_events: EventReader<SelectGameMode>,
+    active: Option<Res<ActiveChallenge>>,
+    mode: Res<GameMode>,
+    mut seed: ResMut<CourseSeed>,
+    mut commands: Commands,
+) {
+    let ended = end_events.read().count() > 0;
+    let selected = mode_events.read().last().map(|SelectGameMode(mode)| *mode);
+
+    let Some(active) = active else {
+        return;
+    };
+    if !ended && selected.is_none() {
+        return;
+    }
+
+    // The daily mode sets its own seed.
+    if selected.unwrap_or(*mode) != GameMode::Daily {
+        *seed = active.previous_seed;
+    }
+    commands.remove_resource::<ActiveChallenge>();
+}
+
+/// Keeps the challenge's course, whatever else sets the seed in the menu.
+fn hold_challenge_seed(active: Option<Res<ActiveChallenge>>, mut seed: ResMut<CourseSeed>) {
+    if let Some(active) = active {
+        let challenge_seed = CourseSeed(active.challenge.seed);
+        if *seed != challenge_seed {
+            *seed = challenge_seed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::GameHarness;
+
+    fn challenge() -> Challenge {
+        Challenge {
+            game_version: [0, 1, 0],
+            mode: GameMode::Daily,
+            seed: 0xDEAD_BEEF_0123_4567,
+            target: 1234,
+        }
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for mode in GameMode::ALL {
+            let challenge = Challenge {
+                mode,
+                ..challenge()
+            };
+            let code = challenge.encode();
+
+            assert_eq!(code.parse::<Challenge>(), Ok(challenge));
+        }
+    }
+
+    #[test]
+    fn codes_are_read_in_any_case_and_with_lookalike_letters() {
+        let code = challenge().encode();
+        let sloppy = code.to_lowercase().replace('0', "o").replace('1', "l");
+
+        assert_eq!(sloppy.parse::<Challenge>(), Ok(challenge()));
+        assert_eq!(code.replace('-', " ").parse::<Challenge>(), Ok(challenge()));
+    }
+
+    #[test]
+    fn every_single_character_typo_is_caught() {
+        let code = challenge().encode();
+
+        for (position, original) in code.char_indices().filter(|(_, c)| *c != '-') {
+            for replacement in ALPHABET.iter().map(|symbol| *symbol as char) {
+                if replacement == original {
+                    continue;
+                }
+                let mut corrupted = code.clone();
+                corrupted.replace_range(position..position + 1, &replacement.to_string());
+
+                assert!(
+                    corrupted.parse::<Challenge>().is_err(),
+                    "{} was accepted",
+                    corrupted
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_and_malformed_codes_are_rejected() {
+        let code = challenge().encode();
+
+        assert!(code[..code.len() - 4].parse::<Challenge>().is_err());
+        assert_eq!("".parse::<Challenge>(), Err(ChallengeCodeError::TooShort));
+        assert_eq!(
+            "ABC!".parse::<Challenge>(),
+            Err(ChallengeCodeError::InvalidCharacter('!'))
+        );
+    }
+
+    #[test]
+    fn codes_from_newer_versions_are_rejected() {
+        let mut bytes = vec![CODE_VERSION + 1, 0, 1, 0, 0];
+        bytes.extend_from_slice(&checksum(&bytes).to_be_bytes());
+        let code: String = to_base32(&bytes).into_iter().collect();
+
+        assert_eq!(
+            code.parse::<Challenge>(),
+            Err(ChallengeCodeError::UnsupportedVersion(CODE_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn a_challenge_code_sets_the_course_until_it_is_cleared() {
+        let mut harness = GameHarness::new();
+        let own_seed = *harness.world().resource::<CourseSeed>();
+
+        let code = Challenge::new(GameMode::Endless, CourseSeed(42), 300.0).encode();
+        let challenge = code.parse::<Challenge>().unwrap();
+        harness.world_mut().send_event(PlayChallenge(challenge));
+        harness.run_ticks(2);
+
+        assert_eq!(*harness.world().resource::<CourseSeed>(), CourseSeed(42));
+        assert_eq!(
+            harness
+                .world()
+                .resource::<ActiveChallenge>()
+                .challenge
+                .target,
+            300
+        );
+
+        // The course survives a run and coming back to the menu.
+        harness.start_run();
+        harness.teleport_player(Vec2::new(0.0, -2000.0));
+        harness.tick();
+        harness.continue_after_death();
+        assert_eq!(*harness.world().resource::<CourseSeed>(), CourseSeed(42));
+
+        harness.world_mut().send_event(EndChallenge);
+        harness.run_ticks(2);
+
+        assert!(!harness.world().contains_resource::<ActiveChallenge>());
+        assert_eq!(*harness.world().resource::<CourseSeed>(), own_seed);
+    }
+}
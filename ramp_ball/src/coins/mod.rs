@@ -0,0 +1,160 @@
+use avian2d::prelude::*;
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    contacts::{pickup_layers, ContactsSet, PickupContact},
+    events::{CoinCollected, RunStarted},
+    platforms::Platform,
+    player::PlayerState,
+    seed::{CourseSeed, SplitMix64},
+    GameState,
+};
+
+/// Coins hovering over the course, picked up by rolling or flying through
+/// them. Each platform's coins are laid out from the course seed, so a course
+/// always has the same ones.
+pub struct CoinsPlugin;
+
+impl Plugin for CoinsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunCoins>()
+            .add_systems(
+                Update,
+                (
+                    place_coins.run_if(not(in_state(GameState::Editor))),
+                    reset_run_coins,
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                collect_coins
+                    .after(ContactsSet)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Alive)),
+            );
+    }
+}
+
+const COIN_RADIUS: f32 = 20.0;
+
+/// Height of a coin above the platform point it hovers over.
+const COIN_HEIGHT: f32 = 70.0;
+
+/// Chance of a coin over each of a platform's points after the first.
+const COIN_CHANCE: f32 = 0.6;
+
+#[derive(Component)]
+pub struct Coin;
+
+/// Coins picked up in the current run, or the last one once it has ended.
+#[derive(Resource, Debug, Default)]
+pub struct RunCoins(pub u32);
+
+fn place_coins(
+    seed: Res<CourseSeed>,
+    platforms: Query<(Entity, &Platform), Added<Platform>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if platforms.is_empty() {
+        return;
+    }
+
+    let mesh = Mesh2dHandle(meshes.add(Circle::new(COIN_RADIUS)));
+    let material = materials.add(Color::hsl(50.0, 1.0, 0.5));
+
+    for (entity, platform) in platforms.iter() {
+        let mut rng = SplitMix64::new(seed.0 ^ platform.index.wrapping_mul(0xC01C_0FFE_E000_0001));
+
+        commands.entity(entity).with_children(|parent| {
+            for point in platform.points.iter().skip(1) {
+                if rng.range(0.0, 1.0) >= COIN_CHANCE {
+                    continue;
+                }
+
+                parent.spawn((
+                    Coin,
+                    Collider::circle(COIN_RADIUS),
+                    Sensor,
+                    pickup_layers(),
+                    MaterialMesh2dBundle {
+                        mesh: mesh.clone(),
+                        material: material.clone(),
+                        transform: Transform::from_xyz(point.x, point.y + COIN_HEIGHT, 1.0),
+                        ..default()
+                    },
+                ));
+            }
+        });
+    }
+}
+
+fn reset_run_coins(mut run_started: EventReader<RunStarted>, mut coins: ResMut<RunCoins>) {
+    if run_started.read().count() > 0 {
+        coins.0 = 0;
+    }
+}
+
+fn collect_coins(
+    mut pickup_contacts: EventReader<PickupContact>,
+    coins: Query<(), With<Coin>>,
+    mut run_coins: ResMut<RunCoins>,
+    mut commands: Commands,
+    mut coin_collected: EventWriter<CoinCollected>,
+) {
+    let mut collected = Vec::new();
+
+    for contact in pickup_contacts.read() {
+        // Two balls can reach the same coin on one tick.
+        if !coins.contains(contact.pickup) || collected.contains(&contact.pickup) {
+            continue;
+        }
+        collected.push(contact.pickup);
+
+        commands.entity(contact.pickup).despawn_recursive();
+        run_coins.0 += 1;
+        coin_collected.send(CoinCollected);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{harness::GameHarness, wallet::Wallet};
+
+    #[test]
+    fn rolling_through_a_coin_picks_it_up_into_the_wallet() {
+        let mut harness = GameHarness::new();
+        let banked = harness.world().resource::<Wallet>().coins();
+
+        harness.start_run();
+        let coin = harness
+            .world_mut()
+            .query_filtered::<&GlobalTransform, With<Coin>>()
+            .iter(harness.world())
+            .map(|transform| transform.translation().truncate())
+            .next()
+            .expect("the course has coins");
+        let coins_before = harness
+            .world_mut()
+            .query::<&Coin>()
+            .iter(harness.world())
+            .count();
+
+        harness.teleport_player(coin);
+        harness.run_ticks(3);
+
+        assert_eq!(harness.world().resource::<RunCoins>().0, 1);
+        let coins_after = harness
+            .world_mut()
+            .query::<&Coin>()
+            .iter(harness.world())
+            .count();
+        assert_eq!(coins_after, coins_before - 1);
+        assert_eq!(harness.world().resource::<Wallet>().coins(), banked + 1);
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    ecs::system::SystemId,
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState, InputSystem,
+    },
+    prelude::*,
+};
+use bevy_bsml::prelude::*;
+
+use crate::{actions::ActionSet, player::PlayerState, seed::CourseSeed, GameState};
+
+/// A text console for cheats and debugging, only built with the `dev`
+/// feature. Backquote opens and closes it.
+///
+/// Plugins add their own commands with [`AddConsoleCommand`].
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConsoleCommands>()
+            .init_resource::<Console>()
+            .add_systems(
+                PreUpdate,
+                swallow_keyboard
+                    .run_if(console_open)
+                    .after(InputSystem)
+                    .before(ActionSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_console_keys,
+                    run_submitted_commands,
+                    show_console.run_if(resource_changed::<Console>),
+                )
+                    .chain(),
+            )
+            .add_console_command("help", "help", help)
+            .add_console_command("state", "state <mainmenu|playing|dead>", set_state)
+            .add_console_command("seed", "seed <n>", set_seed);
+    }
+}
+
+const TOGGLE_KEY: KeyCode = KeyCode::Backquote;
+const LOG_LINES: usize = 12;
+
+/// What a command prints back: a confirmation or an error.
+pub type ConsoleResult = Result<String, String>;
+
+struct ConsoleCommand {
+    usage: &'static str,
+    system: SystemId<Vec<String>, ConsoleResult>,
+}
+
+#[derive(Resource, Default)]
+struct ConsoleCommands {
+    commands: BTreeMap<&'static str, ConsoleCommand>,
+}
+
+pub trait AddConsoleCommand {
+    /// Registers a console command. The system gets the words typed after
+    /// the command name.
+    fn add_console_command<M>(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        system: impl IntoSystem<Vec<String>, ConsoleResult, M> + 'static,
+    ) -> &mut Self;
+}
+
+impl AddConsoleCommand for App {
+    fn add_console_command<M>(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        system: impl IntoSystem<Vec<String>, ConsoleResult, M> + 'static,
+    ) -> &mut Self {
+        let system = self.world_mut().register_system(system);
+        self.world_mut()
+            .get_resource_or_insert_with(ConsoleCommands::default)
+            .commands
+            .insert(name, ConsoleCommand { usage, system });
+        self
+    }
+}
+
+#[derive(Resource, Default)]
+struct Console {
+    open: bool,
+    input: String,
+    submitted: Vec<String>,
+    log: Vec<String>,
+}
+
+#[derive(Component)]
+struct ConsoleOverlay {
+    log: String,
+    input: String,
+}
+
+bsml! {ConsoleOverlay;
+    (node class=[W_FULL, FLEX_COL, BG_GRAY_500]) {
+        (text class=[FontSize::px(18.0)]) { "{}", self.log }
+        (text class=[FontSize::px(18.0)]) { "> {}_", self.input }
+    }
+}
+
+fn console_open(console: Res<Console>) -> bool {
+    console.open
+}
+
+/// Keeps typing from also diving, pausing or confirming menus.
+fn swallow_keyboard(mut keys: ResMut<ButtonInput<KeyCode>>) {
+    keys.reset_all();
+}
+
+fn handle_console_keys(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut console: ResMut<Console>,
+) {
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        if event.key_code == TOGGLE_KEY {
+            console.open = !console.open;
+            continue;
+        }
+
+        if !console.open {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input);
+                console.submitted.push(line);
+            }
+            Key::Escape => console.open = false,
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::Space => console.input.push(' '),
+            Key::Character(text) => console.input.push_str(text),
+            _ => {}
+        }
+    }
+}
+
+fn run_submitted_commands(world: &mut World) {
+    let submitted = std::mem::take(&mut world.resource_mut::<Console>().submitted);
+
+    for line in submitted {
+        let output = match run_command_line(world, &line) {
+            Ok(output) => output,
+            Err(error) => format!("error: {}", error),
+        };
+
+        let mut console = world.resource_mut::<Console>();
+        console.log.push(format!("> {}", line));
+        if !output.is_empty() {
+            console.log.push(output);
+        }
+    }
+}
+
+fn run_command_line(world: &mut World, line: &str) -> ConsoleResult {
+    let mut words = line.split_whitespace().map(str::to_string);
+
+    let Some(name) = words.next() else {
+        return Ok(String::new());
+    };
+
+    let Some(system) = world
+        .resource::<ConsoleCommands>()
+        .commands
+        .get(name.as_str())
+        .map(|command| command.system)
+    else {
+        return Err(format!("unknown command '{}', try 'help'", name));
+    };
+
+    world
+        .run_system_with_input(system, words.collect())
+        .unwrap_or_else(|error| Err(format!("{:?}", error)))
+}
+
+fn show_console(
+    mut commands: Commands,
+    console: Res<Console>,
+    mut overlays: Query<(Entity, &mut ConsoleOverlay)>,
+) {
+    let log = console.log[console.log.len().saturating_sub(LOG_LINES)..].join("\n");
+
+    match (console.open, overlays.get_single_mut()) {
+        (true, Ok((_, mut overlay))) => {
+            overlay.log = log;
+            overlay.input = console.input.clone();
+        }
+        (true, Err(_)) => {
+            commands.spawn_bsml(ConsoleOverlay {
+                log,
+                input: console.input.clone(),
+            });
+        }
+        (false, Ok((entity, _))) => commands.despawn_bsml(entity),
+        (false, Err(_)) => {}
+    }
+}
+
+/// Parses the single argument of a command.
+pub fn parse_argument<T: std::str::FromStr>(args: &[String], usage: &str) -> Result<T, String> {
+    match args {
+        [arg] => arg
+            .parse()
+            .map_err(|_| format!("'{}' is not valid, usage: {}", arg, usage)),
+        _ => Err(format!("usage: {}", usage)),
+    }
+}
+
+fn help(In(_): In<Vec<String>>, commands: Res<ConsoleCommands>) -> ConsoleResult {
+    Ok(commands
+        .commands
+        .values()
+        .map(|command| command.usage)
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn set_state(
+    In(args): In<Vec<String>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_player_state: ResMut<NextState<PlayerState>>,
+) -> ConsoleResult {
+    let name: String = parse_argument(&args, "state <mainmenu|playing|dead>")?;
+
+    match name.to_lowercase().as_str() {
+        "mainmenu" | "menu" => next_game_state.set(GameState::MainMenu),
+        "playing" => next_game_state.set(GameState::Playing),
+        "dead" => next_player_state.set(PlayerState::Dead),
+        _ => return Err(format!("unknown state '{}'", name)),
+    }
+
+    Ok(format!("switching to {}", name))
+}
+
+fn set_seed(In(args): In<Vec<String>>, mut seed: ResMut<CourseSeed>) -> ConsoleResult {
+    seed.0 = parse_argument(&args, "seed <n>")?;
+    Ok(format!(
+        "course seed is now {}, the course changes in the main menu",
+        seed.0
+    ))
+}
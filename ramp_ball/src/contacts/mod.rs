@@ -0,0 +1,296 @@
+use avian2d::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    events::{PlayerLanded, PlayerTookOff},
+    player::Player,
+};
+
+pub struct ContactsPlugin;
+
+impl Plugin for ContactsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HazardContact>()
+            .add_event::<PickupContact>()
+            .add_systems(
+                FixedUpdate,
+                (dispatch_player_contacts, track_terrain_contacts).in_set(ContactsSet),
+            );
+    }
+}
+
+/// Turns collisions into contact events once per fixed tick.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContactsSet;
+
+#[derive(PhysicsLayer, Clone, Copy, Debug)]
+pub enum GameLayer {
+    Player,
+    Terrain,
+    Hazard,
+    Pickup,
+}
+
+pub fn player_layers() -> CollisionLayers {
+    CollisionLayers::new(
+        GameLayer::Player,
+        [GameLayer::Terrain, GameLayer::Hazard, GameLayer::Pickup],
+    )
+}
+
+pub fn terrain_layers() -> CollisionLayers {
+    CollisionLayers::new(GameLayer::Terrain, GameLayer::Player)
+}
+
+pub fn hazard_layers() -> CollisionLayers {
+    CollisionLayers::new(GameLayer::Hazard, GameLayer::Player)
+}
+
+pub fn pickup_layers() -> CollisionLayers {
+    CollisionLayers::new(GameLayer::Pickup, GameLayer::Player)
+}
+
+/// Sent when the player starts touching an entity on the hazard layer.
+#[derive(Event)]
+pub struct HazardContact {
+    pub player: Entity,
+    pub hazard: Entity,
+}
+
+/// Sent when the player starts touching an entity on the pickup layer.
+#[derive(Event)]
+pub struct PickupContact {
+    pub player: Entity,
+    pub pickup: Entity,
+}
+
+/// The terrain a player is touching. Landing and taking off are the moments
+/// this goes from empty to not and back.
+#[derive(Component, Default)]
+pub struct TerrainContacts(HashSet<Entity>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactKind {
+    Terrain,
+    Hazard,
+    Pickup,
+}
+
+/// Decides what a contact between the player and another collider means.
+///
+/// Only layers that both sides agree on count, so an entity is never treated
+/// as a hazard or pickup unless it is a member of that layer and the player
+/// listens for it.
+pub fn classify_contact(player: CollisionLayers, other: CollisionLayers) -> Option<ContactKind> {
+    if !player.interacts_with(other) {
+        return None;
+    }
+
+    let shared = other.memberships & player.filters;
+
+    if shared.has_all(GameLayer::Hazard) {
+        Some(ContactKind::Hazard)
+    } else if shared.has_all(GameLayer::Pickup) {
+        Some(ContactKind::Pickup)
+    } else if shared.has_all(GameLayer::Terrain) {
+        Some(ContactKind::Terrain)
+    } else {
+        None
+    }
+}
+
+fn dispatch_player_contacts(
+    mut collisions_started: EventReader<CollisionStarted>,
+    players: Query<&CollisionLayers, With<Player>>,
+    colliders: Query<&CollisionLayers>,
+    mut hazard_contacts: EventWriter<HazardContact>,
+    mut pickup_contacts: EventWriter<PickupContact>,
+) {
+    for CollisionStarted(entity1, entity2) in collisions_started.read() {
+        let (player, other) = if players.contains(*entity1) {
+            (*entity1, *entity2)
+        } else if players.contains(*entity2) {
+            (*entity2, *entity1)
+        } else {
+            continue;
+        };
+
+        let (Ok(player_layers), Ok(other_layers)) = (players.get(player), colliders.get(other))
+        else {
+            continue;
+        };
+
+        match classify_contact(*player_layers, *other_layers) {
+            Some(ContactKind::Hazard) => {
+                hazard_contacts.send(HazardContact {
+                    player,
+                    hazard: other,
+                });
+            }
+            Some(ContactKind::Pickup) => {
+                pickup_contacts.send(PickupContact {
+                    player,
+                    pickup: other,
+                });
+            }
+            Some(ContactKind::Terrain) | None => {}
+        }
+    }
+}
+
+fn track_terrain_contacts(
+    mut collisions_started: EventReader<CollisionStarted>,
+    mut collisions_ended: EventReader<CollisionEnded>,
+    mut players: Query<(&CollisionLayers, &LinearVelocity, &mut TerrainContacts), With<Player>>,
+    colliders: Query<&CollisionLayers>,
+    mut landed: EventWriter<PlayerLanded>,
+    mut took_off: EventWriter<PlayerTookOff>,
+) {
+    for CollisionStarted(entity1, entity2) in collisions_started.read() {
+        let (player, other) = if players.contains(*entity1) {
+            (*entity1, *entity2)
+        } else {
+            (*entity2, *entity1)
+        };
+
+        let (Ok((player_layers, velocity, mut contacts)), Ok(other_layers)) =
+            (players.get_mut(player), colliders.get(other))
+        else {
+            continue;
+        };
+
+        if classify_contact(*player_layers, *other_layers) != Some(ContactKind::Terrain) {
+            continue;
+        }
+
+        if contacts.0.is_empty() {
+            landed.send(PlayerLanded {
+                speed: velocity.length(),
+            });
+        }
+        contacts.0.insert(other);
+    }
+
+    // Ended contacts are matched by entity alone, as terrain despawned
+    // mid-contact has no layers left to check.
+    for CollisionEnded(entity1, entity2) in collisions_ended.read() {
+        let (player, other) = if players.contains(*entity1) {
+            (*entity1, *entity2)
+        } else {
+            (*entity2, *entity1)
+        };
+
+        let Ok((_, _, mut contacts)) = players.get_mut(player) else {
+            continue;
+        };
+
+        if contacts.0.remove(&other) && contacts.0.is_empty() {
+            took_off.send(PlayerTookOff);
+        }
+    }
+
+    // Nor is an ended collision guaranteed for terrain despawned mid-contact.
+    for (_, _, mut contacts) in players.iter_mut() {
+        if contacts.0.is_empty() {
+            continue;
+        }
+        contacts.0.retain(|entity| colliders.contains(*entity));
+        if contacts.0.is_empty() {
+            took_off.send(PlayerTookOff);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::GameHarness;
+
+    #[test]
+    fn player_registers_each_layer_as_its_own_kind() {
+        assert_eq!(
+            classify_contact(player_layers(), hazard_layers()),
+            Some(ContactKind::Hazard)
+        );
+        assert_eq!(
+            classify_contact(player_layers(), pickup_layers()),
+            Some(ContactKind::Pickup)
+        );
+        assert_eq!(
+            classify_contact(player_layers(), terrain_layers()),
+            Some(ContactKind::Terrain)
+        );
+    }
+
+    #[test]
+    fn pickups_and_terrain_are_never_hazards() {
+        assert_ne!(
+            classify_contact(player_layers(), pickup_layers()),
+            Some(ContactKind::Hazard)
+        );
+        assert_ne!(
+            classify_contact(player_layers(), terrain_layers()),
+            Some(ContactKind::Hazard)
+        );
+        assert_ne!(
+            classify_contact(player_layers(), terrain_layers()),
+            Some(ContactKind::Pickup)
+        );
+    }
+
+    #[test]
+    fn hazard_that_ignores_the_player_is_not_registered() {
+        let hazard = CollisionLayers::new(GameLayer::Hazard, GameLayer::Terrain);
+
+        assert_eq!(classify_contact(player_layers(), hazard), None);
+    }
+
+    #[test]
+    fn player_that_ignores_pickups_does_not_register_them() {
+        let player =
+            CollisionLayers::new(GameLayer::Player, [GameLayer::Terrain, GameLayer::Hazard]);
+
+        assert_eq!(classify_contact(player, pickup_layers()), None);
+    }
+
+    #[test]
+    fn entities_off_the_player_layers_are_ignored() {
+        let other = CollisionLayers::new(GameLayer::Terrain, GameLayer::Terrain);
+
+        assert_eq!(classify_contact(player_layers(), other), None);
+    }
+
+    #[test]
+    fn the_ball_lands_on_the_first_platform() {
+        let mut harness = GameHarness::new();
+        harness.record::<PlayerLanded>().record::<PlayerTookOff>();
+
+        harness.start_run();
+        assert!(harness.run_until(2.0, |harness| {
+            !harness.recorded::<PlayerLanded>().is_empty()
+        }));
+
+        assert_eq!(harness.recorded::<PlayerLanded>().len(), 1);
+        assert!(harness.recorded::<PlayerTookOff>().is_empty());
+        assert!(harness.recorded::<PlayerLanded>()[0].speed > 0.0);
+    }
+
+    #[test]
+    fn leaving_the_ground_is_a_take_off() {
+        let mut harness = GameHarness::new();
+        harness.record::<PlayerLanded>().record::<PlayerTookOff>();
+
+        harness.start_run();
+        assert!(harness.run_until(2.0, |harness| !harness.touching_platforms().is_empty()));
+        let took_off = harness.recorded::<PlayerTookOff>().len();
+        let position = harness.player_position().unwrap();
+        harness.teleport_player(position + Vec2::Y * 500.0);
+        harness.run_ticks(2);
+
+        assert_eq!(harness.recorded::<PlayerTookOff>().len(), took_off + 1);
+        assert_eq!(
+            harness.recorded::<PlayerLanded>().len(),
+            harness.recorded::<PlayerTookOff>().len()
+        );
+    }
+}
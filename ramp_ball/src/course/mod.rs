@@ -0,0 +1,47 @@
+use std::fs;
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{platforms::PlatformKind, ron_asset::RonAssetLoader};
+
+pub struct CoursePlugin;
+
+impl Plugin for CoursePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Course>()
+            .register_asset_loader(RonAssetLoader::<Course>::new(&["course.ron"]));
+    }
+}
+
+/// Where the editor saves and loads its course, relative to the assets folder.
+pub const CUSTOM_COURSE_PATH: &str = "courses/custom.course.ron";
+
+/// A hand-made course, as laid out in the editor.
+#[derive(Asset, TypePath, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Course {
+    pub platforms: Vec<CoursePlatform>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoursePlatform {
+    pub kind: PlatformKind,
+    pub position: Vec2,
+    /// Spline control points, relative to `position`.
+    pub points: Vec<Vec2>,
+}
+
+/// Writes a course into the assets folder, where the asset server (and the
+/// file watcher) will pick it up.
+pub fn save_course(path: &str, course: &Course) -> Result<(), String> {
+    let full_path = FileAssetReader::get_base_path().join("assets").join(path);
+
+    let contents = ron::ser::to_string_pretty(course, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())?;
+
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+    }
+
+    fs::write(&full_path, contents).map_err(|error| format!("{}: {}", full_path.display(), error))
+}
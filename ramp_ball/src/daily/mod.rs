@@ -0,0 +1,238 @@
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::EditedRun,
+    game_mode::GameMode,
+    online::OnlineRace,
+    player::{PlayerState, TravelDistanceMeters},
+    replay::ReplayPlayback,
+    save::{self, SaveDirectory},
+    seed::{CourseSeed, SplitMix64},
+    versus::Versus,
+    GameState,
+};
+
+/// The daily challenge: one course per UTC day, the same for everyone, with
+/// its own best distances and a streak of consecutive days played.
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        let saves = save::directory(app);
+
+        app.insert_resource(saves.load::<DailyRecord>(DAILY_FILE))
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                refresh_daily_seed
+                    .run_if(resource_exists::<Daily>)
+                    .run_if(not(resource_exists::<ReplayPlayback>)),
+            )
+            .add_systems(
+                Update,
+                follow_game_mode.run_if(resource_changed::<GameMode>),
+            )
+            .add_systems(
+                OnEnter(PlayerState::Dead),
+                record_daily_run
+                    .run_if(resource_exists::<Daily>)
+                    .run_if(not(resource_exists::<ReplayPlayback>))
+                    .run_if(not(resource_exists::<EditedRun>))
+                    .run_if(not(resource_exists::<Versus>))
+                    .run_if(not(resource_exists::<OnlineRace>)),
+            );
+    }
+}
+
+const DAILY_FILE: &str = "daily.ron";
+
+/// Mixed into the day number so daily courses don't line up with courses
+/// seeded by small integers.
+const DAILY_SALT: u64 = 0xDA11_C0DE;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Present while [`GameMode::Daily`] is the selected mode.
+#[derive(Resource, Debug)]
+pub struct Daily {
+    /// Days since the Unix epoch, in UTC.
+    pub day: u64,
+    /// Seed to go back to when another mode is picked.
+    previous_seed: CourseSeed,
+}
+
+/// Best distance per day and the current streak, saved locally.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyRecord {
+    best: BTreeMap<u64, f32>,
+    streak: u32,
+    last_played: Option<u64>,
+}
+
+impl DailyRecord {
+    pub fn best(&self, day: u64) -> Option<f32> {
+        self.best.get(&day).copied()
+    }
+
+    /// Consecutive days played up to `today`. A streak survives until the
+    /// end of the day after it was last extended.
+    pub fn streak(&self, today: u64) -> u32 {
+        match self.last_played {
+            Some(day) if day == today || day + 1 == today => self.streak,
+            _ => 0,
+        }
+    }
+
+    /// Records a finished run on `day`'s course.
+    fn record(&mut self, day: u64, distance: f32) {
+        if self.best(day).map_or(true, |best| distance > best) {
+            self.best.insert(day, distance);
+        }
+
+        self.streak = match self.last_played {
+            Some(last) if last == day => self.streak,
+            Some(last) if last + 1 == day => self.streak + 1,
+            _ => 1,
+        };
+        self.last_played = Some(day);
+    }
+
+    /// One line for the main menu.
+    pub fn describe(&self, day: u64) -> String {
+        let best = self
+            .best(day)
+            .map_or_else(|| "-".to_string(), |best| format!("{:.0} m", best));
+        format!(
+            "Daily {}  best {}  streak {}",
+            date_label(day),
+            best,
+            self.streak(day)
+        )
+    }
+}
+
+/// Days since the Unix epoch, in UTC.
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / SECONDS_PER_DAY)
+}
+
+pub fn daily_seed(day: u64) -> CourseSeed {
+    CourseSeed(SplitMix64::new(day ^ DAILY_SALT).next_u64())
+}
+
+/// Formats a day number as `YYYY-MM-DD`.
+fn date_label(day: u64) -> String {
+    // Howard Hinnant's days-to-civil conversion, for days after the epoch.
+    let z = day + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day_of_month)
+}
+
+fn follow_game_mode(
+    mode: Res<GameMode>,
+    mut commands: Commands,
+    daily: Option<Res<Daily>>,
+    mut seed: ResMut<CourseSeed>,
+) {
+    match (*mode == GameMode::Daily, daily) {
+        (false, Some(daily)) => {
+            *seed = daily.previous_seed;
+            commands.remove_resource::<Daily>();
+        }
+        (true, None) => {
+            let day = today();
+            commands.insert_resource(Daily {
+                day,
+                previous_seed: *seed,
+            });
+            *seed = daily_seed(day);
+        }
+        _ => {}
+    }
+}
+
+/// Puts the day's course back after an online race or replay used another
+/// seed, and moves on to the next day's course after midnight UTC.
+fn refresh_daily_seed(mut daily: ResMut<Daily>, mut seed: ResMut<CourseSeed>) {
+    let day = today();
+    if daily.day != day {
+        daily.day = day;
+    }
+
+    let todays_seed = daily_seed(day);
+    if *seed != todays_seed {
+        *seed = todays_seed;
+    }
+}
+
+fn record_daily_run(
+    daily: Res<Daily>,
+    seed: Res<CourseSeed>,
+    distance: Res<TravelDistanceMeters>,
+    mut record: ResMut<DailyRecord>,
+    saves: Res<SaveDirectory>,
+) {
+    // Only runs on the day's own course count.
+    if *seed != daily_seed(daily.day) {
+        return;
+    }
+
+    record.record(daily.day, distance.0);
+    saves.store(DAILY_FILE, &*record);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_are_labelled_with_their_utc_date() {
+        assert_eq!(date_label(0), "1970-01-01");
+        assert_eq!(date_label(11_016), "2000-02-29");
+        assert_eq!(date_label(20_745), "2026-10-19");
+    }
+
+    #[test]
+    fn each_day_gets_its_own_stable_seed() {
+        assert_eq!(daily_seed(20_745), daily_seed(20_745));
+        assert_ne!(daily_seed(20_745), daily_seed(20_746));
+    }
+
+    #[test]
+    fn consecutive_days_extend_the_streak_and_gaps_reset_it() {
+        let mut record = DailyRecord::default();
+
+        record.record(10, 100.0);
+        record.record(10, 50.0);
+        record.record(11, 80.0);
+        assert_eq!(record.streak(11), 2);
+        assert_eq!(record.streak(12), 2);
+        assert_eq!(record.streak(13), 0);
+        assert_eq!(record.best(10), Some(100.0));
+        assert_eq!(record.best(11), Some(80.0));
+
+        record.record(14, 10.0);
+        assert_eq!(record.streak(14), 1);
+    }
+}
@@ -0,0 +1,178 @@
+use avian2d::prelude::*;
+use bevy::{
+    diagnostic::{DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+    sprite::{Wireframe2dConfig, Wireframe2dPlugin},
+};
+use bevy_bsml::prelude::*;
+
+use crate::{
+    console::ConsolePlugin,
+    killcam::KillcamState,
+    platforms::Platform,
+    player::{Player, PlayerState},
+    replay::ReplayState,
+    spikes::Spikes,
+    GameState,
+};
+
+/// Debug overlay and rendering toggles, only built with the `dev` feature.
+///
+/// F1 toggles the overlay, F2 physics debug rendering and F3 wireframes;
+/// backquote opens the console.
+pub struct DevToolsPlugin;
+
+impl Plugin for DevToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            FrameTimeDiagnosticsPlugin,
+            EntityCountDiagnosticsPlugin,
+            PhysicsDebugPlugin::default(),
+            Wireframe2dPlugin,
+            ConsolePlugin,
+        ))
+        .add_systems(Startup, (hide_physics_debug, spawn_dev_overlay))
+        .add_systems(
+            Update,
+            (
+                toggle_dev_overlay,
+                toggle_physics_debug,
+                toggle_wireframe,
+                update_dev_overlay,
+            ),
+        );
+    }
+}
+
+const OVERLAY_KEY: KeyCode = KeyCode::F1;
+const PHYSICS_DEBUG_KEY: KeyCode = KeyCode::F2;
+const WIREFRAME_KEY: KeyCode = KeyCode::F3;
+
+#[derive(Component, Default)]
+struct DevOverlay {
+    performance: String,
+    states: String,
+    player: String,
+    course: String,
+}
+
+bsml! {DevOverlay;
+    (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_END, BG_TRANSPARENT]) {
+        (text class=[FontSize::px(16.0)]) { "{}", self.performance }
+        (text class=[FontSize::px(16.0)]) { "{}", self.states }
+        (text class=[FontSize::px(16.0)]) { "{}", self.player }
+        (text class=[FontSize::px(16.0)]) { "{}", self.course }
+    }
+}
+
+fn spawn_dev_overlay(mut commands: Commands) {
+    commands.spawn_bsml(DevOverlay::default());
+}
+
+fn hide_physics_debug(mut config_store: ResMut<GizmoConfigStore>) {
+    config_store.config_mut::<PhysicsGizmos>().0.enabled = false;
+}
+
+fn toggle_dev_overlay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut overlays: Query<&mut Visibility, With<DevOverlay>>,
+) {
+    if keyboard.just_pressed(OVERLAY_KEY) {
+        for mut visibility in overlays.iter_mut() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+fn toggle_physics_debug(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut config_store: ResMut<GizmoConfigStore>,
+) {
+    if keyboard.just_pressed(PHYSICS_DEBUG_KEY) {
+        let config = &mut config_store.config_mut::<PhysicsGizmos>().0;
+        config.enabled = !config.enabled;
+    }
+}
+
+fn toggle_wireframe(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut wireframe_config: ResMut<Wireframe2dConfig>,
+) {
+    if keyboard.just_pressed(WIREFRAME_KEY) {
+        wireframe_config.global = !wireframe_config.global;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_dev_overlay(
+    mut overlays: Query<&mut DevOverlay>,
+    diagnostics: Res<DiagnosticsStore>,
+    meshes: Res<Assets<Mesh>>,
+    materials: Res<Assets<ColorMaterial>>,
+    images: Res<Assets<Image>>,
+    states: (
+        Res<State<GameState>>,
+        Res<State<PlayerState>>,
+        Res<State<KillcamState>>,
+        Res<State<ReplayState>>,
+    ),
+    players: Query<(&Position, &LinearVelocity, &GravityScale), With<Player>>,
+    spikes: Query<(&Position, &Collider), With<Spikes>>,
+    platforms: Query<(), With<Platform>>,
+) {
+    let Ok(mut overlay) = overlays.get_single_mut() else {
+        return;
+    };
+
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    let entities = diagnostics
+        .get(&EntityCountDiagnosticsPlugin::ENTITY_COUNT)
+        .and_then(|count| count.value())
+        .unwrap_or_default();
+    overlay.performance = format!(
+        "{:.0} fps | {:.0} entities | {} meshes, {} materials, {} images",
+        fps,
+        entities,
+        meshes.len(),
+        materials.len(),
+        images.len()
+    );
+
+    let (game_state, player_state, killcam_state, replay_state) = states;
+    overlay.states = format!(
+        "{:?} | player {:?} | killcam {:?} | replay {:?}",
+        game_state.get(),
+        player_state.get(),
+        killcam_state.get(),
+        replay_state.get()
+    );
+
+    let player = players.iter().next();
+    overlay.player = match player {
+        Some((_, velocity, gravity_scale)) => format!(
+            "velocity ({:.0}, {:.0}) | gravity scale {:.1}",
+            velocity.x, velocity.y, gravity_scale.0
+        ),
+        None => "no player".to_string(),
+    };
+
+    let spike_edge = spikes
+        .iter()
+        .map(|(position, collider)| collider.aabb(position.0, 0.0).max.x)
+        .reduce(f32::max);
+    let spike_distance = match (player, spike_edge) {
+        (Some((position, _, _)), Some(edge)) => format!("{:.0}px", position.x - edge),
+        _ => "-".to_string(),
+    };
+    overlay.course = format!(
+        "spikes {} behind | {} platforms",
+        spike_distance,
+        platforms.iter().count()
+    );
+}
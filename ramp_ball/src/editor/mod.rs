@@ -0,0 +1,572 @@
+use avian2d::prelude::*;
+use bevy::{
+    asset::LoadState, input::mouse::MouseMotion, prelude::*,
+    render::camera::Camera as RenderCamera, sprite::Mesh2dHandle,
+};
+
+use crate::{
+    actions::{Action, ActionState},
+    camera::{Camera, CameraFollowSet, CameraRig},
+    course::{save_course, Course, CoursePlatform, CUSTOM_COURSE_PATH},
+    platforms::{
+        platform_geometry, spawn_course, spawn_platform, NextPlatformIndex, Platform, PlatformKind,
+    },
+    player::Player,
+    seed::CourseSeed,
+    GameState,
+};
+
+/// In-game editor for laying out spline platforms by hand and trying them
+/// out straight away.
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Editor>()
+            .add_event::<EditorCommand>()
+            .add_systems(OnEnter(GameState::Editor), enter_editor)
+            .add_systems(OnExit(GameState::Editor), remember_course)
+            .add_systems(OnEnter(GameState::MainMenu), end_edited_run)
+            .add_systems(
+                Update,
+                (
+                    handle_editor_actions,
+                    handle_editor_commands,
+                    finish_loading_course,
+                    pan_editor_camera.after(CameraFollowSet),
+                    edit_control_points,
+                    place_player,
+                    rebuild_edited_platforms,
+                    draw_control_points,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Editor)),
+            );
+    }
+}
+
+const PAN_SPEED: f32 = 800.0;
+/// How close, in world units, a click has to be to grab a control point.
+const PICK_RADIUS: f32 = 30.0;
+const POINT_RADIUS: f32 = 12.0;
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorCommand {
+    NewPlatform,
+    CycleKind,
+    Save,
+    Load,
+    PlayFromHere,
+    Exit,
+}
+
+/// Buttons on top of the course. Clicks on them are not edits.
+#[derive(Component)]
+pub struct EditorButton;
+
+/// Present while a run started from the editor is going on. Such runs are
+/// not on the seeded course, so they don't count towards high scores,
+/// replays or ghosts.
+#[derive(Resource)]
+pub struct EditedRun;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SelectedPoint {
+    platform: Entity,
+    point: usize,
+}
+
+#[derive(Resource, Default)]
+pub struct Editor {
+    pub kind: PlatformKind,
+    pub status: String,
+    /// Waiting for a click to drop the ball and start a run.
+    pub placing_player: bool,
+    camera_position: Vec2,
+    selected: Option<SelectedPoint>,
+    dragging: bool,
+    loading: Option<Handle<Course>>,
+    /// The course as it was when the editor was last left, restored when it
+    /// is opened again.
+    course: Option<Course>,
+}
+
+fn enter_editor(
+    mut editor: ResMut<Editor>,
+    cameras: Query<&Transform, With<Camera>>,
+    platforms: Query<Entity, With<Platform>>,
+    mut next_index: ResMut<NextPlatformIndex>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if let Ok(transform) = cameras.get_single() {
+        editor.camera_position = transform.translation.truncate();
+    }
+
+    editor.selected = None;
+    editor.dragging = false;
+    editor.placing_player = false;
+    editor.status = "Click a point to drag it, shift+click to add one".to_string();
+
+    if let Some(course) = editor.course.clone() {
+        replace_course(
+            &course,
+            &platforms,
+            &mut next_index,
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+        );
+    }
+}
+
+fn remember_course(mut editor: ResMut<Editor>, platforms: Query<(&Platform, &Transform)>) {
+    editor.course = Some(course_from(&platforms));
+    editor.loading = None;
+}
+
+fn end_edited_run(mut commands: Commands) {
+    commands.remove_resource::<EditedRun>();
+}
+
+fn handle_editor_actions(
+    actions: Res<ActionState>,
+    mut editor_commands: EventWriter<EditorCommand>,
+) {
+    if actions.just_pressed(Action::Back) {
+        editor_commands.send(EditorCommand::Exit);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_editor_commands(
+    mut editor_commands: EventReader<EditorCommand>,
+    mut editor: ResMut<Editor>,
+    mut platforms: Query<(&mut Platform, &Transform)>,
+    seed: Res<CourseSeed>,
+    asset_server: Res<AssetServer>,
+    mut next_index: ResMut<NextPlatformIndex>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for command in editor_commands.read() {
+        match command {
+            EditorCommand::NewPlatform => {
+                let index = next_index.0;
+                next_index.0 += 1;
+                let platform = Platform::generated(*seed, index, editor.kind);
+                let entity = spawn_platform(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    platform,
+                    editor.camera_position.extend(0.0),
+                );
+                editor.selected = Some(SelectedPoint {
+                    platform: entity,
+                    point: 0,
+                });
+                editor.status = format!("Added a {} platform", editor.kind.name());
+            }
+            EditorCommand::CycleKind => {
+                let next = PlatformKind::ALL
+                    .iter()
+                    .position(|kind| *kind == editor.kind)
+                    .map_or(0, |position| (position + 1) % PlatformKind::ALL.len());
+                editor.kind = PlatformKind::ALL[next];
+
+                // Reshapes the selected platform too, so the kind can be set
+                // on platforms that already exist.
+                if let Some(selected) = editor.selected {
+                    if let Ok((mut platform, _)) = platforms.get_mut(selected.platform) {
+                        *platform = Platform::generated(*seed, platform.index, editor.kind);
+                        editor.selected = None;
+                    }
+                }
+            }
+            EditorCommand::Save => {
+                let course = course_from(&platforms.to_readonly());
+                editor.status = match save_course(CUSTOM_COURSE_PATH, &course) {
+                    Ok(()) => format!("Saved {} platforms", course.platforms.len()),
+                    Err(error) => format!("Could not save: {}", error),
+                };
+            }
+            EditorCommand::Load => {
+                editor.loading = Some(asset_server.load(CUSTOM_COURSE_PATH));
+                editor.status = "Loading...".to_string();
+            }
+            EditorCommand::PlayFromHere => {
+                editor.placing_player = !editor.placing_player;
+                editor.status = if editor.placing_player {
+                    "Click where the ball should drop from".to_string()
+                } else {
+                    String::new()
+                };
+            }
+            EditorCommand::Exit => next_state.set(GameState::MainMenu),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn finish_loading_course(
+    mut editor: ResMut<Editor>,
+    asset_server: Res<AssetServer>,
+    courses: Res<Assets<Course>>,
+    platforms: Query<Entity, With<Platform>>,
+    mut next_index: ResMut<NextPlatformIndex>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Some(handle) = editor.loading.clone() else {
+        return;
+    };
+
+    match asset_server.get_load_state(&handle) {
+        Some(LoadState::Loaded) => {
+            if let Some(course) = courses.get(&handle) {
+                replace_course(
+                    course,
+                    &platforms,
+                    &mut next_index,
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                );
+                editor.selected = None;
+                editor.status = format!("Loaded {} platforms", course.platforms.len());
+            }
+            editor.loading = None;
+        }
+        Some(LoadState::Failed(_)) => {
+            editor.status = "No saved course to load".to_string();
+            editor.loading = None;
+        }
+        _ => {}
+    }
+}
+
+fn pan_editor_camera(
+    mut editor: ResMut<Editor>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    time: Res<Time<Real>>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
+) {
+    let Ok((mut camera_transform, projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    let mut direction = Vec2::ZERO;
+    if keyboard.pressed(KeyCode::ArrowLeft) {
+        direction.x -= 1.0;
+    }
+    if keyboard.pressed(KeyCode::ArrowRight) {
+        direction.x += 1.0;
+    }
+    if keyboard.pressed(KeyCode::ArrowDown) {
+        direction.y -= 1.0;
+    }
+    if keyboard.pressed(KeyCode::ArrowUp) {
+        direction.y += 1.0;
+    }
+
+    let mut position = editor.camera_position + direction * PAN_SPEED * time.delta_seconds();
+
+    // Dragging with the right button grabs the course: screen y points down.
+    if mouse_buttons.pressed(MouseButton::Right) {
+        for motion in mouse_motion.read() {
+            position += Vec2::new(-motion.delta.x, motion.delta.y) * projection.scale;
+        }
+    } else {
+        mouse_motion.clear();
+    }
+
+    if editor.camera_position != position {
+        editor.camera_position = position;
+    }
+
+    camera_transform.translation.x = position.x;
+    camera_transform.translation.y = position.y;
+}
+
+/// Where the mouse is pointing in the world, unless it is over a button.
+fn cursor_in_world(
+    windows: &Query<&Window>,
+    cameras: &Query<(&RenderCamera, &GlobalTransform), With<Camera>>,
+    buttons: &Query<&Interaction, With<EditorButton>>,
+) -> Option<Vec2> {
+    if buttons
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        return None;
+    }
+
+    let cursor = windows.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = cameras.get_single().ok()?;
+    camera.viewport_to_world_2d(camera_transform, cursor)
+}
+
+fn edit_control_points(
+    mut editor: ResMut<Editor>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window>,
+    cameras: Query<(&RenderCamera, &GlobalTransform), With<Camera>>,
+    buttons: Query<&Interaction, With<EditorButton>>,
+    mut platforms: Query<(Entity, &mut Platform, &Transform)>,
+) {
+    if editor.placing_player {
+        return;
+    }
+
+    if mouse_buttons.just_released(MouseButton::Left) {
+        editor.dragging = false;
+    }
+
+    // Not Backspace, which is bound to leaving the editor.
+    if keyboard.just_pressed(KeyCode::Delete) {
+        if let Some(selected) = editor.selected {
+            if let Ok((_, mut platform, _)) = platforms.get_mut(selected.platform) {
+                // A spline needs two ends.
+                if platform.points.len() > 2 {
+                    platform.points.remove(selected.point);
+                    editor.selected = None;
+                }
+            }
+        }
+    }
+
+    let Some(cursor) = cursor_in_world(&windows, &cameras, &buttons) else {
+        return;
+    };
+
+    if editor.dragging && mouse_buttons.pressed(MouseButton::Left) {
+        if let Some(selected) = editor.selected {
+            if let Ok((_, mut platform, transform)) = platforms.get_mut(selected.platform) {
+                let point = cursor - transform.translation.truncate();
+                if platform.points[selected.point] != point {
+                    platform.points[selected.point] = point;
+                }
+            }
+        }
+        return;
+    }
+
+    if !mouse_buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if let (true, Some(selected)) = (shift, editor.selected) {
+        if let Ok((_, mut platform, transform)) = platforms.get_mut(selected.platform) {
+            // Keeps the points in order along the platform.
+            let point = cursor - transform.translation.truncate();
+            let index = platform
+                .points
+                .iter()
+                .position(|existing| existing.x > point.x)
+                .unwrap_or(platform.points.len());
+            platform.points.insert(index, point);
+
+            editor.selected = Some(SelectedPoint {
+                platform: selected.platform,
+                point: index,
+            });
+            editor.dragging = true;
+            return;
+        }
+    }
+
+    let nearest = platforms
+        .iter()
+        .flat_map(|(entity, platform, transform)| {
+            platform
+                .points
+                .iter()
+                .enumerate()
+                .map(move |(point, position)| {
+                    let distance = (transform.translation.truncate() + *position).distance(cursor);
+                    (
+                        SelectedPoint {
+                            platform: entity,
+                            point,
+                        },
+                        distance,
+                    )
+                })
+        })
+        .filter(|(_, distance)| *distance <= PICK_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(selected, _)| selected);
+
+    editor.selected = nearest;
+    editor.dragging = nearest.is_some();
+}
+
+/// Drops the ball where the mouse is clicked and starts a run from there.
+#[allow(clippy::too_many_arguments)]
+fn place_player(
+    mut editor: ResMut<Editor>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window>,
+    cameras: Query<(&RenderCamera, &GlobalTransform), With<Camera>>,
+    buttons: Query<&Interaction, With<EditorButton>>,
+    mut players: Query<(&mut Transform, &mut Position, &mut LinearVelocity), With<Player>>,
+    mut rigs: Query<&mut CameraRig, With<Camera>>,
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !editor.placing_player || !mouse_buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some(cursor) = cursor_in_world(&windows, &cameras, &buttons) else {
+        return;
+    };
+
+    for (mut transform, mut position, mut velocity) in players.iter_mut() {
+        transform.translation = cursor.extend(transform.translation.z);
+        position.0 = cursor;
+        velocity.0 = Vec2::ZERO;
+    }
+
+    // Starts the camera on the ball instead of easing over from the menu.
+    for mut rig in rigs.iter_mut() {
+        rig.focus = cursor;
+    }
+
+    editor.placing_player = false;
+    commands.insert_resource(EditedRun);
+    next_state.set(GameState::Playing);
+}
+
+fn rebuild_edited_platforms(
+    platforms: Query<(Entity, &Platform, &Mesh2dHandle), Changed<Platform>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut commands: Commands,
+) {
+    for (entity, platform, mesh_handle) in platforms.iter() {
+        let (mesh, collider) = platform_geometry(&platform.points);
+        meshes.insert(&mesh_handle.0, mesh);
+
+        match collider {
+            Some(collider) => commands.entity(entity).insert(collider),
+            None => commands.entity(entity).remove::<Collider>(),
+        };
+    }
+}
+
+fn draw_control_points(
+    editor: Res<Editor>,
+    platforms: Query<(Entity, &Platform, &Transform)>,
+    mut gizmos: Gizmos,
+) {
+    for (entity, platform, transform) in platforms.iter() {
+        let origin = transform.translation.truncate();
+
+        for pair in platform.points.windows(2) {
+            gizmos.line_2d(
+                origin + pair[0],
+                origin + pair[1],
+                Color::srgba(1.0, 1.0, 1.0, 0.3),
+            );
+        }
+
+        for (index, point) in platform.points.iter().enumerate() {
+            let selected = editor.selected
+                == Some(SelectedPoint {
+                    platform: entity,
+                    point: index,
+                });
+            let color = if selected {
+                Color::srgb(1.0, 0.8, 0.0)
+            } else {
+                Color::WHITE
+            };
+            gizmos.circle_2d(origin + *point, POINT_RADIUS, color);
+        }
+    }
+}
+
+fn course_from(platforms: &Query<(&Platform, &Transform)>) -> Course {
+    let mut platforms: Vec<_> = platforms.iter().collect();
+    platforms.sort_by_key(|(platform, _)| platform.index);
+
+    Course {
+        platforms: platforms
+            .into_iter()
+            .map(|(platform, transform)| CoursePlatform {
+                kind: platform.kind,
+                position: transform.translation.truncate(),
+                points: platform.points.clone(),
+            })
+            .collect(),
+    }
+}
+
+/// Swaps whatever platforms are out for the ones in `course`.
+fn replace_course(
+    course: &Course,
+    platforms: &Query<Entity, With<Platform>>,
+    next_index: &mut NextPlatformIndex,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) {
+    for entity in platforms.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    spawn_course(course, next_index, commands, meshes, materials);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::GameHarness;
+
+    #[test]
+    fn deleting_a_point_stays_in_the_editor() {
+        let mut harness = GameHarness::new();
+        harness.open_editor();
+        harness.world_mut().send_event(EditorCommand::NewPlatform);
+        harness.tick();
+
+        let selected = harness.world().resource::<Editor>().selected.unwrap();
+        let mut platform = harness
+            .world_mut()
+            .get_mut::<Platform>(selected.platform)
+            .unwrap();
+        // One more than the two ends a spline can't lose.
+        while platform.points.len() < 3 {
+            let end = *platform.points.last().unwrap();
+            platform.points.push(end + Vec2::X * 100.0);
+        }
+        let points = platform.points.len();
+
+        harness.press_key(KeyCode::Delete);
+
+        assert_eq!(harness.game_state(), GameState::Editor);
+        let platform = harness.world().get::<Platform>(selected.platform).unwrap();
+        assert_eq!(platform.points.len(), points - 1);
+        assert_eq!(harness.world().resource::<Editor>().selected, None);
+    }
+
+    #[test]
+    fn back_leaves_the_editor_for_the_main_menu() {
+        let mut harness = GameHarness::new();
+        harness.open_editor();
+
+        harness.press(Action::Back);
+
+        assert_eq!(harness.game_state(), GameState::MainMenu);
+    }
+}
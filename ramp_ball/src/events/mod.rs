@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Registers the gameplay events. Gameplay systems send these as things
+/// happen, so that UI, audio, stats and the like can react without reaching
+/// into other modules' states and resources.
+pub struct GameplayEventsPlugin;
+
+impl Plugin for GameplayEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RunStarted>()
+            .add_event::<RunEnded>()
+            .add_event::<DiveStarted>()
+            .add_event::<DiveEnded>()
+            .add_event::<PlayerLanded>()
+            .add_event::<PlayerTookOff>()
+            .add_event::<PlatformSpawned>()
+            .add_event::<PlatformSunk>()
+            .add_event::<CoinCollected>();
+    }
+}
+
+/// Sent when the ball is dropped at the start of a run.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RunStarted {
+    pub seed: u64,
+}
+
+/// Sent when the player dies. A rewound run can end more than once.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RunEnded {
+    pub distance: f32,
+    pub cause: DeathCause,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeathCause {
+    Spikes,
+    Fell,
+    /// Crossed a time trial's finish line, the one way a run ends well.
+    Finished,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DiveStarted;
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DiveEnded;
+
+/// Sent when the ball touches down on terrain after being in the air.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerLanded {
+    /// How fast the ball was moving when it landed, in pixels per second.
+    pub speed: f32,
+}
+
+/// Sent when the ball leaves the last piece of terrain it was touching.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerTookOff;
+
+/// Sent when a new platform is added to the course.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlatformSpawned {
+    pub entity: Entity,
+    pub index: u64,
+}
+
+/// Sent when the player has passed a platform and it starts sinking away.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlatformSunk {
+    pub entity: Entity,
+    pub index: u64,
+}
+
+/// Sent when a ball picks up a coin.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CoinCollected;
@@ -0,0 +1,298 @@
+use bevy::{
+    color::HexColorError,
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+};
+use lyon::{
+    math::{point, Point},
+    path::Polygon,
+    tessellation::{geometry_builder::simple_builder, FillOptions, FillTessellator, VertexBuffers},
+};
+use serde::Deserialize;
+
+/// Sides of the polygons standing in for circles. The ball's ring covers its
+/// outer few pixels, so the corners of its outline never show.
+const CIRCLE_SIDES: usize = 64;
+
+/// A flag drawn from layers, each painted over the ones before it.
+///
+/// Positions and sizes are in flag units: the flag spans -1 to 1 across and
+/// up with its center at the origin, and is stretched over the ball the way
+/// a skin texture is, then clipped to the ball's circle.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct Flag(pub Vec<Layer>);
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum Layer {
+    /// Paints the whole flag.
+    Fill(FlagColor),
+    /// Equal stripes from top to bottom.
+    HStripes(Vec<FlagColor>),
+    /// Equal stripes from left to right.
+    VStripes(Vec<FlagColor>),
+    /// A bar across and a bar up, `width` wide and crossing at `(x, y)`.
+    Cross {
+        color: FlagColor,
+        #[serde(default)]
+        x: f32,
+        #[serde(default)]
+        y: f32,
+        width: f32,
+    },
+    /// A disc centered on `(x, y)`.
+    Circle {
+        color: FlagColor,
+        #[serde(default)]
+        x: f32,
+        #[serde(default)]
+        y: f32,
+        radius: f32,
+    },
+}
+
+/// An sRGB color written in hex, like `"#C8102E"`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct FlagColor(pub Color);
+
+impl TryFrom<String> for FlagColor {
+    type Error = HexColorError;
+
+    fn try_from(hex: String) -> Result<Self, Self::Error> {
+        Srgba::hex(hex).map(|color| FlagColor(color.into()))
+    }
+}
+
+impl Layer {
+    /// The convex pieces the layer paints, counterclockwise in flag units.
+    fn pieces(&self) -> Vec<(Color, Vec<Point>)> {
+        match self {
+            Layer::Fill(color) => vec![(color.0, rectangle(-1.0, -1.0, 1.0, 1.0))],
+            Layer::HStripes(colors) => {
+                let height = 2.0 / colors.len() as f32;
+                colors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, color)| {
+                        let top = 1.0 - i as f32 * height;
+                        (color.0, rectangle(-1.0, top - height, 1.0, top))
+                    })
+                    .collect()
+            }
+            Layer::VStripes(colors) => {
+                let width = 2.0 / colors.len() as f32;
+                colors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, color)| {
+                        let left = -1.0 + i as f32 * width;
+                        (color.0, rectangle(left, -1.0, left + width, 1.0))
+                    })
+                    .collect()
+            }
+            Layer::Cross { color, x, y, width } => {
+                let half = width / 2.0;
+                vec![
+                    (color.0, rectangle(-1.0, y - half, 1.0, y + half)),
+                    (color.0, rectangle(x - half, -1.0, x + half, 1.0)),
+                ]
+            }
+            Layer::Circle {
+                color,
+                x,
+                y,
+                radius,
+            } => vec![(color.0, circle(point(*x, *y), *radius))],
+        }
+    }
+}
+
+fn rectangle(left: f32, bottom: f32, right: f32, top: f32) -> Vec<Point> {
+    vec![
+        point(left, bottom),
+        point(right, bottom),
+        point(right, top),
+        point(left, top),
+    ]
+}
+
+fn circle(center: Point, radius: f32) -> Vec<Point> {
+    (0..CIRCLE_SIDES)
+        .map(|i| {
+            let angle = std::f32::consts::TAU * i as f32 / CIRCLE_SIDES as f32;
+            center + lyon::math::vector(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// Cuts the convex `shape` down to the part inside the convex `outline`,
+/// both counterclockwise (Sutherland-Hodgman).
+fn clip(shape: &[Point], outline: &[Point]) -> Vec<Point> {
+    let mut clipped = shape.to_vec();
+
+    for (i, &a) in outline.iter().enumerate() {
+        let edge = outline[(i + 1) % outline.len()] - a;
+        let inside = |p: Point| edge.cross(p - a) >= 0.0;
+        let crossing = |p: Point, q: Point| p + (q - p) * (edge.cross(a - p) / edge.cross(q - p));
+
+        let input = std::mem::take(&mut clipped);
+        for (j, &p) in input.iter().enumerate() {
+            let q = input[(j + 1) % input.len()];
+            match (inside(p), inside(q)) {
+                (true, true) => clipped.push(q),
+                (true, false) => clipped.push(crossing(p, q)),
+                (false, true) => {
+                    clipped.push(crossing(p, q));
+                    clipped.push(q);
+                }
+                (false, false) => {}
+            }
+        }
+
+        if clipped.len() < 3 {
+            return Vec::new();
+        }
+    }
+
+    clipped
+}
+
+/// Triangles covering a ball of `radius` in the flag, in ball coordinates,
+/// with each vertex's color alongside it.
+fn tessellate(flag: &Flag, radius: f32) -> (VertexBuffers<Point, u16>, Vec<Color>) {
+    let outline = circle(Point::origin(), 1.0);
+    let mut tessellator = FillTessellator::new();
+    let mut buffers: VertexBuffers<Point, u16> = VertexBuffers::new();
+    let mut colors = Vec::new();
+
+    for (color, piece) in flag.0.iter().flat_map(Layer::pieces) {
+        let clipped: Vec<Point> = clip(&piece, &outline)
+            .into_iter()
+            .map(|p| p * radius)
+            .collect();
+        if clipped.is_empty() {
+            continue;
+        }
+
+        tessellator
+            .tessellate_polygon(
+                Polygon {
+                    points: &clipped,
+                    closed: true,
+                },
+                &FillOptions::default(),
+                &mut simple_builder(&mut buffers),
+            )
+            .unwrap();
+        colors.resize(buffers.vertices.len(), color);
+    }
+
+    (buffers, colors)
+}
+
+/// The flag as a mesh for a ball of `radius`, colored through its vertex
+/// colors so it can be drawn with a plain white material.
+pub fn flag_mesh(flag: &Flag, radius: f32) -> Mesh {
+    let (buffers, colors) = tessellate(flag, radius);
+
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    );
+
+    let vertices: Vec<_> = buffers
+        .vertices
+        .into_iter()
+        .map(|vertex| Vec3::new(vertex.x, vertex.y, 0.0))
+        .collect();
+    let colors: Vec<_> = colors
+        .into_iter()
+        .map(|color| color.to_linear().to_f32_array())
+        .collect();
+
+    let indices = buffers.indices.into_iter().map(|x| x as u32).collect();
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.insert_indices(Indices::U32(indices));
+
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area_by_color(flag: &Flag) -> Vec<(Color, f32)> {
+        let (buffers, colors) = tessellate(flag, 1.0);
+        let mut areas: Vec<(Color, f32)> = Vec::new();
+
+        for triangle in buffers.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| buffers.vertices[triangle[i] as usize]);
+            let area = (b - a).cross(c - a).abs() / 2.0;
+            let color = colors[triangle[0] as usize];
+
+            match areas.iter_mut().find(|(seen, _)| *seen == color) {
+                Some((_, total)) => *total += area,
+                None => areas.push((color, area)),
+            }
+        }
+
+        areas
+    }
+
+    #[test]
+    fn flags_parse_from_the_compact_format() {
+        let flag: Flag =
+            ron::from_str(r##"[Fill("#006AA7"), Cross(color: "#FECC00", x: -0.25, width: 0.3)]"##)
+                .unwrap();
+
+        assert_eq!(
+            flag.0[1],
+            Layer::Cross {
+                color: FlagColor(Srgba::hex("FECC00").unwrap().into()),
+                x: -0.25,
+                y: 0.0,
+                width: 0.3,
+            }
+        );
+        assert!(ron::from_str::<Flag>(r#"[Fill("not a color")]"#).is_err());
+    }
+
+    #[test]
+    fn stripes_are_clipped_to_the_ball() {
+        let flag: Flag =
+            ron::from_str(r##"[HStripes(["#000000", "#DD0000", "#FFCE00"])]"##).unwrap();
+        let (buffers, _) = tessellate(&flag, 40.0);
+        assert!(buffers
+            .vertices
+            .iter()
+            .all(|p| p.to_vector().length() <= 40.001));
+
+        let areas = area_by_color(&flag);
+        let total: f32 = areas.iter().map(|(_, area)| area).sum();
+        assert!((total - std::f32::consts::PI).abs() < 0.01, "{}", total);
+
+        // The middle stripe crosses the widest part of the ball.
+        let [top, middle, bottom] = [0, 1, 2].map(|i| areas[i].1);
+        assert!((top - bottom).abs() < 0.001);
+        assert!(middle > top * 1.3);
+    }
+
+    #[test]
+    fn later_layers_paint_over_earlier_ones() {
+        let flag: Flag = ron::from_str(
+            r##"[Fill("#FFFFFF"), Circle(color: "#BC002D", radius: 0.6), Circle(color: "#000000", x: 3.0, radius: 0.5)]"##,
+        )
+        .unwrap();
+
+        // A disc wholly off the ball adds nothing.
+        let areas = area_by_color(&flag);
+        assert_eq!(areas.len(), 2);
+        assert!((areas[1].1 - std::f32::consts::PI * 0.36).abs() < 0.01);
+    }
+}
@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+
+use crate::GameState;
+
+/// Which mode the next run is played in. Plugins read the mode's
+/// [`ModeRules`] instead of each mode forking their systems.
+pub struct GameModePlugin;
+
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .add_event::<SelectGameMode>()
+            .add_systems(
+                Update,
+                select_game_mode.run_if(in_state(GameState::MainMenu)),
+            );
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+    /// Generated platforms and a spike wall, until the ball is caught.
+    #[default]
+    Endless,
+    /// The endless course without the spikes, where falling off just drops
+    /// the ball back in.
+    Zen,
+    /// A fixed, hand-made course raced against the clock to a finish line.
+    TimeTrial,
+    /// The endless rules on a course seeded from the date.
+    Daily,
+}
+
+/// Where a mode's platforms come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CourseSource {
+    /// Generated from the current [`CourseSeed`](crate::seed::CourseSeed).
+    Seeded,
+    /// Loaded from a course file, relative to the assets folder.
+    Authored(&'static str),
+}
+
+/// What a mode turns on and off.
+#[derive(Debug, Clone, Copy)]
+pub struct ModeRules {
+    pub course: CourseSource,
+    pub spikes: bool,
+    pub falling_kills: bool,
+    /// Whether the run is timed to a finish line at the end of the course.
+    pub timed: bool,
+    /// Whether runs count towards high scores, ghosts and the leaderboard.
+    pub ranked: bool,
+}
+
+/// The time trial's course.
+pub const TIME_TRIAL_COURSE_PATH: &str = "courses/time_trial.course.ron";
+
+impl GameMode {
+    pub const ALL: [GameMode; 4] = [
+        GameMode::Endless,
+        GameMode::Zen,
+        GameMode::TimeTrial,
+        GameMode::Daily,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Endless => "Endless",
+            GameMode::Zen => "Zen",
+            GameMode::TimeTrial => "Time Trial",
+            GameMode::Daily => "Daily",
+        }
+    }
+
+    /// The mode after this one in the main menu's selector.
+    pub fn next(self) -> Self {
+        let position = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+
+    pub fn rules(self) -> ModeRules {
+        const ENDLESS: ModeRules = ModeRules {
+            course: CourseSource::Seeded,
+            spikes: true,
+            falling_kills: true,
+            timed: false,
+            ranked: true,
+        };
+
+        match self {
+            GameMode::Endless | GameMode::Daily => ENDLESS,
+            GameMode::Zen => ModeRules {
+                spikes: false,
+                falling_kills: false,
+                ranked: false,
+                ..ENDLESS
+            },
+            GameMode::TimeTrial => ModeRules {
+                course: CourseSource::Authored(TIME_TRIAL_COURSE_PATH),
+                timed: true,
+                ranked: false,
+                ..ENDLESS
+            },
+        }
+    }
+}
+
+/// Run condition for systems that only record runs played for score.
+pub fn ranked_mode(mode: Res<GameMode>) -> bool {
+    mode.rules().ranked
+}
+
+/// Sent by the main menu's mode selector.
+#[derive(Event)]
+pub struct SelectGameMode(pub GameMode);
+
+fn select_game_mode(mut events: EventReader<SelectGameMode>, mut mode: ResMut<GameMode>) {
+    if let Some(SelectGameMode(selected)) = events.read().last() {
+        if *mode != *selected {
+            *mode = *selected;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use avian2d::prelude::*;
+
+    use super::*;
+    use crate::{events::RunEnded, harness::GameHarness, player::PlayerState, spikes::Spikes};
+
+    #[test]
+    fn only_the_endless_course_is_ranked() {
+        for mode in GameMode::ALL {
+            let rules = mode.rules();
+            assert_eq!(
+                rules.ranked,
+                matches!(mode, GameMode::Endless | GameMode::Daily),
+                "{:?}",
+                mode
+            );
+            assert_eq!(
+                rules.course == CourseSource::Seeded,
+                mode != GameMode::TimeTrial,
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn zen_is_endless_without_the_dangers() {
+        let zen = GameMode::Zen.rules();
+
+        assert!(!zen.spikes);
+        assert!(!zen.falling_kills);
+        assert!(!zen.timed);
+        assert_eq!(zen.course, CourseSource::Seeded);
+    }
+
+    #[test]
+    fn the_time_trial_is_timed_on_its_own_course() {
+        let time_trial = GameMode::TimeTrial.rules();
+
+        assert!(time_trial.timed);
+        assert!(time_trial.spikes);
+        assert!(time_trial.falling_kills);
+        assert_eq!(
+            time_trial.course,
+            CourseSource::Authored(TIME_TRIAL_COURSE_PATH)
+        );
+        assert!(!GameMode::Endless.rules().timed);
+    }
+
+    #[test]
+    fn the_selector_cycles_through_every_mode() {
+        let mut mode = GameMode::Endless;
+        for expected in GameMode::ALL
+            .iter()
+            .cycle()
+            .skip(1)
+            .take(GameMode::ALL.len())
+        {
+            mode = mode.next();
+            assert_eq!(mode, *expected);
+        }
+    }
+
+    #[test]
+    fn zen_mode_has_no_spike_wall_and_drops_fallen_balls_back_in() {
+        let mut harness = GameHarness::new();
+
+        harness.select_mode(GameMode::Zen);
+        harness.start_run();
+        harness.teleport_player(Vec2::new(0.0, -2000.0));
+        harness.run_seconds(1.0);
+
+        assert_eq!(harness.player_state(), PlayerState::Alive);
+        assert!(harness.recorded::<RunEnded>().is_empty());
+        assert!(harness.player_position().unwrap().y > -2000.0);
+
+        let spike_velocities: Vec<Vec2> = harness
+            .world_mut()
+            .query_filtered::<&LinearVelocity, With<Spikes>>()
+            .iter(harness.world())
+            .map(|velocity| velocity.0)
+            .collect();
+        assert_eq!(spike_velocities, vec![Vec2::ZERO]);
+    }
+}
@@ -0,0 +1,137 @@
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    editor::EditedRun, game_mode::ranked_mode, high_scores::HighScores, online::OnlineRace,
+    player::Player, seed::CourseSeed, trajectory::Trajectory, tuning::Tuning, versus::Versus,
+    GameState,
+};
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GhostDeltaMeters(None))
+            .add_systems(
+                OnEnter(GameState::Playing),
+                spawn_ghost
+                    .run_if(ranked_mode)
+                    .run_if(not(resource_exists::<EditedRun>))
+                    .run_if(not(resource_exists::<Versus>))
+                    .run_if(not(resource_exists::<OnlineRace>)),
+            )
+            .add_systems(OnEnter(GameState::MainMenu), despawn_ghost)
+            .add_systems(
+                Update,
+                (move_ghost, update_ghost_delta)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Translucent ball replaying the best run on the current seed.
+#[derive(Component)]
+pub struct Ghost {
+    trajectory: Trajectory,
+}
+
+/// How far ahead (positive) or behind (negative) the player is compared to
+/// the ghost.
+#[derive(Resource)]
+pub struct GhostDeltaMeters(pub Option<f32>);
+
+fn spawn_ghost(
+    mut commands: Commands,
+    high_scores: Res<HighScores>,
+    seed: Res<CourseSeed>,
+    tuning: Res<Tuning>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Some(best) = high_scores.best(*seed) else {
+        return;
+    };
+
+    let Some(start) = best.ghost.position_at(0.0) else {
+        return;
+    };
+
+    commands.spawn((
+        Ghost {
+            trajectory: best.ghost.clone(),
+        },
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Circle {
+                radius: tuning.player_radius,
+            })),
+            material: materials.add(Color::hsla(180.0, 1.0, 0.75, 0.35)),
+            transform: Transform::from_xyz(start.x, start.y, -1.0),
+            ..default()
+        },
+    ));
+}
+
+fn despawn_ghost(
+    mut commands: Commands,
+    query: Query<Entity, With<Ghost>>,
+    mut delta: ResMut<GhostDeltaMeters>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    delta.0 = None;
+}
+
+fn move_ghost(mut ghost_query: Query<(&Ghost, &mut Transform)>, trajectory: Res<Trajectory>) {
+    for (ghost, mut transform) in ghost_query.iter_mut() {
+        if let Some(position) = ghost.trajectory.position_at(trajectory.duration()) {
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+        }
+    }
+}
+
+fn update_ghost_delta(
+    ghost_query: Query<&Transform, With<Ghost>>,
+    player_query: Query<&Transform, (With<Player>, Without<Ghost>)>,
+    mut delta: ResMut<GhostDeltaMeters>,
+) {
+    if let (Ok(ghost_transform), Ok(player_transform)) =
+        (ghost_query.get_single(), player_query.get_single())
+    {
+        delta.0 = Some((player_transform.translation.x - ghost_transform.translation.x) / 100.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::GameHarness;
+
+    fn ghosts(harness: &mut GameHarness) -> usize {
+        harness
+            .world_mut()
+            .query_filtered::<(), With<Ghost>>()
+            .iter(harness.world())
+            .count()
+    }
+
+    #[test]
+    fn the_ghost_races_on_a_seed_with_a_best_run() {
+        let mut harness = GameHarness::new();
+
+        harness.start_run();
+        assert_eq!(ghosts(&mut harness), 0);
+
+        harness.run_seconds(2.0);
+        harness.teleport_player(Vec2::new(0.0, -2000.0));
+        harness.tick();
+        harness.continue_after_death();
+
+        harness.start_run();
+        assert_eq!(ghosts(&mut harness), 1);
+    }
+}
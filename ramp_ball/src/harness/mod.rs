@@ -0,0 +1,441 @@
+//! Headless harness for gameplay scenario tests.
+//!
+//! Builds the whole game on an `App` without a window or renderer, advances
+//! it one fixed physics step per update and drives it through the same
+//! actions a player would use.
+
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    actions::{Action, ActionBindings, Binding},
+    events::RunEnded,
+    game_mode::{GameMode, SelectGameMode},
+    headless_app,
+    killcam::KillcamState,
+    platforms::Platform,
+    player::{Player, PlayerState, Seat},
+    versus::StartVersus,
+    GameState, HEADLESS_FRAME,
+};
+
+/// Length of one update. Matches the default fixed timestep, so every update
+/// runs exactly one physics step.
+pub const TICK: Duration = HEADLESS_FRAME;
+
+/// How long an idle ball survives before the spike wall reaches it.
+pub const SPIKES_CATCH_IDLE_PLAYER_SECONDS: f32 = 25.0;
+
+pub struct GameHarness {
+    app: App,
+}
+
+/// Events of one type sent since the harness started recording them.
+#[derive(Resource)]
+struct Recorded<E: Event + Clone>(Vec<E>);
+
+impl GameHarness {
+    /// A game sitting in the main menu, the way it looks right after launch.
+    pub fn new() -> Self {
+        let mut app = headless_app();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(TICK));
+
+        let mut harness = Self { app };
+        harness.record::<RunEnded>();
+        harness.tick();
+        harness
+    }
+
+    pub fn world(&self) -> &World {
+        self.app.world()
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    pub fn tick(&mut self) {
+        self.app.update();
+    }
+
+    pub fn run_ticks(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+
+    pub fn run_seconds(&mut self, seconds: f32) {
+        self.run_ticks(ticks_in(seconds));
+    }
+
+    /// Ticks until `condition` holds, for at most `seconds`. Returns whether
+    /// it ever did.
+    pub fn run_until(
+        &mut self,
+        seconds: f32,
+        mut condition: impl FnMut(&mut Self) -> bool,
+    ) -> bool {
+        for _ in 0..ticks_in(seconds) {
+            if condition(self) {
+                return true;
+            }
+            self.tick();
+        }
+        condition(self)
+    }
+
+    /// Holds down the first keyboard key bound to `action` until released.
+    pub fn hold(&mut self, action: Action) {
+        let key = self.key_for(action);
+        self.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+    }
+
+    pub fn release(&mut self, action: Action) {
+        let key = self.key_for(action);
+        self.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(key);
+    }
+
+    /// Taps `action`: held for one tick, then released for one.
+    pub fn press(&mut self, action: Action) {
+        let key = self.key_for(action);
+        self.press_key(key);
+    }
+
+    /// Taps a key that isn't bound to an action, like the editor's.
+    pub fn press_key(&mut self, key: KeyCode) {
+        self.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+        self.tick();
+        self.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(key);
+        self.tick();
+    }
+
+    fn key_for(&self, action: Action) -> KeyCode {
+        self.world()
+            .resource::<ActionBindings>()
+            .bindings(action)
+            .iter()
+            .find_map(|binding| match binding {
+                Binding::Key(key) => Some(*key),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("{:?} has no keyboard binding", action))
+    }
+
+    /// Starts recording events of type `E`, readable through [`Self::recorded`].
+    pub fn record<E: Event + Clone>(&mut self) -> &mut Self {
+        self.app
+            .insert_resource(Recorded::<E>(Vec::new()))
+            .add_systems(
+                Last,
+                |mut events: EventReader<E>, mut recorded: ResMut<Recorded<E>>| {
+                    recorded.0.extend(events.read().cloned());
+                },
+            );
+        self
+    }
+
+    pub fn recorded<E: Event + Clone>(&self) -> &[E] {
+        &self.world().resource::<Recorded<E>>().0
+    }
+
+    pub fn game_state(&self) -> GameState {
+        self.world().resource::<State<GameState>>().get().clone()
+    }
+
+    pub fn player_state(&self) -> PlayerState {
+        self.world().resource::<State<PlayerState>>().get().clone()
+    }
+
+    pub fn killcam_state(&self) -> KillcamState {
+        self.world().resource::<State<KillcamState>>().get().clone()
+    }
+
+    pub fn player(&mut self) -> Option<Entity> {
+        self.world_mut()
+            .query_filtered::<Entity, With<Player>>()
+            .iter(self.app.world())
+            .next()
+    }
+
+    pub fn player_position(&mut self) -> Option<Vec2> {
+        self.world_mut()
+            .query_filtered::<&Position, With<Player>>()
+            .iter(self.app.world())
+            .next()
+            .map(|position| position.0)
+    }
+
+    /// Moves the ball, keeping its velocity.
+    pub fn teleport_player(&mut self, position: Vec2) {
+        let mut players = self
+            .world_mut()
+            .query_filtered::<(&mut Position, &mut Transform), With<Player>>();
+        for (mut player_position, mut transform) in players.iter_mut(self.app.world_mut()) {
+            player_position.0 = position;
+            transform.translation = position.extend(transform.translation.z);
+        }
+    }
+
+    /// Indices of the platforms the ball is touching.
+    pub fn touching_platforms(&mut self) -> Vec<u64> {
+        let Some(colliding) = self
+            .world_mut()
+            .query_filtered::<&CollidingEntities, With<Player>>()
+            .iter(self.app.world())
+            .next()
+            .map(|colliding| colliding.iter().copied().collect::<Vec<_>>())
+        else {
+            return Vec::new();
+        };
+
+        let mut platforms = self.world_mut().query::<&Platform>();
+        colliding
+            .into_iter()
+            .filter_map(|entity| platforms.get(self.app.world(), entity).ok())
+            .map(|platform| platform.index)
+            .collect()
+    }
+
+    /// Drops the ball from the main menu.
+    pub fn start_run(&mut self) {
+        assert_eq!(self.game_state(), GameState::MainMenu);
+        self.press(Action::Confirm);
+        assert_eq!(self.game_state(), GameState::Playing);
+    }
+
+    /// Opens the level editor from the main menu, the way its button does.
+    pub fn open_editor(&mut self) {
+        assert_eq!(self.game_state(), GameState::MainMenu);
+        self.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Editor);
+        self.tick();
+        assert_eq!(self.game_state(), GameState::Editor);
+    }
+
+    /// Picks a mode in the main menu, the way its mode selector does.
+    pub fn select_mode(&mut self, mode: GameMode) {
+        assert_eq!(self.game_state(), GameState::MainMenu);
+        self.world_mut().send_event(SelectGameMode(mode));
+        self.run_ticks(2);
+        assert_eq!(*self.world().resource::<GameMode>(), mode);
+    }
+
+    /// Starts a split-screen match from the main menu.
+    pub fn start_versus(&mut self) {
+        assert_eq!(self.game_state(), GameState::MainMenu);
+        self.world_mut().send_event(StartVersus);
+        self.run_ticks(2);
+        assert_eq!(self.game_state(), GameState::Playing);
+    }
+
+    /// Moves one player's ball, keeping its velocity.
+    pub fn teleport_seat(&mut self, seat: Seat, position: Vec2) {
+        let mut players = self
+            .world_mut()
+            .query_filtered::<(&Seat, &mut Position, &mut Transform), With<Player>>();
+        for (player_seat, mut player_position, mut transform) in
+            players.iter_mut(self.app.world_mut())
+        {
+            if *player_seat == seat {
+                player_position.0 = position;
+                transform.translation = position.extend(transform.translation.z);
+            }
+        }
+    }
+
+    pub fn seats(&mut self) -> Vec<Seat> {
+        let mut seats: Vec<Seat> = self
+            .world_mut()
+            .query_filtered::<&Seat, With<Player>>()
+            .iter(self.app.world())
+            .copied()
+            .collect();
+        seats.sort();
+        seats
+    }
+
+    /// Skips the killcam and presses Continue on the game over menu.
+    pub fn continue_after_death(&mut self) {
+        assert_eq!(self.player_state(), PlayerState::Dead);
+        if self.killcam_state() == KillcamState::Playing {
+            self.press(Action::Confirm);
+        }
+        self.press(Action::Confirm);
+    }
+}
+
+fn ticks_in(seconds: f32) -> u32 {
+    (seconds / TICK.as_secs_f32()).ceil() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{events::DeathCause, rewind::RewindRequest};
+
+    #[test]
+    fn game_starts_in_the_main_menu_with_a_live_player() {
+        let mut harness = GameHarness::new();
+
+        assert_eq!(harness.game_state(), GameState::MainMenu);
+        assert_eq!(harness.player_state(), PlayerState::Alive);
+        assert!(harness.player().is_some());
+    }
+
+    #[test]
+    fn confirm_in_the_main_menu_starts_a_run() {
+        let mut harness = GameHarness::new();
+
+        harness.start_run();
+        let start = harness.player_position().unwrap();
+        harness.run_seconds(1.0);
+
+        assert_eq!(harness.game_state(), GameState::Playing);
+        assert!(harness.player_position().unwrap().y < start.y);
+    }
+
+    #[test]
+    fn player_stays_put_in_the_main_menu() {
+        let mut harness = GameHarness::new();
+
+        let start = harness.player_position().unwrap();
+        harness.run_seconds(2.0);
+
+        assert_eq!(harness.player_position().unwrap(), start);
+    }
+
+    #[test]
+    fn spikes_kill_the_player_without_input() {
+        let mut harness = GameHarness::new();
+
+        harness.start_run();
+        let died = harness.run_until(SPIKES_CATCH_IDLE_PLAYER_SECONDS, |harness| {
+            harness.player_state() == PlayerState::Dead
+        });
+
+        assert!(died);
+        assert_eq!(harness.recorded::<RunEnded>().len(), 1);
+        assert_eq!(harness.recorded::<RunEnded>()[0].cause, DeathCause::Spikes);
+        assert!(harness.player().is_none());
+    }
+
+    #[test]
+    fn holding_dive_from_the_first_platform_lands_on_the_second() {
+        let mut harness = GameHarness::new();
+
+        harness.start_run();
+        harness.hold(Action::Dive);
+
+        let landed_on_first =
+            harness.run_until(2.0, |harness| harness.touching_platforms().contains(&0));
+        assert!(landed_on_first);
+
+        let landed_on_second =
+            harness.run_until(10.0, |harness| harness.touching_platforms().contains(&1));
+        assert!(landed_on_second);
+        assert_eq!(harness.player_state(), PlayerState::Alive);
+    }
+
+    #[test]
+    fn falling_off_the_bottom_of_the_screen_kills_the_player() {
+        let mut harness = GameHarness::new();
+
+        harness.start_run();
+        harness.teleport_player(Vec2::new(0.0, -2000.0));
+        harness.tick();
+
+        assert_eq!(harness.player_state(), PlayerState::Dead);
+        assert_eq!(harness.recorded::<RunEnded>()[0].cause, DeathCause::Fell);
+    }
+
+    #[test]
+    fn death_plays_the_killcam_while_staying_in_the_run() {
+        let mut harness = GameHarness::new();
+
+        harness.start_run();
+        harness.teleport_player(Vec2::new(0.0, -2000.0));
+        harness.run_ticks(2);
+
+        assert_eq!(harness.game_state(), GameState::Playing);
+        assert_eq!(harness.killcam_state(), KillcamState::Playing);
+    }
+
+    #[test]
+    fn continue_returns_to_the_main_menu_and_respawns_the_player() {
+        let mut harness = GameHarness::new();
+
+        harness.start_run();
+        harness.teleport_player(Vec2::new(0.0, -2000.0));
+        harness.tick();
+        harness.continue_after_death();
+
+        assert_eq!(harness.game_state(), GameState::MainMenu);
+        assert_eq!(harness.killcam_state(), KillcamState::Idle);
+        assert_eq!(harness.player_state(), PlayerState::Alive);
+        assert!(harness.player_position().unwrap().length() < 1.0);
+    }
+
+    #[test]
+    fn a_new_run_can_start_after_continuing() {
+        let mut harness = GameHarness::new();
+
+        harness.start_run();
+        harness.teleport_player(Vec2::new(0.0, -2000.0));
+        harness.tick();
+        harness.continue_after_death();
+        harness.start_run();
+        harness.run_seconds(1.0);
+
+        assert_eq!(harness.player_state(), PlayerState::Alive);
+        assert_eq!(harness.recorded::<RunEnded>().len(), 1);
+    }
+
+    #[test]
+    fn rewinding_brings_the_player_back_to_life() {
+        let mut harness = GameHarness::new();
+
+        harness.start_run();
+        harness.run_seconds(2.0);
+        harness.teleport_player(Vec2::new(0.0, -2000.0));
+        harness.tick();
+        assert_eq!(harness.player_state(), PlayerState::Dead);
+
+        harness.world_mut().send_event(RewindRequest);
+        harness.run_ticks(2);
+
+        assert_eq!(harness.game_state(), GameState::Playing);
+        assert_eq!(harness.player_state(), PlayerState::Alive);
+        assert!(harness.player_position().unwrap().y > -1000.0);
+    }
+
+    #[test]
+    fn pausing_freezes_the_run_without_leaving_it() {
+        let mut harness = GameHarness::new();
+
+        harness.start_run();
+        harness.run_seconds(0.5);
+        harness.press(Action::Pause);
+
+        let paused_at = harness.player_position().unwrap();
+        harness.run_seconds(1.0);
+
+        assert_eq!(harness.game_state(), GameState::Playing);
+        assert_eq!(harness.player_position().unwrap(), paused_at);
+
+        harness.press(Action::Pause);
+        harness.run_seconds(0.5);
+
+        assert_ne!(harness.player_position().unwrap(), paused_at);
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::EditedRun,
+    game_mode::ranked_mode,
+    player::{PlayerState, TravelDistanceMeters},
+    replay::ReplayPlayback,
+    save::{self, SaveDirectory},
+    seed::CourseSeed,
+    trajectory::Trajectory,
+    versus::Versus,
+};
+
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        let saves = save::directory(app);
+
+        app.insert_resource(saves.load::<HighScores>(HIGH_SCORES_FILE))
+            .add_systems(
+                OnEnter(PlayerState::Dead),
+                record_high_score
+                    .run_if(ranked_mode)
+                    .run_if(not(resource_exists::<ReplayPlayback>))
+                    .run_if(not(resource_exists::<EditedRun>))
+                    .run_if(not(resource_exists::<Versus>)),
+            );
+    }
+}
+
+const HIGH_SCORES_FILE: &str = "high_scores.ron";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    pub distance: f32,
+    pub ghost: Trajectory,
+}
+
+/// Best run per course seed, including the path it took.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    best: HashMap<u64, HighScore>,
+}
+
+impl HighScores {
+    pub fn best(&self, seed: CourseSeed) -> Option<&HighScore> {
+        self.best.get(&seed.0)
+    }
+}
+
+fn record_high_score(
+    mut high_scores: ResMut<HighScores>,
+    seed: Res<CourseSeed>,
+    distance: Res<TravelDistanceMeters>,
+    trajectory: Res<Trajectory>,
+    saves: Res<SaveDirectory>,
+) {
+    let is_new_best = high_scores
+        .best(*seed)
+        .map_or(true, |best| distance.0 > best.distance);
+
+    if is_new_best {
+        high_scores.best.insert(
+            seed.0,
+            HighScore {
+                distance: distance.0,
+                ghost: trajectory.clone(),
+            },
+        );
+        saves.store(HIGH_SCORES_FILE, &*high_scores);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn end_run(app: &mut App, distance: f32) {
+        app.world_mut().resource_mut::<TravelDistanceMeters>().0 = distance;
+        app.world_mut().resource_mut::<Trajectory>().samples = vec![Vec2::new(distance, 0.0)];
+        app.update();
+    }
+
+    #[test]
+    fn only_a_better_run_replaces_the_ghost_and_it_is_saved() {
+        let saves = SaveDirectory::default();
+        let seed = CourseSeed(7);
+
+        let mut app = App::new();
+        app.init_resource::<HighScores>()
+            .init_resource::<Trajectory>()
+            .insert_resource(TravelDistanceMeters(0.0))
+            .insert_resource(seed)
+            .insert_resource(saves.clone())
+            .add_systems(Update, record_high_score);
+
+        end_run(&mut app, 120.0);
+        end_run(&mut app, 80.0);
+
+        let best = app.world().resource::<HighScores>().best(seed).unwrap();
+        assert_eq!(best.distance, 120.0);
+        assert_eq!(best.ghost.samples, vec![Vec2::new(120.0, 0.0)]);
+
+        end_run(&mut app, 150.0);
+
+        let saved = saves.load::<HighScores>(HIGH_SCORES_FILE);
+        let best = saved.best(seed).unwrap();
+        assert_eq!(best.distance, 150.0);
+        assert_eq!(best.ghost.samples, vec![Vec2::new(150.0, 0.0)]);
+        assert!(saved.best(CourseSeed(8)).is_none());
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::VecDeque;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    camera::{Camera, CameraFollowSet},
+    platforms::Platform,
+    player::{spawn_ball_look, Player, PlayerLook, PlayerState},
+    replay::ReplayPlayback,
+    rewind::Rewound,
+    spikes::Spikes,
+    tuning::Tuning,
+    versus::Versus,
+    GameState,
+};
+
+pub struct KillcamPlugin;
+
+impl Plugin for KillcamPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_state(KillcamState::Idle)
+            .init_resource::<KillcamBuffer>()
+            .add_systems(OnEnter(GameState::Playing), reset_killcam_buffer)
+            .add_systems(Update, clear_killcam_on_rewind)
+            .add_systems(
+                FixedUpdate,
+                record_killcam_frame
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Alive)),
+            )
+            .add_systems(
+                OnEnter(PlayerState::Dead),
+                start_killcam
+                    .run_if(not(resource_exists::<ReplayPlayback>))
+                    .run_if(not(resource_exists::<Versus>)),
+            )
+            .add_systems(
+                OnEnter(KillcamState::Playing),
+                (pause_physics, save_live_scenery, spawn_killcam_ball),
+            )
+            .add_systems(
+                OnExit(KillcamState::Playing),
+                (resume_physics, restore_live_scenery, despawn_killcam_ball),
+            )
+            .add_systems(
+                Update,
+                play_killcam
+                    .after(CameraFollowSet)
+                    .run_if(in_state(KillcamState::Playing)),
+            );
+    }
+}
+
+/// How much of the run before death is kept.
+const KILLCAM_SECONDS: f32 = 3.0;
+const KILLCAM_SPEED: f32 = 0.25;
+
+#[derive(States, Debug, Clone, Eq, PartialEq, Hash)]
+pub enum KillcamState {
+    Idle,
+    Playing,
+}
+
+struct KillcamFrame {
+    player: Option<Vec2>,
+    scenery: Vec<(Entity, Vec2)>,
+}
+
+/// Rolling window of the last few seconds of the run.
+#[derive(Resource, Default)]
+struct KillcamBuffer {
+    frames: VecDeque<KillcamFrame>,
+    capacity: usize,
+    sample_interval: f32,
+    playhead: f32,
+    /// Where the scenery really is, put back once the killcam is over.
+    live_scenery: Vec<(Entity, Vec2)>,
+}
+
+#[derive(Component)]
+struct KillcamBall;
+
+fn reset_killcam_buffer(mut buffer: ResMut<KillcamBuffer>, time: Res<Time<Fixed>>) {
+    buffer.sample_interval = time.timestep().as_secs_f32();
+    buffer.capacity = (KILLCAM_SECONDS / buffer.sample_interval).ceil() as usize;
+    buffer.frames.clear();
+    buffer.playhead = 0.0;
+}
+
+fn scenery_positions(
+    scenery_query: &Query<(Entity, &Transform), Or<(With<Spikes>, With<Platform>)>>,
+) -> Vec<(Entity, Vec2)> {
+    scenery_query
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation.truncate()))
+        .collect()
+}
+
+fn record_killcam_frame(
+    mut buffer: ResMut<KillcamBuffer>,
+    player_query: Query<&Transform, With<Player>>,
+    scenery_query: Query<(Entity, &Transform), Or<(With<Spikes>, With<Platform>)>>,
+) {
+    let frame = KillcamFrame {
+        player: player_query
+            .get_single()
+            .ok()
+            .map(|transform| transform.translation.truncate()),
+        scenery: scenery_positions(&scenery_query),
+    };
+
+    if buffer.frames.len() >= buffer.capacity {
+        buffer.frames.pop_front();
+    }
+    buffer.frames.push_back(frame);
+}
+
+fn clear_killcam_on_rewind(mut rewinds: EventReader<Rewound>, mut buffer: ResMut<KillcamBuffer>) {
+    if rewinds.read().count() > 0 {
+        buffer.frames.clear();
+        buffer.playhead = 0.0;
+    }
+}
+
+fn start_killcam(mut next_state: ResMut<NextState<KillcamState>>) {
+    next_state.set(KillcamState::Playing);
+}
+
+fn pause_physics(mut time: ResMut<Time<Physics>>) {
+    time.pause();
+}
+
+fn resume_physics(mut time: ResMut<Time<Physics>>) {
+    time.unpause();
+}
+
+fn save_live_scenery(
+    mut buffer: ResMut<KillcamBuffer>,
+    scenery_query: Query<(Entity, &Transform), Or<(With<Spikes>, With<Platform>)>>,
+) {
+    buffer.live_scenery = scenery_positions(&scenery_query);
+}
+
+/// The killcam moves the real scenery back in time, so it has to be put back
+/// before physics picks the rewound positions up.
+fn restore_live_scenery(
+    mut buffer: ResMut<KillcamBuffer>,
+    mut scenery_query: Query<&mut Transform, Or<(With<Spikes>, With<Platform>)>>,
+) {
+    for (entity, position) in buffer.live_scenery.drain(..) {
+        if let Ok(mut transform) = scenery_query.get_mut(entity) {
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+        }
+    }
+}
+
+fn spawn_killcam_ball(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    look: Res<PlayerLook>,
+    tuning: Res<Tuning>,
+) {
+    commands
+        .spawn((
+            KillcamBall,
+            SpatialBundle {
+                transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            spawn_ball_look(
+                parent,
+                &asset_server,
+                &mut meshes,
+                &mut materials,
+                &look,
+                tuning.player_radius,
+            );
+        });
+}
+
+fn despawn_killcam_ball(mut commands: Commands, query: Query<Entity, With<KillcamBall>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn play_killcam(
+    mut buffer: ResMut<KillcamBuffer>,
+    time: Res<Time>,
+    mut ball_query: Query<(&mut Transform, &mut Visibility), With<KillcamBall>>,
+    mut scenery_query: Query<
+        &mut Transform,
+        (
+            Or<(With<Spikes>, With<Platform>)>,
+            Without<KillcamBall>,
+            Without<Camera>,
+        ),
+    >,
+    mut camera_query: Query<&mut Transform, (With<Camera>, Without<KillcamBall>)>,
+    mut next_state: ResMut<NextState<KillcamState>>,
+) {
+    if buffer.sample_interval > 0.0 {
+        buffer.playhead += time.delta_seconds() * KILLCAM_SPEED / buffer.sample_interval;
+    }
+
+    let Some(frame) = buffer.frames.get(buffer.playhead as usize) else {
+        next_state.set(KillcamState::Idle);
+        return;
+    };
+
+    if let Ok((mut ball_transform, mut visibility)) = ball_query.get_single_mut() {
+        match frame.player {
+            Some(position) => {
+                ball_transform.translation.x = position.x;
+                ball_transform.translation.y = position.y;
+                *visibility = Visibility::Visible;
+
+                if let Ok(mut camera_transform) = camera_query.get_single_mut() {
+                    camera_transform.translation.x = position.x;
+                }
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    for (entity, position) in &frame.scenery {
+        if let Ok(mut transform) = scenery_query.get_mut(*entity) {
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actions::Action,
+        harness::{GameHarness, SPIKES_CATCH_IDLE_PLAYER_SECONDS},
+    };
+
+    fn scenery(harness: &mut GameHarness) -> Vec<(Entity, Vec2)> {
+        let world = harness.world_mut();
+        world
+            .query_filtered::<(Entity, &Position), Or<(With<Spikes>, With<Platform>)>>()
+            .iter(world)
+            .map(|(entity, position)| (entity, position.0))
+            .collect()
+    }
+
+    #[test]
+    fn skipping_the_killcam_puts_the_scenery_back() {
+        let mut harness = GameHarness::new();
+
+        harness.start_run();
+        assert!(
+            harness.run_until(SPIKES_CATCH_IDLE_PLAYER_SECONDS, |harness| {
+                harness.killcam_state() == KillcamState::Playing
+            })
+        );
+        let live = scenery(&mut harness);
+        harness.run_ticks(10);
+        harness.press(Action::Confirm);
+
+        assert_eq!(harness.killcam_state(), KillcamState::Idle);
+        // Physics has run for the two ticks of the press since.
+        let tolerance = 2.0 * Tuning::default().sink_speed / 64.0 + 1.0;
+        let after = scenery(&mut harness);
+        for (entity, position) in live {
+            let (_, now) = after.iter().find(|(other, _)| *other == entity).unwrap();
+            assert!(
+                now.distance(position) < tolerance,
+                "{:?} moved from {} to {}",
+                entity,
+                position,
+                now
+            );
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    events::RunEnded,
+    player::PlayerState,
+    replay::{Replay, RunRecorded},
+    seed::CourseSeed,
+    GameState,
+};
+
+/// Sends finished runs to a `leaderboard_server` and fetches the best runs
+/// on the current course for the main menu. The server re-simulates each
+/// replay before accepting its score.
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Leaderboard::from_env())
+            .init_resource::<FinishedRun>()
+            .add_systems(OnEnter(GameState::MainMenu), fetch_top_scores)
+            // A rewind carries a dead run on, so it is only submitted once
+            // the player leaves the game over screen.
+            .add_systems(OnExit(GameState::Playing), submit_finished_run)
+            .add_systems(OnExit(PlayerState::Dead), forget_finished_run)
+            .add_systems(
+                Update,
+                (
+                    fetch_top_scores
+                        .run_if(in_state(GameState::MainMenu))
+                        .run_if(resource_changed::<CourseSeed>),
+                    keep_finished_run,
+                    receive_replies,
+                ),
+            );
+    }
+}
+
+/// Overrides [`DEFAULT_ADDRESS`] for the leaderboard server.
+const SERVER_ADDRESS_VAR: &str = "RAMP_BALL_LEADERBOARD";
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:7879";
+
+/// How many runs the main menu lists.
+const TOP_SCORES: usize = 5;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Verifying a run means simulating it again, which can take a while.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq)]
+struct ScoreEntry {
+    name: String,
+    distance: f32,
+}
+
+enum Reply {
+    Top {
+        seed: u64,
+        result: Result<Vec<ScoreEntry>, String>,
+    },
+    Submitted(Result<String, String>),
+}
+
+/// The score and replay of the run that last ended, until it's submitted.
+#[derive(Resource, Default)]
+struct FinishedRun {
+    distance: Option<f32>,
+    replay: Option<Replay>,
+}
+
+/// What is known about the leaderboard. Requests run on threads of their
+/// own and report back through `replies`.
+#[derive(Resource)]
+pub struct Leaderboard {
+    address: String,
+    name: String,
+    sender: Sender<Reply>,
+    replies: Mutex<Receiver<Reply>>,
+    fetching: Option<u64>,
+    /// Best runs on the seed they were fetched for.
+    top: Option<(u64, Vec<ScoreEntry>)>,
+    /// How the last request went, for the player.
+    status: String,
+}
+
+impl Leaderboard {
+    fn from_env() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            address: std::env::var(SERVER_ADDRESS_VAR)
+                .unwrap_or_else(|_| DEFAULT_ADDRESS.to_string()),
+            name: std::env::var("USER").unwrap_or_else(|_| "player".to_string()),
+            sender,
+            replies: Mutex::new(receiver),
+            fetching: None,
+            top: None,
+            status: String::new(),
+        }
+    }
+
+    fn fetch(&mut self, seed: CourseSeed) {
+        if self.fetching == Some(seed.0) {
+            return;
+        }
+        self.fetching = Some(seed.0);
+
+        let address = self.address.clone();
+        let sender = self.sender.clone();
+        let path = format!("/scores?seed={}&limit={}", seed.0, TOP_SCORES);
+
+        thread::spawn(move || {
+            let result = request(&address, "GET", &path, "").and_then(|(status, body)| {
+                if status == 200 {
+                    Ok(parse_top_scores(&body))
+                } else {
+                    Err(body)
+                }
+            });
+            let _ = sender.send(Reply::Top {
+                seed: seed.0,
+                result,
+            });
+        });
+    }
+
+    /// The main menu's listing for `seed`.
+    pub fn describe(&self, seed: CourseSeed) -> String {
+        let mut lines = Vec::new();
+
+        match &self.top {
+            Some((top_seed, entries)) if *top_seed == seed.0 && !entries.is_empty() => {
+                lines.push("Best on this course".to_string());
+                for (rank, entry) in entries.iter().enumerate() {
+                    lines.push(format!(
+                        "{}. {} {:.0}m",
+                        rank + 1,
+                        entry.name,
+                        entry.distance
+                    ));
+                }
+            }
+            Some((top_seed, _)) if *top_seed == seed.0 => {
+                lines.push("No runs on this course yet".to_string());
+            }
+            _ => {}
+        }
+
+        if !self.status.is_empty() {
+            lines.push(self.status.clone());
+        }
+
+        lines.join("\n")
+    }
+}
+
+fn fetch_top_scores(mut leaderboard: ResMut<Leaderboard>, seed: Res<CourseSeed>) {
+    leaderboard.fetch(*seed);
+}
+
+fn keep_finished_run(
+    mut run_ended: EventReader<RunEnded>,
+    mut run_recorded: EventReader<RunRecorded>,
+    mut finished: ResMut<FinishedRun>,
+) {
+    if let Some(ended) = run_ended.read().last() {
+        finished.distance = Some(ended.distance);
+    }
+    if let Some(RunRecorded(replay)) = run_recorded.read().last() {
+        finished.replay = Some(replay.clone());
+    }
+}
+
+/// The run was rewound, so it isn't over.
+fn forget_finished_run(mut finished: ResMut<FinishedRun>) {
+    *finished = FinishedRun::default();
+}
+
+/// Submits the run once both its score and its replay are in. Runs without
+/// a replay, such as versus matches, aren't submitted.
+fn submit_finished_run(mut finished: ResMut<FinishedRun>, mut leaderboard: ResMut<Leaderboard>) {
+    let FinishedRun {
+        distance: Some(distance),
+        replay: Some(replay),
+    } = std::mem::take(&mut *finished)
+    else {
+        return;
+    };
+
+    let body = format!(
+        "name {}\nseed {}\ndistance {}\nreplay {}\n",
+        leaderboard.name,
+        replay.seed,
+        distance,
+        to_hex(&replay.encode())
+    );
+    let address = leaderboard.address.clone();
+    let sender = leaderboard.sender.clone();
+    leaderboard.status = "Verifying run...".to_string();
+
+    thread::spawn(move || {
+        let result =
+            request(&address, "POST", "/scores", &body).and_then(|(status, body)| match status {
+                201 => Ok(body),
+                _ => Err(body),
+            });
+        let _ = sender.send(Reply::Submitted(result));
+    });
+}
+
+fn receive_replies(mut leaderboard: ResMut<Leaderboard>, seed: Res<CourseSeed>) {
+    let replies: Vec<Reply> = leaderboard.replies.lock().unwrap().try_iter().collect();
+
+    for reply in replies {
+        match reply {
+            Reply::Top {
+                seed: top_seed,
+                result,
+            } => {
+                if leaderboard.fetching == Some(top_seed) {
+                    leaderboard.fetching = None;
+                }
+                match result {
+                    Ok(entries) => leaderboard.top = Some((top_seed, entries)),
+                    Err(error) => {
+                        warn!("Fetching the leaderboard failed: {}", error);
+                        leaderboard.status = "Leaderboard offline".to_string();
+                    }
+                }
+            }
+            Reply::Submitted(Ok(rank)) => {
+                leaderboard.status = format!("Run verified, {}", rank);
+                // The run may have made the list.
+                leaderboard.fetch(*seed);
+            }
+            Reply::Submitted(Err(error)) => {
+                warn!("Leaderboard didn't take the run: {}", error);
+                leaderboard.status = format!("Run not accepted: {}", error);
+            }
+        }
+    }
+}
+
+/// One HTTP/1.1 request. Returns the status code and body.
+fn request(address: &str, method: &str, path: &str, body: &str) -> Result<(u16, String), String> {
+    let socket_address = address
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .ok_or_else(|| format!("can't resolve {}", address))?;
+
+    let mut stream = TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT)
+        .map_err(|error| format!("can't reach {}: {}", address, error))?;
+    let _ = stream.set_read_timeout(Some(REPLY_TIMEOUT));
+
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        address,
+        body.len(),
+        body
+    )
+    .map_err(|error| error.to_string())?;
+
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|error| error.to_string())?;
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or("malformed response")?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or("malformed response")?;
+
+    Ok((status, body.to_string()))
+}
+
+fn parse_top_scores(body: &str) -> Vec<ScoreEntry> {
+    body.lines()
+        .filter_map(|line| {
+            let (distance, name) = line.split_once('\t')?;
+            Some(ScoreEntry {
+                name: name.to_string(),
+                distance: distance.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        net::TcpListener,
+        time::Instant,
+    };
+
+    use super::*;
+    use crate::{harness::GameHarness, rewind::RewindRequest};
+
+    /// Counts the runs sent to `listener` within a second.
+    fn count_submissions(listener: &TcpListener) -> usize {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let mut submissions = 0;
+
+        while Instant::now() < deadline {
+            let Ok((stream, _)) = listener.accept() else {
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            };
+            stream.set_nonblocking(false).unwrap();
+            let mut request_line = String::new();
+            BufReader::new(stream).read_line(&mut request_line).unwrap();
+            if request_line.starts_with("POST /scores") {
+                submissions += 1;
+            }
+        }
+
+        submissions
+    }
+
+    #[test]
+    fn a_rewound_run_is_submitted_once_after_the_game_over_screen() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+
+        let mut harness = GameHarness::new();
+        harness.world_mut().resource_mut::<Leaderboard>().address =
+            listener.local_addr().unwrap().to_string();
+
+        harness.start_run();
+        harness.run_seconds(1.0);
+        harness.teleport_player(Vec2::new(0.0, -2000.0));
+        harness.tick();
+        harness.world_mut().send_event(RewindRequest);
+        harness.run_ticks(2);
+        assert_eq!(harness.player_state(), PlayerState::Alive);
+
+        harness.teleport_player(Vec2::new(0.0, -2000.0));
+        harness.tick();
+        assert_eq!(harness.player_state(), PlayerState::Dead);
+        assert_ne!(
+            harness.world().resource::<Leaderboard>().status,
+            "Verifying run..."
+        );
+
+        harness.continue_after_death();
+        assert_eq!(count_submissions(&listener), 1);
+    }
+}
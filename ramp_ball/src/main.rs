@@ -1,91 +1,219 @@
+use std::time::Duration;
+
 use avian2d::prelude::*;
 
 use bevy::{
-    input::touch::TouchPhase,
-    prelude::*,
-    sprite::{Wireframe2dConfig, Wireframe2dPlugin},
-    window::PrimaryWindow,
+    app::ScheduleRunnerPlugin, asset::AssetPlugin, input::InputPlugin, prelude::*,
+    state::app::StatesPlugin,
 };
 
+use achievements::AchievementsPlugin;
+use actions::ActionsPlugin;
+use boost::BoostPlugin;
+use camera::GameCameraPlugin;
+use challenge::ChallengePlugin;
+use coins::CoinsPlugin;
+use contacts::ContactsPlugin;
+use course::CoursePlugin;
+use daily::DailyPlugin;
+use editor::EditorPlugin;
+use events::GameplayEventsPlugin;
+use game_mode::GameModePlugin;
+use ghost::GhostPlugin;
+use high_scores::HighScoresPlugin;
+use killcam::KillcamPlugin;
+use leaderboard::LeaderboardPlugin;
+use missions::MissionsPlugin;
+use online::{HeadlessRacerPlugin, OnlinePlugin};
 use platforms::PlatformsPlugin;
 use player::PlayerPlugin;
+use race_protocol::DEFAULT_ADDRESS;
+use replay::ReplayPlugin;
+use rewind::RewindPlugin;
+use seed::CourseSeed;
+use shop::ShopPlugin;
+use skins::SkinsPlugin;
 use spikes::SpikesPlugin;
+use stats::StatsPlugin;
+use time_trial::TimeTrialPlugin;
+use trajectory::TrajectoryPlugin;
+use tuning::TuningPlugin;
 use ui::GameUiPlugin;
+use verify::verify_replay;
+use versus::VersusPlugin;
+use wallet::WalletPlugin;
 
+mod achievements;
+mod actions;
+mod boost;
+mod camera;
+mod challenge;
+mod coins;
+#[cfg(feature = "dev")]
+mod console;
+mod contacts;
+mod course;
+mod daily;
+#[cfg(feature = "dev")]
+mod dev;
+mod editor;
+mod events;
+mod flags;
+mod game_mode;
+mod ghost;
+#[cfg(test)]
+mod harness;
+mod high_scores;
+mod killcam;
+mod leaderboard;
+mod missions;
+mod online;
 mod platforms;
 mod player;
+mod replay;
+mod rewind;
+mod ron_asset;
+mod save;
+mod seed;
+mod shop;
+mod skins;
 mod spikes;
+mod stats;
+mod time_trial;
+mod trajectory;
+mod tuning;
 mod ui;
+mod verify;
+mod versus;
+mod wallet;
 
 #[derive(States, Debug, Clone, Eq, PartialEq, Hash)]
 enum GameState {
     MainMenu,
     Playing,
-    Paused,
+    Editor,
 }
 
 fn main() {
-    App::new()
-        .add_plugins((
-            DefaultPlugins,
-            Wireframe2dPlugin,
-            PhysicsPlugins::default(),
-            // PhysicsDebugPlugin::default(),
-            PlatformsPlugin,
-            PlayerPlugin,
-            SpikesPlugin,
-            GameUiPlugin,
-        ))
-        .insert_state(GameState::MainMenu)
-        .insert_resource(Gravity(Vec2::NEG_Y * 200.0))
-        .add_systems(Update, (toggle_wireframe, simulate_touch_input))
-        .run();
-}
+    let mut args = std::env::args().skip(1);
 
-fn simulate_touch_input(
-    mut touch_input_events: ResMut<Events<TouchInput>>,
-    mouse_button_inputs: Res<ButtonInput<MouseButton>>,
-    query: Query<(&Window, Entity), With<PrimaryWindow>>,
-) {
-    let (window, window_entity) = query.get_single().unwrap();
-    let cursor_position = window.cursor_position().unwrap_or_default();
-
-    if mouse_button_inputs.just_pressed(MouseButton::Left) {
-        touch_input_events.send(TouchInput {
-            window: window_entity,
-            phase: TouchPhase::Started,
-            position: cursor_position,
-            id: 0,
-            force: None,
-        });
-    }
+    match args.next().as_deref() {
+        // `--headless-race [address]` joins an online race as a bot, without
+        // a window.
+        Some("--headless-race") => {
+            let address = args.next().unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+            let mut app = headless_app();
+            app.add_plugins(HeadlessRacerPlugin { address });
+            app.run();
+            return;
+        }
+        // `--verify-replay <file>` re-simulates a replay and prints how the
+        // run ended.
+        Some("--verify-replay") => {
+            let Some(path) = args.next() else {
+                eprintln!("usage: ramp_ball --verify-replay <file>");
+                std::process::exit(2);
+            };
 
-    if mouse_button_inputs.pressed(MouseButton::Left) {
-        touch_input_events.send(TouchInput {
-            window: window_entity,
-            phase: TouchPhase::Moved,
-            position: cursor_position,
-            id: 0,
-            force: None,
-        });
-    }
+            let outcome = std::fs::read(&path)
+                .map_err(|error| format!("can't read {}: {}", path, error))
+                .and_then(|bytes| verify_replay(&bytes).map_err(|error| error.to_string()));
 
-    if mouse_button_inputs.just_released(MouseButton::Left) {
-        touch_input_events.send(TouchInput {
-            window: window_entity,
-            phase: TouchPhase::Ended,
-            position: cursor_position,
-            id: 0,
-            force: None,
-        });
+            match outcome {
+                Ok(outcome) => println!("{}", outcome),
+                Err(error) => {
+                    eprintln!("{}", error);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        _ => {}
     }
+
+    let mut app = App::new();
+
+    app.add_plugins((DefaultPlugins, PhysicsPlugins::default(), GamePlugin));
+
+    #[cfg(feature = "dev")]
+    app.add_plugins(dev::DevToolsPlugin);
+
+    app.run();
 }
 
-fn toggle_wireframe(
-    mut wireframe_config: ResMut<Wireframe2dConfig>,
-    keyboard: Res<ButtonInput<KeyCode>>,
-) {
-    if keyboard.just_pressed(KeyCode::Space) {
-        wireframe_config.global = !wireframe_config.global;
+/// Frame length of the headless app, one fixed physics step.
+const HEADLESS_FRAME: Duration = Duration::from_micros(15625);
+
+/// The whole game without a window or renderer.
+fn headless_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins((
+        MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(HEADLESS_FRAME)),
+        AssetPlugin::default(),
+        StatesPlugin,
+        InputPlugin,
+        TransformPlugin,
+        HierarchyPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<ColorMaterial>()
+    .init_asset::<Image>()
+    .add_plugins((PhysicsPlugins::default(), GamePlugin));
+
+    // Split screen and touch input read the window size.
+    app.world_mut().spawn(Window::default());
+
+    app
+}
+
+/// Everything that makes up the game, without the windowing, rendering and
+/// physics plugins it runs on. Shared with the headless app.
+struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            (
+                TuningPlugin,
+                GameplayEventsPlugin,
+                ActionsPlugin,
+                ContactsPlugin,
+                PlatformsPlugin,
+                PlayerPlugin,
+                GameCameraPlugin,
+                SpikesPlugin,
+            ),
+            (
+                TrajectoryPlugin,
+                HighScoresPlugin,
+                GhostPlugin,
+                ReplayPlugin,
+                KillcamPlugin,
+                RewindPlugin,
+                StatsPlugin,
+                AchievementsPlugin,
+            ),
+            (
+                CoursePlugin,
+                EditorPlugin,
+                VersusPlugin,
+                OnlinePlugin,
+                LeaderboardPlugin,
+                DailyPlugin,
+                GameModePlugin,
+                TimeTrialPlugin,
+                ChallengePlugin,
+                CoinsPlugin,
+                WalletPlugin,
+                MissionsPlugin,
+                SkinsPlugin,
+                ShopPlugin,
+                BoostPlugin,
+            ),
+            GameUiPlugin,
+        ))
+        .insert_state(GameState::MainMenu)
+        .init_resource::<CourseSeed>();
     }
 }
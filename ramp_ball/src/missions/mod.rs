@@ -0,0 +1,329 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::{Goal, GoalProgress, Happening},
+    daily::today,
+    events::DeathCause,
+    save::{self, SaveDirectory},
+    seed::SplitMix64,
+    wallet::Wallet,
+    GameState,
+};
+
+/// Short-term missions that pay out coins. A few from [`MISSIONS`] are
+/// active at a time, picked from the date so they rotate daily, and their
+/// goals are tracked from the same [`Happening`]s as achievements.
+pub struct MissionsPlugin;
+
+impl Plugin for MissionsPlugin {
+    fn build(&self, app: &mut App) {
+        let saves = save::directory(app);
+
+        app.insert_resource(saves.load::<MissionBoard>(MISSIONS_FILE))
+            .add_event::<MissionCompleted>()
+            .add_systems(OnEnter(GameState::MainMenu), rotate_missions)
+            .add_systems(Update, record_missions.run_if(on_event::<Happening>()));
+    }
+}
+
+const MISSIONS_FILE: &str = "missions.ron";
+
+/// Mixed into the day number so missions don't rotate in step with the
+/// daily course.
+const MISSION_SALT: u64 = 0x0015_5104_5A17;
+
+pub const ACTIVE_MISSIONS: usize = 3;
+
+pub struct Mission {
+    /// Saved in the board, so it must never change once released.
+    pub id: &'static str,
+    pub description: &'static str,
+    pub goal: Goal,
+    /// Coins paid into the wallet when the mission is done.
+    pub reward: u64,
+}
+
+pub const MISSIONS: &[Mission] = &[
+    Mission {
+        id: "coins_in_run",
+        description: "Collect 50 coins in one run",
+        goal: Goal::CoinsInRun(50),
+        reward: 100,
+    },
+    Mission {
+        id: "spikes_after_300",
+        description: "Die to the spikes after 300m",
+        goal: Goal::DiedTo {
+            cause: DeathCause::Spikes,
+            after: 300.0,
+        },
+        reward: 50,
+    },
+    Mission {
+        id: "fall_after_200",
+        description: "Fall off the course after 200m",
+        goal: Goal::DiedTo {
+            cause: DeathCause::Fell,
+            after: 200.0,
+        },
+        reward: 50,
+    },
+    Mission {
+        id: "reach_500",
+        description: "Reach 500m in one run",
+        goal: Goal::Distance(500.0),
+        reward: 75,
+    },
+    Mission {
+        id: "travel_2000",
+        description: "Travel 2000m in total",
+        goal: Goal::TotalDistance(2000.0),
+        reward: 75,
+    },
+    Mission {
+        id: "play_5",
+        description: "Play 5 runs",
+        goal: Goal::RunsPlayed(5),
+        reward: 25,
+    },
+    Mission {
+        id: "dive_streak_5",
+        description: "Land 5 dives in a row",
+        goal: Goal::DiveStreak(5),
+        reward: 50,
+    },
+    Mission {
+        id: "close_call_20",
+        description: "Survive 20s with the spikes within 300px",
+        goal: Goal::NearSpikes {
+            seconds: 20.0,
+            within: 300.0,
+        },
+        reward: 75,
+    },
+];
+
+/// Sent when a mission is done and its reward paid.
+#[derive(Event, Clone, Copy)]
+pub struct MissionCompleted(pub &'static Mission);
+
+/// The missions that are active on `day`, the same for everyone.
+pub fn missions_for(day: u64) -> [&'static Mission; ACTIVE_MISSIONS] {
+    let mut rng = SplitMix64::new(day ^ MISSION_SALT);
+    let mut order: Vec<usize> = (0..MISSIONS.len()).collect();
+
+    // The first few steps of a Fisher-Yates shuffle.
+    for i in 0..ACTIVE_MISSIONS {
+        let j = i + (rng.next_u64() % (order.len() - i) as u64) as usize;
+        order.swap(i, j);
+    }
+
+    std::array::from_fn(|i| &MISSIONS[order[i]])
+}
+
+/// The day's missions and how far each has got, saved locally.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MissionBoard {
+    /// Days since the Unix epoch the missions were picked for.
+    day: u64,
+    progress: GoalProgress,
+    completed: BTreeSet<String>,
+}
+
+impl MissionBoard {
+    pub fn active(&self) -> [&'static Mission; ACTIVE_MISSIONS] {
+        missions_for(self.day)
+    }
+
+    /// Moves on to `day`'s missions, dropping the last day's progress.
+    fn rotate(&mut self, day: u64) {
+        if self.day != day {
+            *self = MissionBoard { day, ..default() };
+        }
+    }
+
+    /// Moves the active missions along, returning the ones this completed.
+    fn observe(&mut self, happening: Happening) -> Vec<&'static Mission> {
+        let mut completed = Vec::new();
+
+        for mission in self.active() {
+            if self.completed.contains(mission.id) {
+                continue;
+            }
+
+            if self.progress.advance(mission.id, mission.goal, happening) {
+                self.completed.insert(mission.id.to_string());
+                completed.push(mission);
+            }
+        }
+
+        completed
+    }
+
+    /// One line per active mission. `live` shows how far per-run missions
+    /// have got in the current run rather than their best.
+    pub fn describe(&self, live: bool) -> String {
+        self.active()
+            .iter()
+            .map(|mission| {
+                let target = mission.goal.target();
+                let progress = if live {
+                    self.progress.current(mission.id, mission.goal)
+                } else {
+                    self.progress.best(mission.id)
+                };
+
+                let status = if self.completed.contains(mission.id) {
+                    "Done".to_string()
+                } else {
+                    format!("{:.0}/{:.0}", progress.min(target), target)
+                };
+                format!(
+                    "{}  {}  +{} coins",
+                    mission.description, status, mission.reward
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Also saves the progress made in the last run; completions are saved
+/// straight away.
+fn rotate_missions(mut board: ResMut<MissionBoard>, saves: Res<SaveDirectory>) {
+    board.rotate(today());
+    saves.store(MISSIONS_FILE, &*board);
+}
+
+fn record_missions(
+    mut happenings: EventReader<Happening>,
+    mut board: ResMut<MissionBoard>,
+    mut wallet: ResMut<Wallet>,
+    mut mission_completed: EventWriter<MissionCompleted>,
+    saves: Res<SaveDirectory>,
+) {
+    for happening in happenings.read() {
+        for mission in board.observe(*happening) {
+            info!("Mission complete: {}", mission.description);
+
+            // Saved as done before paying, so a crash in between can't pay
+            // the same mission twice.
+            match saves.try_store(MISSIONS_FILE, &*board) {
+                Ok(()) => {
+                    if let Err(error) = wallet.deposit(mission.reward, &saves) {
+                        error!("Failed to pay out {}: {}", mission.description, error);
+                    }
+                }
+                Err(error) => error!("Failed to save {}: {}", MISSIONS_FILE, error),
+            }
+            mission_completed.send(MissionCompleted(mission));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_day_has_distinct_missions_that_rotate() {
+        let today = missions_for(20_745);
+        let ids: BTreeSet<_> = today.iter().map(|mission| mission.id).collect();
+        assert_eq!(ids.len(), ACTIVE_MISSIONS);
+
+        let again: Vec<_> = missions_for(20_745).iter().map(|m| m.id).collect();
+        assert_eq!(again, today.iter().map(|m| m.id).collect::<Vec<_>>());
+
+        let rotated = (20_746..20_756).any(|day| {
+            missions_for(day)
+                .iter()
+                .map(|m| m.id)
+                .collect::<BTreeSet<_>>()
+                != ids
+        });
+        assert!(rotated);
+    }
+
+    #[test]
+    fn missions_complete_once_and_reset_with_the_day() {
+        // Find a day with the run-count mission active.
+        let day = (0..1000)
+            .find(|day| missions_for(*day).iter().any(|m| m.id == "play_5"))
+            .unwrap();
+        let mut board = MissionBoard::default();
+        board.rotate(day);
+
+        let mut completed = Vec::new();
+        for _ in 0..6 {
+            completed.extend(board.observe(Happening::RunStarted));
+        }
+        assert_eq!(
+            completed
+                .iter()
+                .map(|mission| mission.id)
+                .collect::<Vec<_>>(),
+            vec!["play_5"]
+        );
+
+        board.rotate(day + 1);
+        assert!(board.completed.is_empty());
+        assert_eq!(board.progress.best("play_5"), 0.0);
+    }
+
+    #[test]
+    fn completed_missions_are_saved_before_they_pay_out() {
+        let day = (0..1000)
+            .find(|day| missions_for(*day).iter().any(|m| m.id == "play_5"))
+            .unwrap();
+        let reward = MISSIONS.iter().find(|m| m.id == "play_5").unwrap().reward;
+        let saves = SaveDirectory::default();
+
+        let mut app = App::new();
+        app.add_event::<Happening>()
+            .add_event::<MissionCompleted>()
+            .insert_resource(MissionBoard { day, ..default() })
+            .init_resource::<Wallet>()
+            .insert_resource(saves.clone())
+            .add_systems(Update, record_missions);
+
+        for _ in 0..5 {
+            app.world_mut().send_event(Happening::RunStarted);
+        }
+        app.update();
+
+        let saved = saves.load::<MissionBoard>(MISSIONS_FILE);
+        assert!(saved.completed.contains("play_5"));
+        assert_eq!(app.world().resource::<Wallet>().coins(), reward);
+        assert_eq!(saves.load::<Wallet>("wallet.ron").coins(), reward);
+    }
+
+    #[test]
+    fn dying_to_the_right_thing_late_enough_counts() {
+        let goal = Goal::DiedTo {
+            cause: DeathCause::Spikes,
+            after: 300.0,
+        };
+        let mut progress = GoalProgress::default();
+
+        let early = Happening::RunEnded {
+            distance: 250.0,
+            cause: DeathCause::Spikes,
+        };
+        let fell = Happening::RunEnded {
+            distance: 400.0,
+            cause: DeathCause::Fell,
+        };
+        assert!(!progress.advance("spikes", goal, early));
+        assert!(!progress.advance("spikes", goal, fell));
+
+        let late = Happening::RunEnded {
+            distance: 350.0,
+            cause: DeathCause::Spikes,
+        };
+        assert!(progress.advance("spikes", goal, late));
+    }
+}
@@ -0,0 +1,638 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    net::{Shutdown, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use avian2d::prelude::*;
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+use race_protocol::{ClientMessage, PlayerId, ServerMessage, DEFAULT_ADDRESS, PROTOCOL_VERSION};
+
+use crate::{
+    game_mode::ranked_mode,
+    player::{apply_dive_gravity, DiveInput, Player, PlayerState, TravelDistanceMeters},
+    seed::CourseSeed,
+    tuning::Tuning,
+    GameState,
+};
+
+/// Races against other players through a `race_server`. The server picks
+/// the seed and says when to start; each game simulates its own ball and
+/// shows the others as ghosts from the positions the server relays.
+pub struct OnlinePlugin;
+
+impl Plugin for OnlinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<JoinOnlineRace>()
+            .add_systems(OnEnter(GameState::MainMenu), leave_online_race)
+            .add_systems(
+                Update,
+                // Everyone races by the endless rules.
+                join_online_race
+                    .run_if(in_state(GameState::MainMenu))
+                    .run_if(ranked_mode),
+            )
+            .add_systems(
+                Update,
+                receive_server_messages.run_if(resource_exists::<OnlineRace>),
+            )
+            .add_systems(
+                FixedUpdate,
+                send_race_input
+                    .after(apply_dive_gravity)
+                    .run_if(resource_exists::<OnlineRace>)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Alive)),
+            )
+            .add_systems(
+                OnEnter(PlayerState::Dead),
+                report_death.run_if(resource_exists::<OnlineRace>),
+            );
+    }
+}
+
+/// Overrides [`DEFAULT_ADDRESS`] for the main menu's Online button.
+const SERVER_ADDRESS_VAR: &str = "RAMP_BALL_RACE_SERVER";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Fixed ticks between position reports, so about 16 a second.
+const POSITION_INTERVAL: u32 = 4;
+
+/// Connects to a race server and waits in its lobby.
+#[derive(Event)]
+pub struct JoinOnlineRace {
+    pub address: String,
+    pub name: String,
+}
+
+impl JoinOnlineRace {
+    /// The server from the environment, or the local default.
+    pub fn from_env() -> Self {
+        Self {
+            address: std::env::var(SERVER_ADDRESS_VAR)
+                .unwrap_or_else(|_| DEFAULT_ADDRESS.to_string()),
+            name: std::env::var("USER").unwrap_or_else(|_| "player".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RacePhase {
+    Lobby,
+    Racing,
+    Over {
+        winner: Option<PlayerId>,
+    },
+    /// The connection failed, was refused or dropped.
+    Closed(String),
+}
+
+/// Present from joining a race until back in the main menu.
+#[derive(Resource)]
+pub struct OnlineRace {
+    stream: Option<TcpStream>,
+    incoming: Mutex<Receiver<Incoming>>,
+    id: Option<PlayerId>,
+    name: String,
+    players_needed: u32,
+    names: HashMap<PlayerId, String>,
+    /// Distances of the balls that are out, ours included.
+    out: HashMap<PlayerId, f32>,
+    pub phase: RacePhase,
+    tick: u32,
+    diving: bool,
+    /// Put back when leaving, since the server picks the course.
+    previous_seed: CourseSeed,
+}
+
+/// What the connection thread hands over to `receive_server_messages`.
+enum Incoming {
+    /// Connected, with the half of the socket to write to.
+    Connected(TcpStream),
+    ConnectFailed(String),
+    Message(ServerMessage),
+}
+
+impl OnlineRace {
+    fn connect(address: &str, name: &str, previous_seed: CourseSeed) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        let race = Self {
+            stream: None,
+            incoming: Mutex::new(receiver),
+            id: None,
+            name: name.to_string(),
+            players_needed: 0,
+            names: HashMap::new(),
+            out: HashMap::new(),
+            phase: RacePhase::Lobby,
+            tick: 0,
+            diving: false,
+            previous_seed,
+        };
+
+        // Connecting and blocking reads stay off the main thread; what comes
+        // in is picked up each frame by `receive_server_messages`.
+        let address = address.to_string();
+        thread::spawn(move || {
+            let reader = match open_stream(&address) {
+                Ok((stream, reader)) => {
+                    // Nobody is listening if the race was left meanwhile.
+                    if sender.send(Incoming::Connected(stream)).is_err() {
+                        return;
+                    }
+                    reader
+                }
+                Err(error) => {
+                    let _ = sender.send(Incoming::ConnectFailed(error));
+                    return;
+                }
+            };
+
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                match line.parse() {
+                    Ok(message) => {
+                        if sender.send(Incoming::Message(message)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(error) => warn!("Ignoring message from race server: {}", error),
+                }
+            }
+        });
+
+        race
+    }
+
+    fn send(&mut self, message: ClientMessage) {
+        let Some(stream) = &mut self.stream else {
+            return;
+        };
+
+        if let Err(error) = writeln!(stream, "{}", message) {
+            self.close(format!("connection lost: {}", error));
+        }
+    }
+
+    fn close(&mut self, reason: String) {
+        if let Some(stream) = self.stream.take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        if !matches!(self.phase, RacePhase::Over { .. } | RacePhase::Closed(_)) {
+            self.phase = RacePhase::Closed(reason);
+        }
+    }
+
+    /// Still waiting for the race to start.
+    pub fn waiting(&self) -> bool {
+        self.phase == RacePhase::Lobby
+    }
+
+    fn name(&self, id: PlayerId) -> &str {
+        if Some(id) == self.id {
+            return "You";
+        }
+        self.names.get(&id).map_or("?", String::as_str)
+    }
+
+    /// One line describing how the race is going.
+    pub fn status(&self) -> String {
+        match &self.phase {
+            RacePhase::Lobby if self.id.is_none() => "Connecting...".to_string(),
+            RacePhase::Lobby => format!(
+                "Waiting for racers {}/{}",
+                self.names.len(),
+                self.players_needed
+            ),
+            RacePhase::Racing => {
+                let mut out: Vec<_> = self.out.iter().collect();
+                out.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+                let out = out
+                    .into_iter()
+                    .map(|(id, distance)| format!("{} out at {:.0}m", self.name(*id), distance))
+                    .collect::<Vec<_>>();
+                if out.is_empty() {
+                    format!("Racing {} players", self.names.len())
+                } else {
+                    out.join(", ")
+                }
+            }
+            RacePhase::Over { winner: Some(id) } if Some(*id) == self.id => "You win!".to_string(),
+            RacePhase::Over { winner: Some(id) } => format!("{} wins", self.name(*id)),
+            RacePhase::Over { winner: None } => "Nobody wins".to_string(),
+            RacePhase::Closed(reason) => format!("Offline: {}", reason),
+        }
+    }
+}
+
+/// Connects, returning a write and a read half of the socket.
+fn open_stream(address: &str) -> Result<(TcpStream, TcpStream), String> {
+    let socket_address = address
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .ok_or_else(|| format!("can't resolve {}", address))?;
+
+    let stream = TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT)
+        .map_err(|error| format!("can't reach {}: {}", address, error))?;
+    let _ = stream.set_nodelay(true);
+    let reader = stream.try_clone().map_err(|error| error.to_string())?;
+
+    Ok((stream, reader))
+}
+
+/// Another racer's ball, placed where the server last saw it.
+#[derive(Component)]
+struct OpponentBall {
+    id: PlayerId,
+    tick: u32,
+}
+
+fn join_online_race(
+    mut events: EventReader<JoinOnlineRace>,
+    race: Option<Res<OnlineRace>>,
+    seed: Res<CourseSeed>,
+    mut commands: Commands,
+) {
+    let Some(join) = events.read().last() else {
+        return;
+    };
+
+    if race.as_ref().is_some_and(|race| race.waiting()) {
+        return;
+    }
+
+    info!("Joining online race at {}", join.address);
+    let previous_seed = race.map_or(*seed, |race| race.previous_seed);
+    commands.insert_resource(OnlineRace::connect(
+        &join.address,
+        &join.name,
+        previous_seed,
+    ));
+}
+
+fn leave_online_race(
+    mut commands: Commands,
+    race: Option<ResMut<OnlineRace>>,
+    mut seed: ResMut<CourseSeed>,
+    opponents: Query<Entity, With<OpponentBall>>,
+) {
+    if let Some(mut race) = race {
+        race.close("left the race".to_string());
+        *seed = race.previous_seed;
+        commands.remove_resource::<OnlineRace>();
+    }
+
+    for entity in opponents.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn receive_server_messages(
+    mut commands: Commands,
+    mut race: ResMut<OnlineRace>,
+    mut seed: ResMut<CourseSeed>,
+    mut opponents: Query<(Entity, &mut OpponentBall, &mut Transform)>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    tuning: Res<Tuning>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    loop {
+        let incoming = race.incoming.lock().unwrap().try_recv();
+
+        let message = match incoming {
+            Ok(Incoming::Message(message)) => message,
+            Ok(Incoming::Connected(stream)) => {
+                race.stream = Some(stream);
+                let name = race.name.clone();
+                race.send(ClientMessage::Join {
+                    version: PROTOCOL_VERSION,
+                    name,
+                });
+                continue;
+            }
+            Ok(Incoming::ConnectFailed(error)) => {
+                race.close(error);
+                break;
+            }
+            Err(TryRecvError::Empty) => break,
+            Err(TryRecvError::Disconnected) => {
+                race.close("server went away".to_string());
+                break;
+            }
+        };
+
+        match message {
+            ServerMessage::Welcome {
+                id,
+                seed: race_seed,
+                players_needed,
+            } => {
+                race.id = Some(id);
+                let name = race.name.clone();
+                race.names.insert(id, name);
+                race.players_needed = players_needed;
+                *seed = CourseSeed(race_seed);
+            }
+            ServerMessage::Joined { id, name } => {
+                race.names.insert(id, name);
+            }
+            ServerMessage::Left { id } => {
+                race.names.remove(&id);
+                despawn_opponent(&mut commands, &opponents, id);
+            }
+            ServerMessage::Start => {
+                race.phase = RacePhase::Racing;
+                if *state.get() == GameState::MainMenu {
+                    next_state.set(GameState::Playing);
+                }
+            }
+            ServerMessage::Opponent { id, tick, x, y } => {
+                let existing = opponents
+                    .iter_mut()
+                    .find(|(_, opponent, _)| opponent.id == id);
+
+                match existing {
+                    Some((_, mut opponent, mut transform)) => {
+                        // Lines arrive in order, but be safe about stale ones.
+                        if tick >= opponent.tick {
+                            opponent.tick = tick;
+                            transform.translation.x = x;
+                            transform.translation.y = y;
+                        }
+                    }
+                    None => {
+                        commands.spawn((
+                            OpponentBall { id, tick },
+                            MaterialMesh2dBundle {
+                                mesh: Mesh2dHandle(meshes.add(Circle {
+                                    radius: tuning.player_radius,
+                                })),
+                                material: materials.add(Color::hsla(300.0, 1.0, 0.75, 0.35)),
+                                transform: Transform::from_xyz(x, y, -1.0),
+                                ..default()
+                            },
+                        ));
+                    }
+                }
+            }
+            ServerMessage::OpponentOut { id, distance } => {
+                race.out.insert(id, distance);
+                despawn_opponent(&mut commands, &opponents, id);
+            }
+            ServerMessage::RaceOver { winner } => {
+                race.phase = RacePhase::Over { winner };
+                race.close("race over".to_string());
+            }
+            ServerMessage::Error { message } => {
+                warn!("Race server: {}", message);
+                if race.waiting() {
+                    race.close(message);
+                }
+            }
+        }
+    }
+}
+
+fn despawn_opponent(
+    commands: &mut Commands,
+    opponents: &Query<(Entity, &mut OpponentBall, &mut Transform)>,
+    id: PlayerId,
+) {
+    for (entity, opponent, _) in opponents.iter() {
+        if opponent.id == id {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn send_race_input(
+    mut race: ResMut<OnlineRace>,
+    players: Query<(&DiveInput, &Position), With<Player>>,
+) {
+    if race.phase != RacePhase::Racing {
+        return;
+    }
+
+    let Ok((dive_input, position)) = players.get_single() else {
+        return;
+    };
+
+    let tick = race.tick;
+
+    if dive_input.pressed != race.diving {
+        race.diving = dive_input.pressed;
+        race.send(ClientMessage::Input {
+            tick,
+            diving: dive_input.pressed,
+        });
+    }
+
+    if tick.is_multiple_of(POSITION_INTERVAL) {
+        race.send(ClientMessage::Position {
+            tick,
+            x: position.x,
+            y: position.y,
+        });
+    }
+
+    race.tick += 1;
+}
+
+fn report_death(mut race: ResMut<OnlineRace>, distance: Res<TravelDistanceMeters>) {
+    if race.phase != RacePhase::Racing {
+        return;
+    }
+
+    if let Some(id) = race.id {
+        race.out.insert(id, distance.0);
+    }
+
+    let tick = race.tick;
+    race.send(ClientMessage::Dead {
+        tick,
+        distance: distance.0,
+    });
+}
+
+/// Joins a race on startup and plays it without a window, diving whenever
+/// the ball is falling. Lets races be tested with several clients on one
+/// machine.
+pub struct HeadlessRacerPlugin {
+    pub address: String,
+}
+
+impl Plugin for HeadlessRacerPlugin {
+    fn build(&self, app: &mut App) {
+        let address = self.address.clone();
+        let name = format!("bot-{}", std::process::id());
+
+        app.add_systems(Startup, move |mut events: EventWriter<JoinOnlineRace>| {
+            events.send(JoinOnlineRace {
+                address: address.clone(),
+                name: name.clone(),
+            });
+        })
+        .add_systems(
+            FixedUpdate,
+            dive_while_falling
+                .before(apply_dive_gravity)
+                .run_if(in_state(GameState::Playing))
+                .run_if(in_state(PlayerState::Alive)),
+        )
+        .add_systems(Update, report_until_over);
+    }
+}
+
+fn dive_while_falling(mut players: Query<(&LinearVelocity, &mut DiveInput), With<Player>>) {
+    for (velocity, mut dive_input) in players.iter_mut() {
+        dive_input.pressed = velocity.y < 0.0;
+    }
+}
+
+fn report_until_over(
+    race: Option<Res<OnlineRace>>,
+    mut last_status: Local<String>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some(race) = race else {
+        return;
+    };
+
+    let status = race.status();
+    if *last_status != status {
+        println!("{}", status);
+        *last_status = status;
+    }
+
+    match race.phase {
+        RacePhase::Over { .. } => {
+            exit.send(AppExit::Success);
+        }
+        RacePhase::Closed(_) => {
+            exit.send(AppExit::error());
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+    use crate::{actions::Action, harness::GameHarness};
+
+    #[test]
+    fn online_race_runs_on_the_servers_seed_and_reports_back() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let mut harness = GameHarness::new();
+        let own_seed = *harness.world().resource::<CourseSeed>();
+        harness.world_mut().send_event(JoinOnlineRace {
+            address,
+            name: "tester".to_string(),
+        });
+        harness.tick();
+
+        let (mut server, _) = listener.accept().unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        harness.run_until(1.0, |harness| {
+            harness.world().resource::<OnlineRace>().stream.is_some()
+        });
+        let mut lines = BufReader::new(server.try_clone().unwrap()).lines();
+        let mut receive =
+            move || -> ClientMessage { lines.next().unwrap().unwrap().parse().unwrap() };
+
+        assert_eq!(
+            receive(),
+            ClientMessage::Join {
+                version: PROTOCOL_VERSION,
+                name: "tester".to_string()
+            }
+        );
+
+        // Confirm doesn't drop the ball while waiting for the other racer.
+        let welcome = ServerMessage::Welcome {
+            id: 1,
+            seed: 99,
+            players_needed: 2,
+        };
+        writeln!(server, "{}", welcome).unwrap();
+        harness.run_until(1.0, |harness| {
+            harness.world().resource::<CourseSeed>().0 == 99
+        });
+        harness.press(Action::Confirm);
+        assert_eq!(harness.game_state(), GameState::MainMenu);
+
+        writeln!(server, "{}", ServerMessage::Start).unwrap();
+        let started = harness.run_until(1.0, |harness| harness.game_state() == GameState::Playing);
+        assert!(started);
+
+        harness.run_seconds(0.5);
+        assert!(matches!(receive(), ClientMessage::Position { tick: 0, .. }));
+
+        harness.teleport_player(Vec2::new(0.0, -2000.0));
+        harness.tick();
+        let dead = std::iter::repeat_with(&mut receive)
+            .find(|message| matches!(message, ClientMessage::Dead { .. }));
+        assert!(dead.is_some());
+
+        writeln!(server, "{}", ServerMessage::RaceOver { winner: Some(1) }).unwrap();
+        harness.run_until(1.0, |harness| {
+            harness.world().resource::<OnlineRace>().phase != RacePhase::Racing
+        });
+        assert_eq!(
+            harness.world().resource::<OnlineRace>().phase,
+            RacePhase::Over { winner: Some(1) }
+        );
+
+        harness.continue_after_death();
+        assert_eq!(harness.game_state(), GameState::MainMenu);
+        assert!(!harness.world().contains_resource::<OnlineRace>());
+        assert_eq!(*harness.world().resource::<CourseSeed>(), own_seed);
+    }
+
+    #[test]
+    fn an_unreachable_server_closes_the_race_without_blocking() {
+        let address = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().to_string()
+        };
+
+        let mut harness = GameHarness::new();
+        harness.world_mut().send_event(JoinOnlineRace {
+            address,
+            name: "tester".to_string(),
+        });
+        harness.tick();
+        assert!(harness.world().contains_resource::<OnlineRace>());
+
+        let closed = harness.run_until(5.0, |harness| {
+            matches!(
+                harness.world().resource::<OnlineRace>().phase,
+                RacePhase::Closed(_)
+            )
+        });
+        assert!(closed);
+    }
+}
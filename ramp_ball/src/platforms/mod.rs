@@ -1,5 +1,6 @@
 use avian2d::prelude::*;
 use bevy::{
+    asset::LoadState,
     prelude::*,
     render::{
         mesh::{Indices, VertexAttributeValues},
@@ -7,6 +8,8 @@ use bevy::{
     },
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
 };
+use serde::{Deserialize, Serialize};
+
 use lyon::{
     geom::point,
     math::Point,
@@ -16,122 +19,249 @@ use lyon::{
     },
 };
 
-use crate::{player::Player, GameState};
+#[cfg(feature = "dev")]
+use crate::console::{parse_argument, AddConsoleCommand, ConsoleResult};
+use crate::{
+    contacts::terrain_layers,
+    course::Course,
+    events::{PlatformSpawned, PlatformSunk},
+    game_mode::{CourseSource, GameMode},
+    player::Player,
+    seed::{CourseSeed, SplitMix64},
+    tuning::Tuning,
+    GameState,
+};
 
 pub struct PlatformsPlugin;
 
 impl Plugin for PlatformsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            OnEnter(GameState::MainMenu),
-            (despawn_platforms, spawn_initial_platforms).chain(),
-        )
-        .add_systems(
-            Update,
-            (
-                sink_passed_platforms,
-                remove_sunk_platforms,
-                replace_sinking_platforms,
-                stop_rising_platforms,
+        app.insert_resource(NextPlatformIndex(0))
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                (despawn_platforms, spawn_initial_platforms).chain(),
             )
-                .run_if(in_state(GameState::Playing)),
-        );
+            .add_systems(
+                Update,
+                (despawn_platforms, spawn_initial_platforms)
+                    .chain()
+                    .run_if(in_state(GameState::MainMenu))
+                    .run_if(resource_changed::<CourseSeed>.or_else(resource_changed::<GameMode>)),
+            )
+            .add_systems(
+                Update,
+                spawn_loaded_course
+                    .run_if(in_state(GameState::MainMenu))
+                    .run_if(resource_exists::<LoadingCourse>),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    sink_passed_platforms,
+                    remove_sunk_platforms,
+                    replace_sinking_platforms,
+                    stop_rising_platforms,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                apply_platform_tuning.run_if(resource_changed::<Tuning>),
+            );
+
+        #[cfg(feature = "dev")]
+        app.add_console_command("spawn", "spawn <rolling|flat|ramp>", spawn_platform_ahead);
     }
 }
 
-#[derive(Component)]
-struct Platform;
+/// A platform along the course, drawn as a smooth spline through its control
+/// points. The points are relative to the platform's position.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Platform {
+    pub index: u64,
+    pub kind: PlatformKind,
+    pub points: Vec<Vec2>,
+}
+
+impl Platform {
+    /// The platform at `index` along a generated course, shaped by the
+    /// course seed.
+    pub fn generated(seed: CourseSeed, index: u64, kind: PlatformKind) -> Self {
+        Self {
+            index,
+            kind,
+            points: kind.points(seed, index).to_vec(),
+        }
+    }
+}
+
+/// The family of shapes a platform is drawn from. Generated courses only use
+/// rolling platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PlatformKind {
+    #[default]
+    Rolling,
+    Flat,
+    Ramp,
+}
+
+impl PlatformKind {
+    pub const ALL: [PlatformKind; 3] = [
+        PlatformKind::Rolling,
+        PlatformKind::Flat,
+        PlatformKind::Ramp,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PlatformKind::Rolling => "rolling",
+            PlatformKind::Flat => "flat",
+            PlatformKind::Ramp => "ramp",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn points(self, seed: CourseSeed, index: u64) -> [Vec2; 4] {
+        let mut rng = SplitMix64::new(seed.0 ^ index.wrapping_mul(0xA24B_AED4_963E_E407));
+
+        match self {
+            PlatformKind::Rolling => [
+                Vec2::new(0.0, 0.0),
+                Vec2::new(200.0, rng.range(0.0, 100.0)),
+                Vec2::new(600.0, rng.range(-150.0, -50.0)),
+                Vec2::new(1200.0, rng.range(50.0, 150.0)),
+            ],
+            PlatformKind::Flat => [
+                Vec2::new(0.0, 0.0),
+                Vec2::new(400.0, 0.0),
+                Vec2::new(800.0, 0.0),
+                Vec2::new(1200.0, 0.0),
+            ],
+            PlatformKind::Ramp => [
+                Vec2::new(0.0, 0.0),
+                Vec2::new(400.0, -100.0),
+                Vec2::new(900.0, -50.0),
+                Vec2::new(1200.0, rng.range(150.0, 250.0)),
+            ],
+        }
+    }
+}
 
 #[derive(Component)]
-struct Sinking;
+pub struct Sinking;
 
 #[derive(Component)]
-struct Rising {
-    target_y: f32,
+pub struct Rising {
+    pub target_y: f32,
 }
 
-const RISE_SPEED: f32 = 500.0;
-const SINK_SPEED: f32 = 500.0;
+/// Index of the next platform along the course, used to derive its shape
+/// from the course seed.
+#[derive(Resource)]
+pub struct NextPlatformIndex(pub u64);
+
+/// An authored course on its way from the assets folder.
+#[derive(Resource)]
+struct LoadingCourse(Handle<Course>);
+
+impl NextPlatformIndex {
+    fn take(&mut self) -> u64 {
+        let index = self.0;
+        self.0 += 1;
+        index
+    }
+}
 
 fn sink_passed_platforms(
     players: Query<&Transform, With<Player>>,
-    platforms: Query<(Entity, &Transform, &Collider), (With<Platform>, Without<Sinking>)>,
+    platforms: Query<(Entity, &Platform, &Transform, &Collider), Without<Sinking>>,
+    tuning: Res<Tuning>,
     mut commands: Commands,
+    mut platform_sunk: EventWriter<PlatformSunk>,
 ) {
-    if let Ok(player_transform) = players.get_single() {
-        for (entity, transform, collider) in platforms.iter() {
+    // Platforms stay up until the last ball has passed them.
+    let trailing_x = players
+        .iter()
+        .map(|transform| transform.translation.x)
+        .min_by(f32::total_cmp);
+
+    if let Some(trailing_x) = trailing_x {
+        for (entity, platform, transform, collider) in platforms.iter() {
             let bounding_box = collider.aabb(
                 Vec2::new(transform.translation.x, transform.translation.y),
                 0.0,
             );
 
-            if player_transform.translation.x - bounding_box.max.x > 50.0 {
+            if trailing_x - bounding_box.max.x > tuning.sink_distance {
                 commands
                     .entity(entity)
                     .insert(Sinking)
-                    .insert(LinearVelocity(Vec2::new(0.0, -SINK_SPEED)));
+                    .insert(LinearVelocity(Vec2::new(0.0, -tuning.sink_speed)));
+                platform_sunk.send(PlatformSunk {
+                    entity,
+                    index: platform.index,
+                });
             }
         }
     };
 }
 
 fn remove_sunk_platforms(
-    windows: Query<&Window>,
+    tuning: Res<Tuning>,
     platforms: Query<(Entity, &Transform), (With<Platform>, With<Sinking>)>,
     mut commands: Commands,
 ) {
-    if let Ok(window) = windows.get_single() {
-        for (entity, transform) in platforms.iter() {
-            if transform.translation.x < -window.height() {
-                commands.entity(entity).despawn_recursive();
-            }
+    for (entity, transform) in platforms.iter() {
+        if transform.translation.y < -tuning.course_height {
+            commands.entity(entity).despawn_recursive();
         }
-    };
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn replace_sinking_platforms(
     platforms: Query<&Transform, (With<Platform>, Added<Sinking>)>,
-    windows: Query<&Window>,
+    seed: Res<CourseSeed>,
+    tuning: Res<Tuning>,
+    mode: Res<GameMode>,
+    mut next_index: ResMut<NextPlatformIndex>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut platform_spawned: EventWriter<PlatformSpawned>,
 ) {
-    if let Ok(window) = windows.get_single() {
-        for transform in platforms.iter() {
-            let mesh = create_smooth_spline_mesh_from(&[
-                point(0.0, 0.0),
-                point(200.0, 50.0),
-                point(600.0, -100.0),
-                point(1200.0, 100.0),
-            ]);
-
-            let collider = create_trimesh_collider_from(&mesh);
-
-            let entity = commands
-                .spawn((
-                    Platform,
-                    Rising {
-                        target_y: transform.translation.y,
-                    },
-                    RigidBody::Kinematic,
-                    LinearVelocity(Vec2::new(0.0, RISE_SPEED)),
-                    MaterialMesh2dBundle {
-                        mesh: Mesh2dHandle(meshes.add(mesh)),
-                        material: materials.add(Color::hsl(90.0, 1.0, 0.75)),
-                        transform: Transform::from_xyz(
-                            transform.translation.x + 2800.0,
-                            transform.translation.y - window.height(),
-                            0.0,
-                        ),
-                        ..default()
-                    },
-                ))
-                .id();
-
-            if let Some(collider) = collider {
-                commands.entity(entity).insert(collider);
-            }
-        }
+    // Authored courses end where they end.
+    if mode.rules().course != CourseSource::Seeded {
+        return;
+    }
+
+    for transform in platforms.iter() {
+        let index = next_index.take();
+        let entity = spawn_platform(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            Platform::generated(*seed, index, PlatformKind::Rolling),
+            Vec3::new(
+                transform.translation.x + 2800.0,
+                transform.translation.y - tuning.course_height,
+                0.0,
+            ),
+        );
+
+        commands.entity(entity).insert((
+            Rising {
+                target_y: transform.translation.y,
+            },
+            LinearVelocity(Vec2::new(0.0, tuning.rise_speed)),
+        ));
+        platform_spawned.send(PlatformSpawned { entity, index });
     }
 }
 
@@ -146,46 +276,192 @@ fn stop_rising_platforms(
     }
 }
 
+/// Keeps platforms that are already moving in step with retuned speeds.
+fn apply_platform_tuning(
+    tuning: Res<Tuning>,
+    mut sinking: Query<&mut LinearVelocity, (With<Platform>, With<Sinking>)>,
+    mut rising: Query<&mut LinearVelocity, (With<Platform>, With<Rising>, Without<Sinking>)>,
+) {
+    for mut velocity in sinking.iter_mut() {
+        velocity.y = -tuning.sink_speed;
+    }
+
+    for mut velocity in rising.iter_mut() {
+        if velocity.y > 0.0 {
+            velocity.y = tuning.rise_speed;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn spawn_initial_platforms(
+    seed: Res<CourseSeed>,
+    mode: Res<GameMode>,
+    asset_server: Res<AssetServer>,
+    mut next_index: ResMut<NextPlatformIndex>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut platform_spawned: EventWriter<PlatformSpawned>,
 ) {
+    next_index.0 = 0;
+    commands.remove_resource::<LoadingCourse>();
+
+    if let CourseSource::Authored(path) = mode.rules().course {
+        commands.insert_resource(LoadingCourse(asset_server.load(path)));
+        return;
+    }
+
     for i in 0..2 {
-        let mesh = create_smooth_spline_mesh_from(&[
-            point(0.0, 0.0),
-            point(200.0, 50.0),
-            point(600.0, -100.0),
-            point(1200.0, 100.0),
-        ]);
-
-        let collider = create_trimesh_collider_from(&mesh);
-
-        let entity = commands
-            .spawn((
-                Platform,
-                RigidBody::Kinematic,
-                MaterialMesh2dBundle {
-                    mesh: Mesh2dHandle(meshes.add(mesh)),
-                    material: materials.add(Color::hsl(90.0, 1.0, 0.75)),
-                    transform: Transform::from_xyz(1400.0 * i as f32 - 400.0, -100.0, 0.0),
-                    ..default()
-                },
-            ))
-            .id();
-
-        if let Some(collider) = collider {
-            commands.entity(entity).insert(collider);
+        let index = next_index.take();
+        let entity = spawn_platform(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            Platform::generated(*seed, index, PlatformKind::Rolling),
+            Vec3::new(1400.0 * i as f32 - 400.0, -100.0, 0.0),
+        );
+        platform_spawned.send(PlatformSpawned { entity, index });
+    }
+}
+
+fn spawn_loaded_course(
+    loading: Res<LoadingCourse>,
+    asset_server: Res<AssetServer>,
+    courses: Res<Assets<Course>>,
+    mut next_index: ResMut<NextPlatformIndex>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    match asset_server.get_load_state(&loading.0) {
+        Some(LoadState::Loaded) => {
+            if let Some(course) = courses.get(&loading.0) {
+                spawn_course(
+                    course,
+                    &mut next_index,
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                );
+            }
+            commands.remove_resource::<LoadingCourse>();
+        }
+        Some(LoadState::Failed(error)) => {
+            error!("Failed to load course: {}", error);
+            commands.remove_resource::<LoadingCourse>();
         }
+        _ => {}
     }
 }
 
+/// Lays out a hand-made course's platforms.
+pub fn spawn_course(
+    course: &Course,
+    next_index: &mut NextPlatformIndex,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) {
+    for (index, course_platform) in course.platforms.iter().enumerate() {
+        spawn_platform(
+            commands,
+            meshes,
+            materials,
+            Platform {
+                index: index as u64,
+                kind: course_platform.kind,
+                points: course_platform.points.clone(),
+            },
+            course_platform.position.extend(0.0),
+        );
+    }
+
+    next_index.0 = course.platforms.len() as u64;
+}
+
+pub fn spawn_platform(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    platform: Platform,
+    translation: Vec3,
+) -> Entity {
+    let (mesh, collider) = platform_geometry(&platform.points);
+
+    let entity = commands
+        .spawn((
+            platform,
+            RigidBody::Kinematic,
+            terrain_layers(),
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(meshes.add(mesh)),
+                material: materials.add(Color::hsl(90.0, 1.0, 0.75)),
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+        ))
+        .id();
+
+    if let Some(collider) = collider {
+        commands.entity(entity).insert(collider);
+    }
+
+    entity
+}
+
+/// Builds the mesh and collider of a platform from its control points.
+pub fn platform_geometry(points: &[Vec2]) -> (Mesh, Option<Collider>) {
+    let points: Vec<Point> = points.iter().map(|p| point(p.x, p.y)).collect();
+    let mesh = create_smooth_spline_mesh_from(&points);
+    let collider = create_trimesh_collider_from(&mesh);
+    (mesh, collider)
+}
+
 fn despawn_platforms(platforms: Query<Entity, With<Platform>>, mut commands: Commands) {
     for entity in platforms.iter() {
-        commands.entity(entity).despawn();
+        commands.entity(entity).despawn_recursive();
     }
 }
 
+/// Drops a platform of the given kind just ahead of and below the leading
+/// ball.
+#[cfg(feature = "dev")]
+#[allow(clippy::too_many_arguments)]
+fn spawn_platform_ahead(
+    In(args): In<Vec<String>>,
+    players: Query<&Transform, With<Player>>,
+    seed: Res<CourseSeed>,
+    mut next_index: ResMut<NextPlatformIndex>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut platform_spawned: EventWriter<PlatformSpawned>,
+) -> ConsoleResult {
+    let name: String = parse_argument(&args, "spawn <rolling|flat|ramp>")?;
+    let kind = PlatformKind::from_name(&name)
+        .ok_or_else(|| format!("unknown platform kind '{}'", name))?;
+
+    let Some(player_transform) = players
+        .iter()
+        .max_by(|a, b| a.translation.x.total_cmp(&b.translation.x))
+    else {
+        return Err("there is no player".to_string());
+    };
+
+    let index = next_index.take();
+    let entity = spawn_platform(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        Platform::generated(*seed, index, kind),
+        player_transform.translation + Vec3::new(200.0, -200.0, 0.0),
+    );
+    platform_spawned.send(PlatformSpawned { entity, index });
+
+    Ok(format!("spawned a {} platform", kind.name()))
+}
+
 fn create_smooth_spline_mesh_from(points: &[Point]) -> Mesh {
     let mut builder = Path::builder().with_svg();
 
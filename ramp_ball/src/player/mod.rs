@@ -4,181 +4,529 @@ use bevy::{
     prelude::*,
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
 };
+use serde::Deserialize;
 
-use crate::{spikes::Spikes, GameState};
+#[cfg(feature = "dev")]
+use crate::console::{parse_argument, AddConsoleCommand, ConsoleResult};
+use crate::{
+    actions::{Action, ActionState},
+    contacts::{player_layers, ContactsSet, HazardContact, TerrainContacts},
+    events::{DeathCause, DiveEnded, DiveStarted, RunEnded, RunStarted},
+    flags::{flag_mesh, Flag},
+    game_mode::GameMode,
+    seed::CourseSeed,
+    tuning::Tuning,
+    versus::Versus,
+    GameState,
+};
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(ActiveTouch { id: None })
+        app.add_event::<Knockout>()
             .insert_resource(TravelDistanceMeters(0.0))
+            .insert_resource(GodMode(false))
+            .init_resource::<PlayerLook>()
             .insert_state(PlayerState::Alive)
-            .add_systems(Startup, spawn_camera)
             .add_systems(
                 OnEnter(GameState::Playing),
                 (reset_travel_distance, enable_player_gravity),
             )
             .add_systems(OnEnter(PlayerState::Dead), despawn_player)
-            .add_systems(OnEnter(GameState::MainMenu), spawn_player)
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                (despawn_player, spawn_player).chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                apply_dive_gravity
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Alive)),
+            )
             .add_systems(
                 Update,
                 (
-                    camera_follow_player,
-                    (
-                        gravity_control_system,
-                        handle_player_collisions,
-                        handle_player_fall,
-                        update_travel_distance,
-                    )
-                        .run_if(in_state(GameState::Playing))
-                        .run_if(in_state(PlayerState::Alive)),
+                    apply_player_tuning.run_if(resource_changed::<Tuning>),
+                    apply_player_look.run_if(resource_changed::<PlayerLook>),
                 ),
+            )
+            // Whatever decides how far a run gets happens on the fixed tick,
+            // so a replay simulates to exactly the same result.
+            .add_systems(
+                FixedUpdate,
+                (
+                    update_travel_distance,
+                    (handle_hazard_contacts, handle_player_fall)
+                        .run_if(|god_mode: Res<GodMode>| !god_mode.0),
+                    knock_out_balls,
+                )
+                    .chain()
+                    .after(ContactsSet)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Alive)),
+            )
+            .add_systems(
+                Update,
+                gravity_control_system
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Alive)),
             );
+
+        #[cfg(feature = "dev")]
+        app.add_console_command("god", "god", toggle_god_mode)
+            .add_console_command("teleport", "teleport <x>", teleport_player)
+            .add_console_command("distance", "distance <m>", set_travel_distance);
     }
 }
 
 #[derive(Component)]
 pub struct Player;
 
+/// Which player a ball, and the camera following it, belongs to. Solo runs
+/// only use the first seat.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Seat {
+    #[default]
+    One,
+    Two,
+}
+
+impl Seat {
+    pub fn name(self) -> &'static str {
+        match self {
+            Seat::One => "P1",
+            Seat::Two => "P2",
+        }
+    }
+
+    /// The action that dives this seat's ball. In versus each player gets
+    /// their own half of the keyboard and screen.
+    pub fn dive_action(self, versus: bool) -> Action {
+        match (self, versus) {
+            (Seat::One, false) => Action::Dive,
+            (Seat::One, true) => Action::LeftDive,
+            (Seat::Two, _) => Action::RightDive,
+        }
+    }
+
+    fn ring_color(self) -> Color {
+        match self {
+            Seat::One => Color::hsl(180.0, 1.0, 0.75),
+            Seat::Two => Color::hsl(30.0, 1.0, 0.75),
+        }
+    }
+}
+
+/// The skin every ball wears unless told otherwise.
+pub const DEFAULT_SKIN_TEXTURE: &str = "textures/skins/flag_of_denmark.png";
+
+/// How a ball is drawn: what's on the ball and the color of the ring around
+/// it. The first seat's look is a resource, set from the chosen skin.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct PlayerLook {
+    pub design: BallDesign,
+    pub ring_color: Color,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum BallDesign {
+    /// An image stretched over the ball, relative to the assets folder.
+    Texture(String),
+    /// A flag drawn as a vector mesh.
+    Flag(Flag),
+}
+
+impl PlayerLook {
+    pub fn for_seat(seat: Seat) -> Self {
+        Self {
+            design: BallDesign::Texture(DEFAULT_SKIN_TEXTURE.to_string()),
+            ring_color: seat.ring_color(),
+        }
+    }
+}
+
+impl Default for PlayerLook {
+    fn default() -> Self {
+        Self::for_seat(Seat::One)
+    }
+}
+
+/// While set, spikes and falling don't kill the player.
+#[derive(Resource)]
+pub struct GodMode(pub bool);
+
 #[derive(States, Debug, Clone, Eq, PartialEq, Hash)]
 pub enum PlayerState {
     Alive,
     Dead,
 }
 
-const PLAYER_RADIUS: f32 = 50.0;
+/// The ball's face, and the design drawn on it so it can be rebuilt when
+/// the ball is resized.
+#[derive(Component)]
+struct BallMesh(BallDesign);
+
+#[derive(Component)]
+struct RingMesh;
+
+/// A ball hit the spikes or fell off the screen this frame.
+#[derive(Event)]
+struct Knockout {
+    player: Entity,
+    cause: DeathCause,
+}
 
 fn spawn_player(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    tuning: Res<Tuning>,
+    look: Res<PlayerLook>,
     mut next_state: ResMut<NextState<PlayerState>>,
 ) {
     next_state.set(PlayerState::Alive);
 
-    let skin_texture_handle = asset_server.load("textures/skins/flag_of_denmark.png");
-
-    let skin_material = materials.add(ColorMaterial {
-        texture: Some(skin_texture_handle),
-        ..Default::default()
-    });
-
-    let circle_mesh = Mesh2dHandle(meshes.add(Circle {
-        radius: PLAYER_RADIUS,
-    }));
-
-    let ring_mesh = Mesh2dHandle(meshes.add(Annulus::new(PLAYER_RADIUS - 5.0, PLAYER_RADIUS)));
+    spawn_player_ball(
+        &mut commands,
+        &asset_server,
+        &mut meshes,
+        &mut materials,
+        &tuning,
+        Seat::One,
+        &look,
+        Vec3::ZERO,
+    );
+}
 
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_player_ball(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    tuning: &Tuning,
+    seat: Seat,
+    look: &PlayerLook,
+    translation: Vec3,
+) -> Entity {
     commands
         .spawn((
             Player,
-            TransformBundle::from_transform(Transform::from_xyz(0.0, 0.0, 0.0)),
+            seat,
+            DiveInput::default(),
+            TransformBundle::from_transform(Transform::from_translation(translation)),
             RigidBody::Dynamic,
-            Collider::circle(PLAYER_RADIUS),
+            Collider::circle(tuning.player_radius),
+            player_layers(),
             GravityScale(0.0),
+            CollidingEntities::default(),
+            TerrainContacts::default(),
         ))
         .with_children(|parent| {
-            parent.spawn(MaterialMesh2dBundle {
-                mesh: circle_mesh,
-                material: skin_material,
-                transform: Transform::from_xyz(0.0, 0.0, 0.0),
-                ..default()
-            });
-            parent.spawn(MaterialMesh2dBundle {
-                mesh: ring_mesh,
-                material: materials.add(Color::hsl(180.0, 1.0, 0.75)),
-                transform: Transform::from_xyz(0.0, 0.0, 5.0),
-                ..default()
-            });
-        });
+            spawn_ball_look(
+                parent,
+                asset_server,
+                meshes,
+                materials,
+                look,
+                tuning.player_radius,
+            );
+        })
+        .id()
+}
+
+/// Spawns the ball and ring meshes that dress a ball in `look`.
+pub fn spawn_ball_look(
+    parent: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    look: &PlayerLook,
+    radius: f32,
+) {
+    parent.spawn((
+        BallMesh(look.design.clone()),
+        MaterialMesh2dBundle {
+            mesh: ball_mesh(meshes, &look.design, radius),
+            material: materials.add(ball_material(&look.design, asset_server)),
+            transform: Transform::from_xyz(0.0, 0.0, 0.0),
+            ..default()
+        },
+    ));
+    parent.spawn((
+        RingMesh,
+        MaterialMesh2dBundle {
+            mesh: ring_mesh(meshes, radius),
+            material: materials.add(look.ring_color),
+            transform: Transform::from_xyz(0.0, 0.0, 5.0),
+            ..default()
+        },
+    ));
+}
+
+fn ball_mesh(meshes: &mut Assets<Mesh>, design: &BallDesign, radius: f32) -> Mesh2dHandle {
+    Mesh2dHandle(match design {
+        BallDesign::Texture(_) => meshes.add(Circle { radius }),
+        BallDesign::Flag(flag) => meshes.add(flag_mesh(flag, radius)),
+    })
+}
+
+/// Flags carry their colors in the mesh, so they get a plain white material.
+fn ball_material(design: &BallDesign, asset_server: &AssetServer) -> ColorMaterial {
+    match design {
+        BallDesign::Texture(path) => ColorMaterial {
+            texture: Some(asset_server.load(path.clone())),
+            ..Default::default()
+        },
+        BallDesign::Flag(_) => ColorMaterial::default(),
+    }
+}
+
+fn ring_mesh(meshes: &mut Assets<Mesh>, radius: f32) -> Mesh2dHandle {
+    Mesh2dHandle(meshes.add(Annulus::new(radius - 5.0, radius)))
+}
+
+/// Redresses the first seat's ball when another skin is chosen.
+#[allow(clippy::too_many_arguments)]
+fn apply_player_look(
+    look: Res<PlayerLook>,
+    tuning: Res<Tuning>,
+    asset_server: Res<AssetServer>,
+    players: Query<&Seat, With<Player>>,
+    mut balls: Query<
+        (
+            &Parent,
+            &mut BallMesh,
+            &mut Mesh2dHandle,
+            &Handle<ColorMaterial>,
+        ),
+        Without<RingMesh>,
+    >,
+    rings: Query<(&Parent, &Handle<ColorMaterial>), With<RingMesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let first_seat = |parent: &Parent| {
+        players
+            .get(parent.get())
+            .is_ok_and(|seat| *seat == Seat::One)
+    };
+
+    for (parent, mut ball, mut mesh, handle) in balls.iter_mut() {
+        if !first_seat(parent) {
+            continue;
+        }
+        ball.0 = look.design.clone();
+        *mesh = ball_mesh(&mut meshes, &look.design, tuning.player_radius);
+        if let Some(material) = materials.get_mut(handle) {
+            *material = ball_material(&look.design, &asset_server);
+        }
+    }
+
+    for (parent, handle) in rings.iter() {
+        if !first_seat(parent) {
+            continue;
+        }
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = look.ring_color;
+        }
+    }
+}
+
+/// Resizes the live ball when the player radius is retuned.
+fn apply_player_tuning(
+    tuning: Res<Tuning>,
+    mut players: Query<&mut Collider, With<Player>>,
+    mut balls: Query<(&BallMesh, &mut Mesh2dHandle), Without<RingMesh>>,
+    mut rings: Query<&mut Mesh2dHandle, (With<RingMesh>, Without<BallMesh>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if players.is_empty() {
+        return;
+    }
+
+    let ring = ring_mesh(&mut meshes, tuning.player_radius);
+
+    for mut collider in players.iter_mut() {
+        *collider = Collider::circle(tuning.player_radius);
+    }
+    for (ball, mut mesh) in balls.iter_mut() {
+        *mesh = ball_mesh(&mut meshes, &ball.0, tuning.player_radius);
+    }
+    for mut mesh in rings.iter_mut() {
+        *mesh = ring.clone();
+    }
 }
 
 fn enable_player_gravity(
-    touches: Res<Touches>,
-    mut active_touch: ResMut<ActiveTouch>,
-    mut query: Query<&mut GravityScale, With<Player>>,
+    actions: Res<ActionState>,
+    seed: Res<CourseSeed>,
+    versus: Option<Res<Versus>>,
+    mut players: Query<(&Seat, &mut DiveInput), With<Player>>,
+    mut run_started: EventWriter<RunStarted>,
+    mut dive_started: EventWriter<DiveStarted>,
 ) {
-    if let Ok(mut player_gravity_scale) = query.get_single_mut() {
-        if let Some(touch) = touches.iter().next() {
-            active_touch.id = Some(touch.id());
-            player_gravity_scale.0 = 10.0;
-        } else {
-            player_gravity_scale.0 = 1.0;
+    run_started.send(RunStarted { seed: seed.0 });
+
+    for (seat, mut dive_input) in players.iter_mut() {
+        dive_input.pressed = actions.pressed(seat.dive_action(versus.is_some()));
+        if dive_input.pressed {
+            dive_started.send(DiveStarted);
         }
-    };
+    }
 }
 
-#[derive(Resource)]
-struct ActiveTouch {
-    id: Option<u64>,
+/// Whether a ball's player is currently asking to dive. Read once per fixed
+/// tick, so anything that feeds input (actions, replays) only needs to write
+/// this.
+#[derive(Component, Default)]
+pub struct DiveInput {
+    pub pressed: bool,
 }
 
 fn gravity_control_system(
-    touches: Res<Touches>,
-    mut active_touch: ResMut<ActiveTouch>,
-    mut query: Query<&mut GravityScale, With<Player>>,
+    actions: Res<ActionState>,
+    versus: Option<Res<Versus>>,
+    mut players: Query<(&Seat, &mut DiveInput), With<Player>>,
+    mut dive_started: EventWriter<DiveStarted>,
+    mut dive_ended: EventWriter<DiveEnded>,
 ) {
-    if let Ok(mut player_gravity_scale) = query.get_single_mut() {
-        if let Some(active_id) = active_touch.id {
-            if touches.just_released(active_id) {
-                active_touch.id = None;
-                player_gravity_scale.0 = 1.0;
-            }
-        } else if let Some(just_pressed) = touches.iter_just_pressed().next() {
-            active_touch.id = Some(just_pressed.id());
-            player_gravity_scale.0 = 10.0;
+    for (seat, mut dive_input) in players.iter_mut() {
+        let action = seat.dive_action(versus.is_some());
+
+        if actions.just_pressed(action) {
+            dive_input.pressed = true;
+            dive_started.send(DiveStarted);
+        } else if actions.just_released(action) {
+            dive_input.pressed = false;
+            dive_ended.send(DiveEnded);
         }
-    };
+    }
 }
 
-fn handle_player_collisions(
-    player_query: Query<&CollidingEntities, With<Player>>,
-    spike_query: Query<Entity, With<Spikes>>,
-    mut next_state: ResMut<NextState<PlayerState>>,
+pub fn apply_dive_gravity(
+    tuning: Res<Tuning>,
+    mut query: Query<(&DiveInput, &mut GravityScale), With<Player>>,
 ) {
-    if let Ok(colliding_entities) = player_query.get_single() {
-        for spike in spike_query.iter() {
-            let is_colliding = colliding_entities.0.iter().any(|&entity| entity == spike);
-            if is_colliding {
-                next_state.set(PlayerState::Dead);
-                break;
-            }
-        }
-    };
+    for (dive_input, mut player_gravity_scale) in query.iter_mut() {
+        player_gravity_scale.0 = tuning.gravity_scale(dive_input.pressed);
+    }
 }
 
+fn handle_hazard_contacts(
+    mut hazard_contacts: EventReader<HazardContact>,
+    mut knockouts: EventWriter<Knockout>,
+) {
+    for contact in hazard_contacts.read() {
+        knockouts.send(Knockout {
+            player: contact.player,
+            cause: DeathCause::Spikes,
+        });
+    }
+}
+
+/// Knocks out balls that fell off the bottom of the course, or in modes where falling
+/// doesn't kill, drops them back in from the top.
 fn handle_player_fall(
-    player_query: Query<&Transform, With<Player>>,
-    window_query: Query<&Window>,
+    mut player_query: Query<(Entity, &Transform, &mut Position, &mut LinearVelocity), With<Player>>,
+    tuning: Res<Tuning>,
+    mode: Res<GameMode>,
+    mut knockouts: EventWriter<Knockout>,
+) {
+    for (player, player_transform, mut position, mut velocity) in player_query.iter_mut() {
+        if player_transform.translation.y + tuning.player_radius / 2.0
+            >= -tuning.course_height / 2.0
+        {
+            continue;
+        }
+
+        if mode.rules().falling_kills {
+            knockouts.send(Knockout {
+                player,
+                cause: DeathCause::Fell,
+            });
+        } else {
+            position.y = tuning.course_height / 2.0;
+            velocity.y = 0.0;
+        }
+    }
+}
+
+/// Takes knocked out balls off the course. The run ends once no ball is
+/// left, or in versus, once a single ball is left to win it.
+fn knock_out_balls(
+    mut knockouts: EventReader<Knockout>,
+    players: Query<(Entity, &Seat), With<Player>>,
+    distance: Res<TravelDistanceMeters>,
+    versus: Option<ResMut<Versus>>,
+    mut commands: Commands,
     mut next_state: ResMut<NextState<PlayerState>>,
+    mut run_ended: EventWriter<RunEnded>,
 ) {
-    if let (Ok(player_transform), Ok(window)) =
-        (player_query.get_single(), window_query.get_single())
-    {
-        if player_transform.translation.y + PLAYER_RADIUS / 2.0 < -window.height() / 2.0 {
-            next_state.set(PlayerState::Dead);
+    let mut knocked_out: Vec<&Knockout> = Vec::new();
+    for knockout in knockouts.read() {
+        if !knocked_out.iter().any(|out| out.player == knockout.player) {
+            knocked_out.push(knockout);
+        }
+    }
+
+    let Some(last) = knocked_out.last() else {
+        return;
+    };
+
+    // Fixed ticks later in the same frame still see the run going.
+    if matches!(*next_state, NextState::Pending(PlayerState::Dead)) {
+        return;
+    }
+
+    let remaining: Vec<Seat> = players
+        .iter()
+        .filter(|(entity, _)| !knocked_out.iter().any(|out| out.player == *entity))
+        .map(|(_, seat)| *seat)
+        .collect();
+
+    if let Some(mut versus) = versus {
+        for out in &knocked_out {
+            commands.entity(out.player).despawn_recursive();
+        }
+
+        if !versus.resolve(&remaining) {
+            return;
         }
     }
+
+    next_state.set(PlayerState::Dead);
+    run_ended.send(RunEnded {
+        distance: distance.0,
+        cause: last.cause,
+    });
 }
 
 fn despawn_player(mut commands: Commands, query: Query<Entity, With<Player>>) {
-    if let Ok(player) = query.get_single() {
+    for player in query.iter() {
         commands.entity(player).despawn_recursive();
-    };
+    }
 }
 
 #[derive(Resource)]
 pub struct TravelDistanceMeters(pub f32);
 
+/// Tracks the ball furthest along the course.
 fn update_travel_distance(
     player_query: Query<&Transform, With<Player>>,
     mut distance: ResMut<TravelDistanceMeters>,
 ) {
-    if let Ok(player_transform) = player_query.get_single() {
-        if player_transform.translation.x > distance.0 {
-            distance.0 = player_transform.translation.x / 100.0;
+    let leader = player_query
+        .iter()
+        .map(|transform| transform.translation.x)
+        .max_by(f32::total_cmp);
+
+    if let Some(x) = leader {
+        let meters = x / 100.0;
+        if meters > distance.0 {
+            distance.0 = meters;
         }
     }
 }
@@ -187,21 +535,58 @@ fn reset_travel_distance(mut distance: ResMut<TravelDistanceMeters>) {
     distance.0 = 0.0;
 }
 
-#[derive(Component)]
-pub struct Camera;
+#[cfg(feature = "dev")]
+fn toggle_god_mode(In(_): In<Vec<String>>, mut god_mode: ResMut<GodMode>) -> ConsoleResult {
+    god_mode.0 = !god_mode.0;
+    Ok(format!(
+        "god mode {}",
+        if god_mode.0 { "on" } else { "off" }
+    ))
+}
+
+/// Puts the balls above the course at `x`, at rest.
+#[cfg(feature = "dev")]
+fn teleport_player(
+    In(args): In<Vec<String>>,
+    mut players: Query<(&mut Position, &mut LinearVelocity), With<Player>>,
+) -> ConsoleResult {
+    let x: f32 = parse_argument(&args, "teleport <x>")?;
+
+    if players.is_empty() {
+        return Err("there is no player".to_string());
+    }
 
-fn spawn_camera(mut commands: Commands) {
-    commands.spawn((Camera, Camera2dBundle::default()));
+    for (mut position, mut velocity) in players.iter_mut() {
+        position.0 = Vec2::new(x, 200.0);
+        velocity.0 = Vec2::ZERO;
+    }
+
+    Ok(format!("teleported to {}", x))
 }
 
-fn camera_follow_player(
-    mut camera_query: Query<&mut Transform, With<Camera>>,
-    player_query: Query<&Transform, (With<Player>, Without<Camera>)>,
-) {
-    let player_transform = player_query.get_single();
-    let camera_transform = camera_query.get_single_mut();
+#[cfg(feature = "dev")]
+fn set_travel_distance(
+    In(args): In<Vec<String>>,
+    mut distance: ResMut<TravelDistanceMeters>,
+) -> ConsoleResult {
+    distance.0 = parse_argument(&args, "distance <m>")?;
+    Ok(format!("distance is now {}m", distance.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::GameHarness;
+
+    #[test]
+    fn a_distance_set_from_the_console_sticks() {
+        let mut harness = GameHarness::new();
+        harness.start_run();
+
+        // What the `distance` console command does.
+        harness.world_mut().resource_mut::<TravelDistanceMeters>().0 = 500.0;
+        harness.run_seconds(1.0);
 
-    if let (Ok(player_transform), Ok(mut camera_transform)) = (player_transform, camera_transform) {
-        camera_transform.translation = Vec3::new(player_transform.translation.x, 0.0, 5.0);
+        assert_eq!(harness.world().resource::<TravelDistanceMeters>().0, 500.0);
     }
 }
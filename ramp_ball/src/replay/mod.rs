@@ -0,0 +1,735 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::{
+    boost::RunBoosted,
+    camera::{Camera, CameraFollowSet},
+    editor::EditedRun,
+    game_mode::{ranked_mode, GameMode},
+    player::{apply_dive_gravity, DiveInput, Player, PlayerState},
+    rewind::Rewound,
+    save::SaveDirectory,
+    seed::CourseSeed,
+    tuning::Tuning,
+    versus::Versus,
+    GameState,
+};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_state(ReplayState::Inactive)
+            .init_resource::<ReplayRecorder>()
+            .add_event::<ReplayCommand>()
+            .add_event::<RunRecorded>()
+            .add_systems(OnEnter(GameState::Playing), reset_recorder)
+            .add_systems(OnExit(ReplayState::Viewing), reset_time)
+            .add_systems(
+                OnEnter(PlayerState::Dead),
+                save_last_replay
+                    .run_if(ranked_mode)
+                    .run_if(not(resource_exists::<ReplayPlayback>))
+                    .run_if(not(resource_exists::<EditedRun>))
+                    .run_if(not(resource_exists::<Versus>)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    feed_replay_input
+                        .before(apply_dive_gravity)
+                        .run_if(resource_exists::<ReplayPlayback>),
+                    record_dive_input
+                        .after(apply_dive_gravity)
+                        .run_if(not(resource_exists::<ReplayPlayback>))
+                        .run_if(not(resource_exists::<Versus>)),
+                )
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Alive)),
+            )
+            .add_systems(
+                Update,
+                (
+                    rewind_recorder,
+                    handle_replay_commands,
+                    begin_replay_run
+                        .run_if(in_state(GameState::MainMenu))
+                        .run_if(resource_exists::<ReplayPlayback>),
+                    update_playback_speed.run_if(resource_exists::<ReplayPlayback>),
+                    move_free_camera
+                        .after(CameraFollowSet)
+                        .run_if(in_state(ReplayState::Viewing)),
+                ),
+            );
+    }
+}
+
+/// Bumped whenever the binary layout of a replay changes.
+pub const REPLAY_FORMAT_VERSION: u16 = 4;
+
+const REPLAY_MAGIC: &[u8; 4] = b"RBRP";
+const LAST_REPLAY_FILE: &str = "last.replay";
+
+const PLAYBACK_SPEEDS: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+const SEEK_SPEED: f32 = 8.0;
+const FREE_CAMERA_SPEED: f32 = 800.0;
+
+/// The constants a run was simulated with. A replay only reproduces the same
+/// run if all of these match.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsConstants {
+    pub gravity: f32,
+    pub dive_gravity_scale: f32,
+    pub glide_gravity_scale: f32,
+    pub player_radius: f32,
+    pub spike_speed: f32,
+    pub rise_speed: f32,
+    pub sink_speed: f32,
+    pub sink_distance: f32,
+    pub course_width: f32,
+    pub course_height: f32,
+    pub fixed_timestep: f32,
+}
+
+impl PhysicsConstants {
+    pub fn current(tuning: &Tuning, time: &Time<Fixed>) -> Self {
+        Self {
+            gravity: tuning.gravity,
+            dive_gravity_scale: tuning.dive_gravity_scale,
+            glide_gravity_scale: tuning.glide_gravity_scale,
+            player_radius: tuning.player_radius,
+            spike_speed: tuning.spike_speed,
+            rise_speed: tuning.rise_speed,
+            sink_speed: tuning.sink_speed,
+            sink_distance: tuning.sink_distance,
+            course_width: tuning.course_width,
+            course_height: tuning.course_height,
+            fixed_timestep: time.timestep().as_secs_f32(),
+        }
+    }
+
+    fn to_array(self) -> [f32; 11] {
+        [
+            self.gravity,
+            self.dive_gravity_scale,
+            self.glide_gravity_scale,
+            self.player_radius,
+            self.spike_speed,
+            self.rise_speed,
+            self.sink_speed,
+            self.sink_distance,
+            self.course_width,
+            self.course_height,
+            self.fixed_timestep,
+        ]
+    }
+
+    fn from_array(values: [f32; 11]) -> Self {
+        Self {
+            gravity: values[0],
+            dive_gravity_scale: values[1],
+            glide_gravity_scale: values[2],
+            player_radius: values[3],
+            spike_speed: values[4],
+            rise_speed: values[5],
+            sink_speed: values[6],
+            sink_distance: values[7],
+            course_width: values[8],
+            course_height: values[9],
+            fixed_timestep: values[10],
+        }
+    }
+}
+
+/// A recorded run: everything needed to simulate it again from the start.
+///
+/// Input is stored as the fixed ticks at which the dive input toggled, with
+/// the run starting out not diving.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub game_version: String,
+    pub seed: u64,
+    pub constants: PhysicsConstants,
+    /// Whether the run started with a boost.
+    pub boosted: bool,
+    pub total_ticks: u32,
+    pub dive_toggles: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    Missing,
+    BadMagic,
+    UnsupportedFormat(u16),
+    Truncated,
+    Malformed,
+    IncompatibleConstants,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Missing => write!(f, "no replay has been recorded yet"),
+            ReplayError::BadMagic => write!(f, "not a replay file"),
+            ReplayError::UnsupportedFormat(version) => {
+                write!(f, "unsupported replay format version {}", version)
+            }
+            ReplayError::Truncated => write!(f, "replay file is truncated"),
+            ReplayError::Malformed => write!(f, "replay file is malformed"),
+            ReplayError::IncompatibleConstants => {
+                write!(f, "replay was recorded with different physics constants")
+            }
+        }
+    }
+}
+
+impl Replay {
+    pub fn is_diving_at(&self, tick: u32) -> bool {
+        let toggles = self
+            .dive_toggles
+            .iter()
+            .take_while(|&&toggle| toggle <= tick)
+            .count();
+        toggles % 2 == 1
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.total_ticks as f32 * self.constants.fixed_timestep
+    }
+
+    /// Refuses replays that would not play back the way they were recorded.
+    pub fn check_compatible(&self, constants: &PhysicsConstants) -> Result<(), ReplayError> {
+        if self.constants == *constants {
+            Ok(())
+        } else {
+            Err(ReplayError::IncompatibleConstants)
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(REPLAY_MAGIC);
+        bytes.extend_from_slice(&REPLAY_FORMAT_VERSION.to_le_bytes());
+
+        let game_version = self.game_version.as_bytes();
+        bytes.push(game_version.len().min(u8::MAX as usize) as u8);
+        bytes.extend_from_slice(&game_version[..game_version.len().min(u8::MAX as usize)]);
+
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        for value in self.constants.to_array() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.push(self.boosted as u8);
+
+        write_varint(&mut bytes, self.total_ticks);
+        write_varint(&mut bytes, self.dive_toggles.len() as u32);
+
+        let mut previous = 0;
+        for &tick in &self.dive_toggles {
+            write_varint(&mut bytes, tick - previous);
+            previous = tick;
+        }
+
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let mut reader = ByteReader { bytes };
+
+        if reader.take(4)? != REPLAY_MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+
+        let format_version = u16::from_le_bytes(reader.array()?);
+        if format_version != REPLAY_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedFormat(format_version));
+        }
+
+        let game_version_length = reader.take(1)?[0] as usize;
+        let game_version = String::from_utf8(reader.take(game_version_length)?.to_vec())
+            .map_err(|_| ReplayError::Malformed)?;
+
+        let seed = u64::from_le_bytes(reader.array()?);
+
+        let mut constants = [0.0; 11];
+        for value in constants.iter_mut() {
+            *value = f32::from_le_bytes(reader.array()?);
+        }
+
+        let boosted = match reader.take(1)?[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(ReplayError::Malformed),
+        };
+
+        let total_ticks = reader.varint()?;
+        let toggle_count = reader.varint()?;
+
+        let mut dive_toggles = Vec::new();
+        let mut tick = 0u32;
+        for _ in 0..toggle_count {
+            tick = tick
+                .checked_add(reader.varint()?)
+                .ok_or(ReplayError::Malformed)?;
+            dive_toggles.push(tick);
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(ReplayError::Malformed);
+        }
+
+        Ok(Self {
+            game_version,
+            seed,
+            constants: PhysicsConstants::from_array(constants),
+            boosted,
+            total_ticks,
+            dive_toggles,
+        })
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], ReplayError> {
+        if self.bytes.len() < count {
+            return Err(ReplayError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ReplayError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn varint(&mut self) -> Result<u32, ReplayError> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.take(1)?[0];
+            let bits = (byte & 0x7F) as u32;
+            // The fifth byte only has room for the top four bits.
+            if bits.leading_zeros() < shift {
+                return Err(ReplayError::Malformed);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ReplayError::Malformed)
+    }
+}
+
+pub fn load_last_replay(
+    saves: &SaveDirectory,
+    tuning: &Tuning,
+    time: &Time<Fixed>,
+) -> Result<Replay, ReplayError> {
+    let bytes = saves
+        .read_bytes(LAST_REPLAY_FILE)
+        .ok_or(ReplayError::Missing)?;
+    let replay = Replay::decode(&bytes)?;
+    replay.check_compatible(&PhysicsConstants::current(tuning, time))?;
+    Ok(replay)
+}
+
+#[derive(States, Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ReplayState {
+    Inactive,
+    Viewing,
+}
+
+/// Requests for the replay viewer, sent by the menus and viewer controls.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum ReplayCommand {
+    WatchLast,
+    TogglePause,
+    Faster,
+    Slower,
+    SeekBy(f32),
+    ToggleFreeCamera,
+    Exit,
+}
+
+/// Sent with the replay of every run that was just saved.
+#[derive(Event, Debug, Clone)]
+pub struct RunRecorded(pub Replay);
+
+#[derive(Resource, Default)]
+struct ReplayRecorder {
+    tick: u32,
+    diving: bool,
+    dive_toggles: Vec<u32>,
+}
+
+/// Present while a replay is being watched.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    pub tick: u32,
+    pub seek_target: Option<u32>,
+    pub speed_index: usize,
+    pub paused: bool,
+    pub free_camera: Option<Vec2>,
+    previous_seed: CourseSeed,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay, previous_seed: CourseSeed) -> Self {
+        Self {
+            replay,
+            tick: 0,
+            seek_target: None,
+            speed_index: 2,
+            paused: false,
+            free_camera: None,
+            previous_seed,
+        }
+    }
+
+    pub fn speed(&self) -> f32 {
+        PLAYBACK_SPEEDS[self.speed_index]
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.tick as f32 * self.replay.constants.fixed_timestep
+    }
+}
+
+fn reset_recorder(mut recorder: ResMut<ReplayRecorder>) {
+    *recorder = ReplayRecorder::default();
+}
+
+fn record_dive_input(
+    players: Query<&DiveInput, With<Player>>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    let diving = players
+        .get_single()
+        .map_or(recorder.diving, |dive_input| dive_input.pressed);
+
+    if diving != recorder.diving {
+        recorder.diving = diving;
+        let tick = recorder.tick;
+        recorder.dive_toggles.push(tick);
+    }
+    recorder.tick += 1;
+}
+
+fn rewind_recorder(mut rewinds: EventReader<Rewound>, mut recorder: ResMut<ReplayRecorder>) {
+    for rewound in rewinds.read() {
+        let tick = recorder.tick.saturating_sub(rewound.ticks);
+        recorder.tick = tick;
+        recorder.dive_toggles.retain(|&toggle| toggle < tick);
+        recorder.diving = recorder.dive_toggles.len() % 2 == 1;
+    }
+}
+
+fn save_last_replay(
+    recorder: Res<ReplayRecorder>,
+    seed: Res<CourseSeed>,
+    tuning: Res<Tuning>,
+    time: Res<Time<Fixed>>,
+    boosted: Res<RunBoosted>,
+    mut run_recorded: EventWriter<RunRecorded>,
+    saves: Res<SaveDirectory>,
+) {
+    let replay = Replay {
+        game_version: env!("CARGO_PKG_VERSION").to_string(),
+        seed: seed.0,
+        constants: PhysicsConstants::current(&tuning, &time),
+        boosted: boosted.0,
+        total_ticks: recorder.tick,
+        dive_toggles: recorder.dive_toggles.clone(),
+    };
+
+    saves.write_bytes(LAST_REPLAY_FILE, &replay.encode());
+    run_recorded.send(RunRecorded(replay));
+}
+
+fn feed_replay_input(
+    mut playback: ResMut<ReplayPlayback>,
+    mut players: Query<&mut DiveInput, With<Player>>,
+) {
+    for mut dive_input in players.iter_mut() {
+        dive_input.pressed = playback.replay.is_diving_at(playback.tick);
+    }
+    playback.tick += 1;
+}
+
+fn begin_replay_run(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Playing);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_replay_commands(
+    mut commands: Commands,
+    mut replay_commands: EventReader<ReplayCommand>,
+    mut playback: Option<ResMut<ReplayPlayback>>,
+    mut seed: ResMut<CourseSeed>,
+    mode: Res<GameMode>,
+    camera_query: Query<&Transform, With<Camera>>,
+    tuning: Res<Tuning>,
+    fixed_time: Res<Time<Fixed>>,
+    saves: Res<SaveDirectory>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_replay_state: ResMut<NextState<ReplayState>>,
+) {
+    for command in replay_commands.read() {
+        match (*command, playback.as_deref_mut()) {
+            // Replays are recorded by the endless rules and only replay
+            // the same way under them.
+            (ReplayCommand::WatchLast, None) if !mode.rules().ranked => {
+                warn!("Cannot watch replay: switch to a ranked mode first");
+            }
+            (ReplayCommand::WatchLast, None) => {
+                match load_last_replay(&saves, &tuning, &fixed_time) {
+                    Ok(replay) => {
+                        let previous_seed = *seed;
+                        *seed = CourseSeed(replay.seed);
+                        commands.insert_resource(ReplayPlayback::new(replay, previous_seed));
+                        next_replay_state.set(ReplayState::Viewing);
+                        next_game_state.set(GameState::MainMenu);
+                    }
+                    Err(error) => warn!("Cannot watch replay: {}", error),
+                }
+            }
+            (ReplayCommand::TogglePause, Some(playback)) => playback.paused = !playback.paused,
+            (ReplayCommand::Faster, Some(playback)) => {
+                playback.speed_index = (playback.speed_index + 1).min(PLAYBACK_SPEEDS.len() - 1);
+            }
+            (ReplayCommand::Slower, Some(playback)) => {
+                playback.speed_index = playback.speed_index.saturating_sub(1);
+            }
+            (ReplayCommand::SeekBy(seconds), Some(playback)) => {
+                let ticks = (seconds / playback.replay.constants.fixed_timestep) as i64;
+                let target =
+                    (playback.tick as i64 + ticks).clamp(0, playback.replay.total_ticks as i64);
+                let target = target as u32;
+
+                // The simulation can only run forwards, so seeking backwards
+                // restarts the run and fast-forwards to the target.
+                if target < playback.tick {
+                    playback.tick = 0;
+                    next_game_state.set(GameState::MainMenu);
+                }
+                playback.seek_target = Some(target);
+            }
+            (ReplayCommand::ToggleFreeCamera, Some(playback)) => {
+                playback.free_camera = match playback.free_camera {
+                    Some(_) => None,
+                    None => camera_query
+                        .get_single()
+                        .map(|transform| transform.translation.truncate())
+                        .ok(),
+                };
+            }
+            (ReplayCommand::Exit, Some(playback)) => {
+                *seed = playback.previous_seed;
+                commands.remove_resource::<ReplayPlayback>();
+                next_replay_state.set(ReplayState::Inactive);
+                next_game_state.set(GameState::MainMenu);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_playback_speed(
+    playback: Option<ResMut<ReplayPlayback>>,
+    player_state: Res<State<PlayerState>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+
+    if playback
+        .seek_target
+        .is_some_and(|target| playback.tick >= target)
+    {
+        playback.seek_target = None;
+    }
+
+    let finished =
+        playback.tick >= playback.replay.total_ticks || *player_state.get() == PlayerState::Dead;
+
+    if playback.seek_target.is_some() {
+        time.unpause();
+        time.set_relative_speed(SEEK_SPEED);
+    } else if playback.paused || finished {
+        time.pause();
+    } else {
+        time.unpause();
+        time.set_relative_speed(playback.speed());
+    }
+}
+
+fn move_free_camera(
+    playback: Option<ResMut<ReplayPlayback>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    time: Res<Time<Real>>,
+    mut camera_query: Query<&mut Transform, With<Camera>>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+
+    let Some(position) = playback.free_camera.as_mut() else {
+        return;
+    };
+
+    let mut direction = Vec2::ZERO;
+    if keyboard.pressed(KeyCode::ArrowLeft) {
+        direction.x -= 1.0;
+    }
+    if keyboard.pressed(KeyCode::ArrowRight) {
+        direction.x += 1.0;
+    }
+    if keyboard.pressed(KeyCode::ArrowDown) {
+        direction.y -= 1.0;
+    }
+    if keyboard.pressed(KeyCode::ArrowUp) {
+        direction.y += 1.0;
+    }
+
+    // Real time, so the camera still moves while the replay is paused.
+    *position += direction * FREE_CAMERA_SPEED * time.delta_seconds();
+
+    if let Ok(mut camera_transform) = camera_query.get_single_mut() {
+        camera_transform.translation.x = position.x;
+        camera_transform.translation.y = position.y;
+    }
+}
+
+/// Restores normal time once the viewer is closed.
+fn reset_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+    time.set_relative_speed(1.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        Replay {
+            game_version: "0.1.0".to_string(),
+            seed: 0xDEAD_BEEF_0123_4567,
+            constants: PhysicsConstants::current(&Tuning::default(), &Time::<Fixed>::default()),
+            boosted: true,
+            total_ticks: 70_000,
+            dive_toggles: vec![3, 130, 131, 20_000, 69_999],
+        }
+    }
+
+    #[test]
+    fn replays_round_trip() {
+        for replay in [
+            replay(),
+            Replay {
+                boosted: false,
+                dive_toggles: Vec::new(),
+                ..replay()
+            },
+        ] {
+            assert_eq!(Replay::decode(&replay.encode()), Ok(replay));
+        }
+    }
+
+    #[test]
+    fn other_files_are_not_replays() {
+        let mut bytes = replay().encode();
+        bytes[0] = b'X';
+
+        assert_eq!(Replay::decode(&bytes), Err(ReplayError::BadMagic));
+        assert_eq!(Replay::decode(b"RB"), Err(ReplayError::Truncated));
+    }
+
+    #[test]
+    fn other_format_versions_are_refused() {
+        let mut bytes = replay().encode();
+        bytes[4..6].copy_from_slice(&(REPLAY_FORMAT_VERSION + 1).to_le_bytes());
+
+        assert_eq!(
+            Replay::decode(&bytes),
+            Err(ReplayError::UnsupportedFormat(REPLAY_FORMAT_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn truncated_replays_are_refused() {
+        let bytes = replay().encode();
+
+        for length in 0..bytes.len() {
+            assert_eq!(
+                Replay::decode(&bytes[..length]),
+                Err(ReplayError::Truncated),
+                "{} bytes",
+                length
+            );
+        }
+    }
+
+    #[test]
+    fn varints_that_overflow_are_malformed() {
+        // Everything up to the tick count and the number of toggles.
+        let mut header = Replay {
+            total_ticks: 0,
+            dive_toggles: Vec::new(),
+            ..replay()
+        }
+        .encode();
+        header.truncate(header.len() - 2);
+
+        let mut too_large = header.clone();
+        too_large.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0x1F, 0]);
+        assert_eq!(Replay::decode(&too_large), Err(ReplayError::Malformed));
+
+        let mut too_long = header.clone();
+        too_long.extend_from_slice(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x00, 0]);
+        assert_eq!(Replay::decode(&too_long), Err(ReplayError::Malformed));
+
+        let mut largest = header;
+        largest.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0]);
+        assert_eq!(
+            Replay::decode(&largest).map(|replay| replay.total_ticks),
+            Ok(u32::MAX)
+        );
+    }
+
+    #[test]
+    fn replays_from_other_physics_are_incompatible() {
+        let replay = replay();
+        let tuning = Tuning {
+            gravity: Tuning::default().gravity * 2.0,
+            ..default()
+        };
+
+        assert_eq!(replay.check_compatible(&replay.constants), Ok(()));
+        assert_eq!(
+            replay.check_compatible(&PhysicsConstants::current(
+                &tuning,
+                &Time::<Fixed>::default()
+            )),
+            Err(ReplayError::IncompatibleConstants)
+        );
+    }
+}
@@ -0,0 +1,461 @@
+use std::collections::VecDeque;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    online::OnlineRace,
+    platforms::{spawn_platform, NextPlatformIndex, Platform, Rising, Sinking},
+    player::{
+        apply_dive_gravity, spawn_player_ball, DiveInput, Player, PlayerLook, PlayerState, Seat,
+        TravelDistanceMeters,
+    },
+    spikes::Spikes,
+    tuning::Tuning,
+    versus::Versus,
+    GameState,
+};
+
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RewindBuffer>()
+            .insert_resource(RewindAvailable(true))
+            .add_event::<RewindRequest>()
+            .add_event::<Rewound>()
+            .add_systems(OnEnter(GameState::Playing), reset_rewind)
+            .add_systems(
+                FixedUpdate,
+                capture_snapshot
+                    .after(apply_dive_gravity)
+                    .run_if(not(resource_exists::<Versus>))
+                    .run_if(not(resource_exists::<OnlineRace>))
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Alive)),
+            )
+            .add_systems(
+                Update,
+                rewind
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Dead)),
+            );
+    }
+}
+
+/// How far back a rewind goes.
+const REWIND_SECONDS: f32 = 3.0;
+
+/// Whether the once-per-run rewind is still unused.
+#[derive(Resource)]
+pub struct RewindAvailable(pub bool);
+
+/// Sent by the game over menu to rewind instead of respawning.
+#[derive(Event)]
+pub struct RewindRequest;
+
+/// Sent after gameplay state was rolled back by `ticks` fixed ticks, so that
+/// per-tick recordings can drop what no longer happened.
+#[derive(Event)]
+pub struct Rewound {
+    pub ticks: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct BodySnapshot {
+    position: Vec2,
+    rotation: Rotation,
+    linear_velocity: Vec2,
+    angular_velocity: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PlatformMotion {
+    Resting,
+    Sinking,
+    Rising { target_y: f32 },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct PlatformSnapshot {
+    platform: Platform,
+    body: BodySnapshot,
+    motion: PlatformMotion,
+}
+
+/// Everything needed to put a run back the way it was at the start of a tick.
+#[derive(Clone, Debug, PartialEq)]
+struct GameplaySnapshot {
+    tick: u32,
+    dive: bool,
+    travel_distance: f32,
+    next_platform_index: u64,
+    player: Option<BodySnapshot>,
+    spikes: Vec<(Entity, BodySnapshot)>,
+    platforms: Vec<PlatformSnapshot>,
+}
+
+/// Ring buffer of the last few seconds of snapshots, one per fixed tick.
+#[derive(Resource, Default)]
+pub struct RewindBuffer {
+    snapshots: VecDeque<GameplaySnapshot>,
+    capacity: usize,
+    next_tick: u32,
+}
+
+impl RewindBuffer {
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// The tick the next snapshot will be taken at.
+    pub fn tick(&self) -> u32 {
+        self.next_tick
+    }
+}
+
+type BodyData = (
+    &'static Transform,
+    Option<&'static Position>,
+    Option<&'static Rotation>,
+    Option<&'static LinearVelocity>,
+    Option<&'static AngularVelocity>,
+);
+
+fn body_snapshot(
+    (transform, position, rotation, linear_velocity, angular_velocity): (
+        &Transform,
+        Option<&Position>,
+        Option<&Rotation>,
+        Option<&LinearVelocity>,
+        Option<&AngularVelocity>,
+    ),
+) -> BodySnapshot {
+    BodySnapshot {
+        position: position.map_or(transform.translation.truncate(), |position| position.0),
+        rotation: rotation.copied().unwrap_or_default(),
+        linear_velocity: linear_velocity.map_or(Vec2::ZERO, |velocity| velocity.0),
+        angular_velocity: angular_velocity.map_or(0.0, |velocity| velocity.0),
+    }
+}
+
+fn body_components(
+    body: &BodySnapshot,
+) -> (
+    Transform,
+    Position,
+    Rotation,
+    LinearVelocity,
+    AngularVelocity,
+) {
+    (
+        Transform::from_translation(body.position.extend(0.0))
+            .with_rotation(Quat::from_rotation_z(body.rotation.as_radians())),
+        Position(body.position),
+        body.rotation,
+        LinearVelocity(body.linear_velocity),
+        AngularVelocity(body.angular_velocity),
+    )
+}
+
+fn reset_rewind(
+    mut buffer: ResMut<RewindBuffer>,
+    mut available: ResMut<RewindAvailable>,
+    time: Res<Time<Fixed>>,
+) {
+    buffer.capacity = (REWIND_SECONDS / time.timestep().as_secs_f32()).ceil() as usize;
+    buffer.snapshots.clear();
+    buffer.next_tick = 0;
+    available.0 = true;
+}
+
+fn capture_snapshot(
+    mut buffer: ResMut<RewindBuffer>,
+    distance: Res<TravelDistanceMeters>,
+    next_platform_index: Res<NextPlatformIndex>,
+    players: Query<(BodyData, &DiveInput), With<Player>>,
+    spikes: Query<(Entity, BodyData), With<Spikes>>,
+    platforms: Query<(&Platform, BodyData, Has<Sinking>, Option<&Rising>)>,
+) {
+    let mut platform_snapshots: Vec<_> = platforms
+        .iter()
+        .map(|(platform, body, sinking, rising)| PlatformSnapshot {
+            platform: platform.clone(),
+            body: body_snapshot(body),
+            motion: match (sinking, rising) {
+                (true, _) => PlatformMotion::Sinking,
+                (false, Some(rising)) => PlatformMotion::Rising {
+                    target_y: rising.target_y,
+                },
+                (false, None) => PlatformMotion::Resting,
+            },
+        })
+        .collect();
+    platform_snapshots.sort_by_key(|snapshot| snapshot.platform.index);
+
+    let player = players.get_single().ok();
+
+    let snapshot = GameplaySnapshot {
+        tick: buffer.next_tick,
+        dive: player.is_some_and(|(_, dive_input)| dive_input.pressed),
+        travel_distance: distance.0,
+        next_platform_index: next_platform_index.0,
+        player: player.map(|(body, _)| body_snapshot(body)),
+        spikes: spikes
+            .iter()
+            .map(|(entity, body)| (entity, body_snapshot(body)))
+            .collect(),
+        platforms: platform_snapshots,
+    };
+
+    if buffer.snapshots.len() >= buffer.capacity {
+        buffer.snapshots.pop_front();
+    }
+    buffer.snapshots.push_back(snapshot);
+    buffer.next_tick += 1;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn rewind(
+    mut requests: EventReader<RewindRequest>,
+    mut buffer: ResMut<RewindBuffer>,
+    mut available: ResMut<RewindAvailable>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    tuning: Res<Tuning>,
+    look: Res<PlayerLook>,
+    mut distance: ResMut<TravelDistanceMeters>,
+    mut next_platform_index: ResMut<NextPlatformIndex>,
+    platforms: Query<Entity, With<Platform>>,
+    mut spikes: Query<
+        (
+            &mut Transform,
+            &mut Position,
+            &mut Rotation,
+            &mut LinearVelocity,
+            &mut AngularVelocity,
+        ),
+        With<Spikes>,
+    >,
+    mut next_state: ResMut<NextState<PlayerState>>,
+    mut rewound: EventWriter<Rewound>,
+) {
+    if requests.read().count() == 0 || !available.0 {
+        return;
+    }
+
+    let Some(snapshot) = buffer.snapshots.pop_front() else {
+        return;
+    };
+
+    let Some(player) = snapshot.player else {
+        return;
+    };
+
+    available.0 = false;
+
+    let ticks = buffer.next_tick - snapshot.tick;
+    buffer.snapshots.clear();
+    buffer.next_tick = snapshot.tick;
+
+    distance.0 = snapshot.travel_distance;
+    next_platform_index.0 = snapshot.next_platform_index;
+
+    let player_entity = spawn_player_ball(
+        &mut commands,
+        &asset_server,
+        &mut meshes,
+        &mut materials,
+        &tuning,
+        Seat::One,
+        &look,
+        player.position.extend(0.0),
+    );
+    commands.entity(player_entity).insert((
+        body_components(&player),
+        DiveInput {
+            pressed: snapshot.dive,
+        },
+        GravityScale(tuning.gravity_scale(snapshot.dive)),
+    ));
+
+    for (entity, body) in &snapshot.spikes {
+        if let Ok((
+            mut transform,
+            mut position,
+            mut rotation,
+            mut linear_velocity,
+            mut angular_velocity,
+        )) = spikes.get_mut(*entity)
+        {
+            transform.translation.x = body.position.x;
+            transform.translation.y = body.position.y;
+            position.0 = body.position;
+            *rotation = body.rotation;
+            linear_velocity.0 = body.linear_velocity;
+            angular_velocity.0 = body.angular_velocity;
+        }
+    }
+
+    for entity in platforms.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for platform in &snapshot.platforms {
+        let entity = spawn_platform(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            platform.platform.clone(),
+            platform.body.position.extend(0.0),
+        );
+
+        commands
+            .entity(entity)
+            .insert(body_components(&platform.body));
+
+        match platform.motion {
+            PlatformMotion::Resting => {}
+            PlatformMotion::Sinking => {
+                commands.entity(entity).insert(Sinking);
+            }
+            PlatformMotion::Rising { target_y } => {
+                commands.entity(entity).insert(Rising { target_y });
+            }
+        }
+    }
+
+    next_state.set(PlayerState::Alive);
+    rewound.send(Rewound { ticks });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{harness::GameHarness, killcam::KillcamState};
+
+    fn started_run() -> GameHarness {
+        let mut harness = GameHarness::new();
+        harness.start_run();
+        harness
+    }
+
+    /// Dives for 40 ticks out of every 80, starting with a glide.
+    fn scripted_dive(tick: u32) -> bool {
+        (tick / 40) % 2 == 1
+    }
+
+    /// Runs the tick the next snapshot is taken at, diving as scripted for
+    /// it, so a rewound run gets the same inputs again.
+    fn step(harness: &mut GameHarness) {
+        let tick = harness.world().resource::<RewindBuffer>().tick();
+        let mut players = harness
+            .world_mut()
+            .query_filtered::<&mut DiveInput, With<Player>>();
+        for mut dive_input in players.iter_mut(harness.world_mut()) {
+            dive_input.pressed = scripted_dive(tick);
+        }
+        harness.tick();
+
+        let next_tick = harness.world().resource::<RewindBuffer>().tick();
+        assert_eq!(next_tick, tick + 1, "the run ended at tick {}", tick);
+    }
+
+    fn player_body(harness: &mut GameHarness) -> BodySnapshot {
+        let mut query = harness
+            .world_mut()
+            .query_filtered::<BodyData, With<Player>>();
+        body_snapshot(query.single(harness.world()))
+    }
+
+    /// Dies and rewinds from the game over menu, which only shows up once
+    /// the killcam is over.
+    fn die_and_rewind(harness: &mut GameHarness) {
+        harness
+            .world_mut()
+            .resource_mut::<NextState<PlayerState>>()
+            .set(PlayerState::Dead);
+        harness.run_until(1.0, |harness| {
+            harness.killcam_state() == KillcamState::Playing
+        });
+        harness.run_until(20.0, |harness| {
+            harness.killcam_state() == KillcamState::Idle
+        });
+        harness.world_mut().send_event(RewindRequest);
+        harness.tick();
+    }
+
+    #[test]
+    fn rewind_restores_the_oldest_snapshot_exactly() {
+        let mut harness = started_run();
+
+        for _ in 0..300 {
+            step(&mut harness);
+        }
+
+        let oldest = harness.world().resource::<RewindBuffer>().snapshots[0].clone();
+
+        die_and_rewind(&mut harness);
+        step(&mut harness);
+
+        let restored = harness.world().resource::<RewindBuffer>().snapshots[0].clone();
+
+        assert_eq!(restored.tick, oldest.tick);
+        assert_eq!(restored.player, oldest.player);
+        assert_eq!(restored.platforms, oldest.platforms);
+        assert_eq!(restored.next_platform_index, oldest.next_platform_index);
+        assert_eq!(restored.travel_distance, oldest.travel_distance);
+    }
+
+    #[test]
+    fn rewind_followed_by_the_same_inputs_reproduces_the_same_outcome() {
+        const DEATH_TICK: u32 = 300;
+        const END_TICK: u32 = 400;
+
+        let mut reference = started_run();
+        while reference.world().resource::<RewindBuffer>().tick() < END_TICK {
+            step(&mut reference);
+        }
+        let expected = player_body(&mut reference);
+
+        let mut rewound = started_run();
+        while rewound.world().resource::<RewindBuffer>().tick() < DEATH_TICK {
+            step(&mut rewound);
+        }
+        die_and_rewind(&mut rewound);
+
+        let resume_tick = rewound.world().resource::<RewindBuffer>().tick();
+        assert!(resume_tick < DEATH_TICK);
+
+        while rewound.world().resource::<RewindBuffer>().tick() < END_TICK {
+            step(&mut rewound);
+        }
+        let actual = player_body(&mut rewound);
+
+        assert!(actual.position.distance(expected.position) < 1e-3);
+        assert!(actual.linear_velocity.distance(expected.linear_velocity) < 1e-3);
+    }
+
+    #[test]
+    fn rewind_is_only_available_once_per_run() {
+        let mut harness = started_run();
+
+        for _ in 0..100 {
+            step(&mut harness);
+        }
+        die_and_rewind(&mut harness);
+        assert!(!harness.world().resource::<RewindAvailable>().0);
+
+        for _ in 0..100 {
+            step(&mut harness);
+        }
+        die_and_rewind(&mut harness);
+
+        assert_eq!(
+            *harness.world().resource::<State<PlayerState>>().get(),
+            PlayerState::Dead
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use std::{fmt, fs, io, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+const SAVE_DIRECTORY: &str = "saves";
+
+/// Where an app keeps its save files.
+#[derive(Resource, Debug, Clone)]
+pub struct SaveDirectory(PathBuf);
+
+#[cfg(not(test))]
+impl Default for SaveDirectory {
+    fn default() -> Self {
+        Self(PathBuf::from(SAVE_DIRECTORY))
+    }
+}
+
+/// Every test app gets a save directory of its own, so tests neither see nor
+/// clobber the player's saves or each other's.
+#[cfg(test)]
+impl Default for SaveDirectory {
+    fn default() -> Self {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static NEXT_APP: AtomicUsize = AtomicUsize::new(0);
+
+        Self(
+            std::env::temp_dir()
+                .join(format!(
+                    "ramp_ball-{}-{}",
+                    std::process::id(),
+                    NEXT_APP.fetch_add(1, Ordering::Relaxed)
+                ))
+                .join(SAVE_DIRECTORY),
+        )
+    }
+}
+
+/// The app's save directory, for plugins loading their saves as they're
+/// built.
+pub fn directory(app: &mut App) -> SaveDirectory {
+    app.world_mut()
+        .get_resource_or_insert_with(SaveDirectory::default)
+        .clone()
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Serialize(ron::Error),
+    Write(io::Error),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Serialize(error) => write!(f, "could not serialize: {}", error),
+            SaveError::Write(error) => write!(f, "could not write: {}", error),
+        }
+    }
+}
+
+impl SaveDirectory {
+    fn path(&self, file_name: &str) -> PathBuf {
+        self.0.join(file_name)
+    }
+
+    /// Loads a save file, falling back to the default value if it is missing
+    /// or unreadable.
+    pub fn load<T: DeserializeOwned + Default>(&self, file_name: &str) -> T {
+        let path = self.path(file_name);
+
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return T::default();
+        };
+
+        ron::from_str(&contents).unwrap_or_else(|error| {
+            warn!("Ignoring corrupt save file {}: {}", path.display(), error);
+            T::default()
+        })
+    }
+
+    /// Writes a save file. The file is written next to its destination first
+    /// and then renamed, so a crash never leaves a half-written save behind.
+    pub fn store<T: Serialize>(&self, file_name: &str, value: &T) {
+        if let Err(error) = self.try_store(file_name, value) {
+            error!("Failed to save {}: {}", file_name, error);
+        }
+    }
+
+    /// Like [`SaveDirectory::store`], but hands the failure back so the
+    /// caller can keep its state in step with what's on disk.
+    pub fn try_store<T: Serialize>(&self, file_name: &str, value: &T) -> Result<(), SaveError> {
+        let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+            .map_err(SaveError::Serialize)?;
+        self.try_write_bytes(file_name, contents.as_bytes())
+            .map_err(SaveError::Write)
+    }
+
+    pub fn read_bytes(&self, file_name: &str) -> Option<Vec<u8>> {
+        fs::read(self.path(file_name)).ok()
+    }
+
+    pub fn write_bytes(&self, file_name: &str, bytes: &[u8]) {
+        if let Err(error) = self.try_write_bytes(file_name, bytes) {
+            error!(
+                "Failed to write save file {}: {}",
+                self.path(file_name).display(),
+                error
+            );
+        }
+    }
+
+    fn try_write_bytes(&self, file_name: &str, bytes: &[u8]) -> io::Result<()> {
+        let path = self.path(file_name);
+        let temporary_path = path.with_extension("tmp");
+
+        path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&temporary_path, bytes))
+            .and_then(|_| fs::rename(&temporary_path, &path))
+    }
+}
@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+
+/// Seed the course is generated from. Runs on the same seed get the same
+/// platforms.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CourseSeed(pub u64);
+
+pub const DEFAULT_SEED: u64 = 0x5EED;
+
+impl Default for CourseSeed {
+    fn default() -> Self {
+        Self(DEFAULT_SEED)
+    }
+}
+
+/// Small deterministic generator, so courses don't depend on platform RNGs.
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `[min, max)`.
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        let unit = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        min + unit * (max - min)
+    }
+}
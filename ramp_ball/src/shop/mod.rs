@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+
+use crate::{
+    save::SaveDirectory,
+    skins::{SkinCatalogue, SkinCatalogueHandle, Unlock},
+    wallet::{Item, PurchaseError, Wallet},
+};
+
+/// Spends wallet coins on skins, revives and boosts. Skin prices come from
+/// the skin catalogue, so only skins it lists for a price can be bought.
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BuyItem>()
+            .add_event::<PurchaseOutcome>()
+            .add_systems(Update, buy_items.run_if(on_event::<BuyItem>()));
+    }
+}
+
+pub const REVIVE_PRICE: u64 = 150;
+
+pub const BOOST_PRICE: u64 = 75;
+
+/// Sent by the shop screen to buy something.
+#[derive(Event)]
+pub struct BuyItem(pub Item);
+
+/// Sent once a purchase has gone through or been turned down.
+#[derive(Event)]
+pub struct PurchaseOutcome {
+    pub item: Item,
+    pub result: Result<(), PurchaseError>,
+}
+
+/// What `item` costs, if it's for sale.
+pub fn price(item: &Item, catalogue: Option<&SkinCatalogue>) -> Result<u64, PurchaseError> {
+    match item {
+        Item::Skin(id) => catalogue
+            .and_then(|catalogue| catalogue.skins.iter().find(|skin| skin.id == *id))
+            .and_then(|skin| match skin.unlock {
+                Unlock::Price(coins) => Some(coins),
+                _ => None,
+            })
+            .ok_or(PurchaseError::NotForSale),
+        Item::Revive => Ok(REVIVE_PRICE),
+        Item::Boost => Ok(BOOST_PRICE),
+    }
+}
+
+fn buy_items(
+    mut events: EventReader<BuyItem>,
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+    mut wallet: ResMut<Wallet>,
+    saves: Res<SaveDirectory>,
+    mut outcomes: EventWriter<PurchaseOutcome>,
+) {
+    let catalogue = catalogues.get(&handle.0);
+
+    for BuyItem(item) in events.read() {
+        let result = price(item, catalogue).and_then(|price| wallet.buy(item, price, &saves));
+
+        match &result {
+            Ok(()) => info!("Bought {:?}", item),
+            Err(error) => warn!("Couldn't buy {:?}: {}", item, error),
+        }
+
+        outcomes.send(PurchaseOutcome {
+            item: item.clone(),
+            result,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{player::BallDesign, skins::Skin};
+
+    fn catalogue() -> SkinCatalogue {
+        let skin = |id: &str, unlock| Skin {
+            id: id.to_string(),
+            name: id.to_string(),
+            design: BallDesign::Texture(String::new()),
+            ring_color: Color::WHITE,
+            unlock,
+        };
+
+        SkinCatalogue {
+            skins: vec![
+                skin("free", Unlock::Free),
+                skin("earned", Unlock::Achievement("first_drop".to_string())),
+                skin("far", Unlock::BestDistance(500.0)),
+                skin("bought", Unlock::Price(150)),
+            ],
+        }
+    }
+
+    #[test]
+    fn skins_cost_their_catalogue_price() {
+        let catalogue = catalogue();
+        let bought = Item::Skin("bought".to_string());
+
+        assert!(matches!(price(&bought, Some(&catalogue)), Ok(150)));
+    }
+
+    #[test]
+    fn skins_unlocked_some_other_way_are_not_for_sale() {
+        let catalogue = catalogue();
+
+        for id in ["free", "earned", "far"] {
+            assert!(
+                matches!(
+                    price(&Item::Skin(id.to_string()), Some(&catalogue)),
+                    Err(PurchaseError::NotForSale)
+                ),
+                "{}",
+                id
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_skins_are_not_for_sale() {
+        let unknown = Item::Skin("unknown".to_string());
+        let bought = Item::Skin("bought".to_string());
+
+        assert!(matches!(
+            price(&unknown, Some(&catalogue())),
+            Err(PurchaseError::NotForSale)
+        ));
+        // Nor is anything before the catalogue has loaded.
+        assert!(matches!(
+            price(&bought, None),
+            Err(PurchaseError::NotForSale)
+        ));
+    }
+
+    #[test]
+    fn revives_and_boosts_have_fixed_prices() {
+        assert!(matches!(price(&Item::Revive, None), Ok(REVIVE_PRICE)));
+        assert!(matches!(price(&Item::Boost, None), Ok(BOOST_PRICE)));
+    }
+}
@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::{AchievementRecord, ACHIEVEMENTS},
+    player::{BallDesign, PlayerLook},
+    ron_asset::RonAssetLoader,
+    save::{self, SaveDirectory},
+    stats::LifetimeStats,
+    wallet::Wallet,
+};
+
+/// The skins a ball can wear, listed in `assets/game.skins.ron`, and the one
+/// the player has picked.
+pub struct SkinsPlugin;
+
+impl Plugin for SkinsPlugin {
+    fn build(&self, app: &mut App) {
+        let saves = save::directory(app);
+
+        app.init_asset::<SkinCatalogue>()
+            .register_asset_loader(RonAssetLoader::<SkinCatalogue>::new(&["skins.ron"]))
+            .insert_resource(saves.load::<SkinChoice>(SKIN_FILE))
+            .add_event::<SelectSkin>()
+            .add_systems(Startup, load_skin_catalogue)
+            .add_systems(
+                Update,
+                (
+                    select_skin,
+                    wear_chosen_skin.run_if(
+                        on_event::<AssetEvent<SkinCatalogue>>()
+                            .or_else(resource_changed::<SkinChoice>),
+                    ),
+                )
+                    .chain(),
+            );
+    }
+}
+
+const SKIN_CATALOGUE_PATH: &str = "game.skins.ron";
+
+const SKIN_FILE: &str = "skin.ron";
+
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct SkinCatalogue {
+    pub skins: Vec<Skin>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Skin {
+    pub id: String,
+    pub name: String,
+    pub design: BallDesign,
+    pub ring_color: Color,
+    #[serde(default)]
+    pub unlock: Unlock,
+}
+
+/// What it takes to be allowed to wear a skin.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub enum Unlock {
+    #[default]
+    Free,
+    /// Unlocking the achievement with this id.
+    Achievement(String),
+    /// A best distance of at least this many meters.
+    BestDistance(f32),
+    /// Buying it in the shop for this many coins.
+    Price(u64),
+}
+
+impl Unlock {
+    pub fn describe(&self) -> String {
+        match self {
+            Unlock::Free => "Free".to_string(),
+            Unlock::Achievement(id) => {
+                let name = ACHIEVEMENTS
+                    .iter()
+                    .find(|achievement| achievement.id == id)
+                    .map_or(id.as_str(), |achievement| achievement.name);
+                format!("Unlock the {} achievement", name)
+            }
+            Unlock::BestDistance(meters) => format!("Reach {:.0}m in one run", meters),
+            Unlock::Price(coins) => format!("Buy it in the shop for {} coins", coins),
+        }
+    }
+}
+
+impl Skin {
+    pub fn is_unlocked(
+        &self,
+        achievements: &AchievementRecord,
+        stats: &LifetimeStats,
+        wallet: &Wallet,
+    ) -> bool {
+        match &self.unlock {
+            Unlock::Free => true,
+            Unlock::Achievement(id) => ACHIEVEMENTS
+                .iter()
+                .find(|achievement| achievement.id == id)
+                .is_some_and(|achievement| achievements.is_unlocked(achievement)),
+            Unlock::BestDistance(meters) => stats.best_distance >= *meters,
+            Unlock::Price(_) => wallet.owns_skin(&self.id),
+        }
+    }
+
+    pub fn look(&self) -> PlayerLook {
+        PlayerLook {
+            design: self.design.clone(),
+            ring_color: self.ring_color,
+        }
+    }
+}
+
+/// The skin the player picked, saved locally. The first seat wears the
+/// default look until the catalogue has loaded.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkinChoice {
+    pub skin: Option<String>,
+}
+
+#[derive(Resource)]
+pub struct SkinCatalogueHandle(pub Handle<SkinCatalogue>);
+
+/// Sent by the skins screen to wear a skin, by id.
+#[derive(Event)]
+pub struct SelectSkin(pub String);
+
+fn load_skin_catalogue(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(SkinCatalogueHandle(asset_server.load(SKIN_CATALOGUE_PATH)));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn select_skin(
+    mut events: EventReader<SelectSkin>,
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+    achievements: Res<AchievementRecord>,
+    stats: Res<LifetimeStats>,
+    wallet: Res<Wallet>,
+    mut choice: ResMut<SkinChoice>,
+    saves: Res<SaveDirectory>,
+) {
+    let Some(SelectSkin(id)) = events.read().last() else {
+        return;
+    };
+
+    let Some(skin) = catalogues
+        .get(&handle.0)
+        .and_then(|catalogue| catalogue.skins.iter().find(|skin| skin.id == *id))
+    else {
+        warn!("There is no skin called {}", id);
+        return;
+    };
+
+    if !skin.is_unlocked(&achievements, &stats, &wallet) {
+        warn!("{} is locked: {}", skin.name, skin.unlock.describe());
+        return;
+    }
+
+    if choice.skin.as_ref() != Some(id) {
+        choice.skin = Some(id.clone());
+        saves.store(SKIN_FILE, &*choice);
+    }
+}
+
+/// Dresses the first seat in the chosen skin, or the default one if the
+/// choice is gone from the catalogue or no longer unlocked.
+fn wear_chosen_skin(
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+    choice: Res<SkinChoice>,
+    achievements: Res<AchievementRecord>,
+    stats: Res<LifetimeStats>,
+    wallet: Res<Wallet>,
+    mut look: ResMut<PlayerLook>,
+) {
+    let Some(catalogue) = catalogues.get(&handle.0) else {
+        return;
+    };
+
+    let chosen = catalogue
+        .skins
+        .iter()
+        .filter(|skin| skin.is_unlocked(&achievements, &stats, &wallet))
+        .find(|skin| choice.skin.as_ref() == Some(&skin.id))
+        .map_or_else(PlayerLook::default, Skin::look);
+
+    if *look != chosen {
+        *look = chosen;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, fs, path::Path};
+
+    use super::*;
+
+    #[test]
+    fn the_catalogue_parses_and_every_texture_is_there() {
+        let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let contents = fs::read_to_string(assets.join(SKIN_CATALOGUE_PATH)).unwrap();
+        let catalogue: SkinCatalogue = ron::from_str(&contents).unwrap();
+
+        assert_eq!(catalogue.skins[0].unlock, Unlock::Free);
+
+        let ids: HashSet<_> = catalogue.skins.iter().map(|skin| &skin.id).collect();
+        assert_eq!(ids.len(), catalogue.skins.len());
+
+        for skin in &catalogue.skins {
+            if let BallDesign::Texture(texture) = &skin.design {
+                assert!(assets.join(texture).exists(), "{}", texture);
+            }
+            if let Unlock::Achievement(id) = &skin.unlock {
+                assert!(ACHIEVEMENTS.iter().any(|achievement| achievement.id == id));
+            }
+        }
+    }
+}
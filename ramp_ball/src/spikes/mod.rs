@@ -5,7 +5,9 @@ use bevy::{
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
 };
 
-use crate::GameState;
+#[cfg(feature = "dev")]
+use crate::console::{parse_argument, AddConsoleCommand, ConsoleResult};
+use crate::{contacts::hazard_layers, game_mode::GameMode, tuning::Tuning, GameState};
 
 pub struct SpikesPlugin;
 
@@ -14,73 +16,150 @@ impl Plugin for SpikesPlugin {
         app.add_systems(Startup, spawn_spikes);
         app.add_systems(OnEnter(GameState::Playing), begin_moving_spikes);
         app.add_systems(OnExit(GameState::Playing), reset_spikes);
+        app.add_systems(
+            Update,
+            apply_spike_tuning.run_if(resource_changed::<Tuning>),
+        );
+
+        #[cfg(feature = "dev")]
+        app.add_console_command("spikes", "spikes <speed <n>|stop>", control_spikes);
     }
 }
 
-const NUMBER_OF_SPIKES: i32 = 25;
-
 #[derive(Component)]
 pub struct Spikes;
 
+/// How many spikes the wall is currently drawn with.
+#[derive(Component)]
+struct SpikeCount(u32);
+
 fn spawn_spikes(
-    windows: Query<&Window>,
+    tuning: Res<Tuning>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    if let Ok(window) = windows.get_single() {
-        let spike_height = window.height() / NUMBER_OF_SPIKES as f32;
-        let spike_width = spike_height * 2.0;
-
-        let spike_mesh = Mesh2dHandle(meshes.add(Triangle2d::new(
-            Vec2::new(0.0, 0.0),
-            Vec2::new(0.0, spike_height),
-            Vec2::new(spike_width, spike_height / 2.0),
-        )));
-        let spike_material = materials.add(Color::hsl(0.0, 0.0, 0.5));
-
-        let offset_x = window.width() / 2.0 - spike_width;
-        let offset_y = -window.height() / 2.0;
-
-        commands
-            .spawn((
-                Spikes,
-                TransformBundle::from_transform(Transform::from_xyz(-window.width(), 0.0, 0.0)),
-                RigidBody::Kinematic,
-                Collider::rectangle(window.width(), window.height()),
-                LinearVelocity::ZERO,
-            ))
-            .with_children(|parent| {
-                for i in 0..NUMBER_OF_SPIKES {
-                    parent.spawn(MaterialMesh2dBundle {
-                        mesh: spike_mesh.clone(),
-                        material: spike_material.clone(),
-                        transform: Transform::from_xyz(
-                            offset_x,
-                            spike_height * i as f32 + offset_y,
-                            5.0,
-                        ),
-                        ..default()
-                    });
-                }
-            });
+    commands
+        .spawn((
+            Spikes,
+            SpikeCount(tuning.number_of_spikes),
+            TransformBundle::from_transform(Transform::from_xyz(-tuning.course_width, 0.0, 0.0)),
+            RigidBody::Kinematic,
+            Collider::rectangle(tuning.course_width, tuning.course_height),
+            hazard_layers(),
+            LinearVelocity::ZERO,
+        ))
+        .with_children(|parent| {
+            spawn_spike_meshes(parent, &tuning, &mut meshes, &mut materials);
+        });
+}
+
+fn spawn_spike_meshes(
+    parent: &mut ChildBuilder,
+    tuning: &Tuning,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) {
+    let number_of_spikes = tuning.number_of_spikes;
+    let spike_height = tuning.course_height / number_of_spikes.max(1) as f32;
+    let spike_width = spike_height * 2.0;
+
+    let spike_mesh = Mesh2dHandle(meshes.add(Triangle2d::new(
+        Vec2::new(0.0, 0.0),
+        Vec2::new(0.0, spike_height),
+        Vec2::new(spike_width, spike_height / 2.0),
+    )));
+    let spike_material = materials.add(Color::hsl(0.0, 0.0, 0.5));
+
+    let offset_x = tuning.course_width / 2.0 - spike_width;
+    let offset_y = -tuning.course_height / 2.0;
+
+    for i in 0..number_of_spikes {
+        parent.spawn(MaterialMesh2dBundle {
+            mesh: spike_mesh.clone(),
+            material: spike_material.clone(),
+            transform: Transform::from_xyz(offset_x, spike_height * i as f32 + offset_y, 5.0),
+            ..default()
+        });
     }
 }
 
-fn begin_moving_spikes(mut query: Query<&mut LinearVelocity, With<Spikes>>) {
+/// Sets the wall off after the ball, in modes that have one. Otherwise it
+/// stays parked off screen.
+fn begin_moving_spikes(
+    tuning: Res<Tuning>,
+    mode: Res<GameMode>,
+    mut query: Query<&mut LinearVelocity, With<Spikes>>,
+) {
+    if !mode.rules().spikes {
+        return;
+    }
+
     for mut linear_velocity in query.iter_mut() {
-        linear_velocity.0 = Vec2::new(50.0, 0.0);
+        linear_velocity.0 = Vec2::new(tuning.spike_speed, 0.0);
+    }
+}
+
+/// Redraws the wall when the spike count changes and keeps a moving wall at
+/// the tuned speed.
+fn apply_spike_tuning(
+    tuning: Res<Tuning>,
+    mut spikes: Query<(Entity, &mut SpikeCount, &mut LinearVelocity), With<Spikes>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, mut count, mut velocity) in spikes.iter_mut() {
+        if velocity.x != 0.0 {
+            velocity.x = tuning.spike_speed;
+        }
+
+        if count.0 != tuning.number_of_spikes {
+            count.0 = tuning.number_of_spikes;
+            commands
+                .entity(entity)
+                .despawn_descendants()
+                .with_children(|parent| {
+                    spawn_spike_meshes(parent, &tuning, &mut meshes, &mut materials);
+                });
+        }
     }
 }
 
 fn reset_spikes(
-    windows: Query<&Window>,
+    tuning: Res<Tuning>,
     mut spikes: Query<(&mut Transform, &mut LinearVelocity), With<Spikes>>,
 ) {
-    if let Ok(window) = windows.get_single() {
-        for (mut transform, mut velocity) in spikes.iter_mut() {
-            transform.translation.x = -window.width();
-            velocity.0 = Vec2::ZERO;
+    for (mut transform, mut velocity) in spikes.iter_mut() {
+        transform.translation.x = -tuning.course_width;
+        velocity.0 = Vec2::ZERO;
+    }
+}
+
+#[cfg(feature = "dev")]
+fn control_spikes(
+    In(args): In<Vec<String>>,
+    game_state: Res<State<GameState>>,
+    mut tuning: ResMut<Tuning>,
+    mut spikes: Query<&mut LinearVelocity, With<Spikes>>,
+) -> ConsoleResult {
+    const USAGE: &str = "spikes <speed <n>|stop>";
+
+    let speed = match args.split_first() {
+        Some((subcommand, rest)) if subcommand == "speed" => {
+            let speed = parse_argument(rest, USAGE)?;
+            tuning.spike_speed = speed;
+            speed
         }
+        Some((subcommand, [])) if subcommand == "stop" => 0.0,
+        _ => return Err(format!("usage: {}", USAGE)),
     };
+
+    if *game_state.get() == GameState::Playing {
+        for mut velocity in spikes.iter_mut() {
+            velocity.0 = Vec2::new(speed, 0.0);
+        }
+    }
+
+    Ok(format!("spikes moving at {}", speed))
 }
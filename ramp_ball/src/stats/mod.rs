@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::{DeathCause, DiveEnded, DiveStarted, RunEnded, RunStarted},
+    player::{Player, PlayerState},
+    replay::ReplayPlayback,
+    save::{self, SaveDirectory},
+    versus::Versus,
+    GameState,
+};
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        let saves = save::directory(app);
+
+        app.insert_resource(saves.load::<LifetimeStats>(STATS_FILE))
+            .init_resource::<RunStats>()
+            .add_systems(
+                Update,
+                (
+                    collect_run_events,
+                    sample_player_motion
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(in_state(PlayerState::Alive)),
+                )
+                    .chain()
+                    .run_if(not(resource_exists::<ReplayPlayback>))
+                    .run_if(not(resource_exists::<Versus>)),
+            );
+    }
+}
+
+const STATS_FILE: &str = "stats.ron";
+
+/// Totals across every run ever played. Distances and altitudes are in
+/// meters, speeds in meters per second and times in seconds.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifetimeStats {
+    pub runs_played: u32,
+    pub total_distance: f32,
+    pub best_distance: f32,
+    /// Finished time trials aren't deaths; they count in `finishes`.
+    pub deaths: HashMap<DeathCause, u32>,
+    pub finishes: u32,
+    pub total_dive_time: f32,
+    pub max_speed: f32,
+    pub max_altitude: f32,
+}
+
+impl LifetimeStats {
+    pub fn deaths_by(&self, cause: DeathCause) -> u32 {
+        self.deaths.get(&cause).copied().unwrap_or(0)
+    }
+
+    fn start_run(&mut self, run: &mut RunStats) {
+        *run = RunStats::default();
+        self.runs_played += 1;
+    }
+
+    fn end_dive(&mut self, run: &mut RunStats, now: f32) {
+        self.total_dive_time += run.end_dive(now);
+    }
+
+    /// Adds a run that has just ended, or ended again after a rewind.
+    fn end_run(&mut self, run: &mut RunStats, ended: &RunEnded, now: f32) {
+        self.end_dive(run, now);
+
+        run.distance = ended.distance;
+        run.cause = Some(ended.cause);
+
+        self.total_distance += (ended.distance - run.counted_distance).max(0.0);
+        run.counted_distance = run.counted_distance.max(ended.distance);
+        self.best_distance = self.best_distance.max(ended.distance);
+        match ended.cause {
+            DeathCause::Finished => self.finishes += 1,
+            cause => *self.deaths.entry(cause).or_default() += 1,
+        }
+        self.max_speed = self.max_speed.max(run.max_speed);
+        self.max_altitude = self.max_altitude.max(run.max_altitude);
+    }
+}
+
+/// Statistics for the current run, or the last one once it has ended.
+#[derive(Resource, Debug, Default)]
+pub struct RunStats {
+    pub distance: f32,
+    pub dive_time: f32,
+    pub max_speed: f32,
+    pub max_altitude: f32,
+    pub cause: Option<DeathCause>,
+    dive_started_at: Option<f32>,
+    /// Distance already added to the lifetime total, so a rewound run that
+    /// ends again only adds what it travelled since.
+    counted_distance: f32,
+}
+
+impl RunStats {
+    fn start_dive(&mut self, now: f32) {
+        self.dive_started_at.get_or_insert(now);
+    }
+
+    /// Returns how long the dive that just ended lasted.
+    fn end_dive(&mut self, now: f32) -> f32 {
+        let Some(started_at) = self.dive_started_at.take() else {
+            return 0.0;
+        };
+        self.dive_time += now - started_at;
+        now - started_at
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn collect_run_events(
+    mut run_started: EventReader<RunStarted>,
+    mut dive_started: EventReader<DiveStarted>,
+    mut dive_ended: EventReader<DiveEnded>,
+    mut run_ended: EventReader<RunEnded>,
+    time: Res<Time>,
+    mut run: ResMut<RunStats>,
+    mut lifetime: ResMut<LifetimeStats>,
+    saves: Res<SaveDirectory>,
+) {
+    let now = time.elapsed_seconds();
+
+    for _ in run_started.read() {
+        lifetime.start_run(&mut run);
+    }
+
+    for _ in dive_started.read() {
+        run.start_dive(now);
+    }
+
+    for _ in dive_ended.read() {
+        lifetime.end_dive(&mut run, now);
+    }
+
+    for ended in run_ended.read() {
+        lifetime.end_run(&mut run, ended, now);
+        saves.store(STATS_FILE, &*lifetime);
+    }
+}
+
+fn sample_player_motion(
+    players: Query<(&Transform, &LinearVelocity), With<Player>>,
+    mut run: ResMut<RunStats>,
+) {
+    for (transform, velocity) in players.iter() {
+        run.max_speed = run.max_speed.max(velocity.length() / 100.0);
+        run.max_altitude = run.max_altitude.max(transform.translation.y / 100.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ended(distance: f32, cause: DeathCause) -> RunEnded {
+        RunEnded { distance, cause }
+    }
+
+    #[test]
+    fn runs_add_up() {
+        let mut lifetime = LifetimeStats::default();
+        let mut run = RunStats::default();
+
+        lifetime.start_run(&mut run);
+        run.max_speed = 12.0;
+        run.max_altitude = 3.0;
+        lifetime.end_run(&mut run, &ended(150.0, DeathCause::Spikes), 10.0);
+
+        lifetime.start_run(&mut run);
+        run.max_speed = 8.0;
+        run.max_altitude = 5.0;
+        lifetime.end_run(&mut run, &ended(100.0, DeathCause::Fell), 20.0);
+
+        assert_eq!(lifetime.runs_played, 2);
+        assert_eq!(lifetime.total_distance, 250.0);
+        assert_eq!(lifetime.best_distance, 150.0);
+        assert_eq!(lifetime.deaths_by(DeathCause::Spikes), 1);
+        assert_eq!(lifetime.deaths_by(DeathCause::Fell), 1);
+        assert_eq!(lifetime.max_speed, 12.0);
+        assert_eq!(lifetime.max_altitude, 5.0);
+        assert_eq!(run.distance, 100.0);
+        assert_eq!(run.cause, Some(DeathCause::Fell));
+    }
+
+    #[test]
+    fn a_rewound_run_only_adds_the_distance_it_gained() {
+        let mut lifetime = LifetimeStats::default();
+        let mut run = RunStats::default();
+
+        lifetime.start_run(&mut run);
+        lifetime.end_run(&mut run, &ended(300.0, DeathCause::Spikes), 10.0);
+        lifetime.end_run(&mut run, &ended(420.0, DeathCause::Fell), 20.0);
+
+        assert_eq!(lifetime.runs_played, 1);
+        assert_eq!(lifetime.total_distance, 420.0);
+        assert_eq!(lifetime.best_distance, 420.0);
+        assert_eq!(lifetime.deaths_by(DeathCause::Spikes), 1);
+        assert_eq!(lifetime.deaths_by(DeathCause::Fell), 1);
+    }
+
+    #[test]
+    fn finishing_a_time_trial_is_not_a_death() {
+        let mut lifetime = LifetimeStats::default();
+        let mut run = RunStats::default();
+
+        lifetime.start_run(&mut run);
+        lifetime.end_run(&mut run, &ended(500.0, DeathCause::Finished), 30.0);
+
+        assert_eq!(lifetime.finishes, 1);
+        assert_eq!(lifetime.deaths_by(DeathCause::Finished), 0);
+        assert!(lifetime.deaths.is_empty());
+        assert_eq!(run.cause, Some(DeathCause::Finished));
+    }
+
+    #[test]
+    fn dive_time_counts_dives_cut_short_by_death() {
+        let mut lifetime = LifetimeStats::default();
+        let mut run = RunStats::default();
+
+        lifetime.start_run(&mut run);
+        run.start_dive(1.0);
+        lifetime.end_dive(&mut run, 3.0);
+        run.start_dive(5.0);
+        // Dive input repeats while held.
+        run.start_dive(5.5);
+        lifetime.end_run(&mut run, &ended(50.0, DeathCause::Spikes), 6.0);
+
+        assert_eq!(run.dive_time, 3.0);
+        assert_eq!(lifetime.total_dive_time, 3.0);
+
+        lifetime.start_run(&mut run);
+        lifetime.end_dive(&mut run, 7.0);
+
+        assert_eq!(run.dive_time, 0.0);
+        assert_eq!(lifetime.total_dive_time, 3.0);
+    }
+}
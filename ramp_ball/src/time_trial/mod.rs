@@ -0,0 +1,271 @@
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    contacts::ContactsSet,
+    events::{DeathCause, RunEnded},
+    game_mode::GameMode,
+    platforms::Platform,
+    player::{Player, PlayerState, TravelDistanceMeters},
+    rewind::Rewound,
+    save::{self, SaveDirectory},
+    GameState,
+};
+
+/// The clock every run keeps, and the finish line that timed modes race it
+/// to at the end of their course.
+pub struct TimeTrialPlugin;
+
+impl Plugin for TimeTrialPlugin {
+    fn build(&self, app: &mut App) {
+        let saves = save::directory(app);
+
+        app.init_resource::<RunClock>()
+            .insert_resource(saves.load::<TimeTrialRecord>(TIME_TRIAL_FILE))
+            .add_systems(OnEnter(GameState::Playing), reset_run_clock)
+            .add_systems(Update, rewind_run_clock)
+            .add_systems(
+                Update,
+                place_finish_line.run_if(in_state(GameState::MainMenu)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    tick_run_clock,
+                    cross_finish_line.run_if(|mode: Res<GameMode>| mode.rules().timed),
+                )
+                    .chain()
+                    .after(ContactsSet)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Alive)),
+            );
+    }
+}
+
+const TIME_TRIAL_FILE: &str = "time_trial.ron";
+
+/// How far before the end of the last platform the finish line is drawn.
+const FINISH_MARGIN: f32 = 100.0;
+
+/// Seconds since the ball was dropped, counted on the fixed tick.
+#[derive(Resource, Debug, Default)]
+pub struct RunClock {
+    pub seconds: f32,
+    /// Set once the ball has crossed a finish line.
+    pub finished: bool,
+}
+
+/// Fastest finish of the time trial course.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeTrialRecord {
+    pub best: Option<f32>,
+}
+
+impl RunClock {
+    /// Winds the clock back, taking back a finish.
+    fn rewind(&mut self, seconds: f32) {
+        self.seconds = (self.seconds - seconds).max(0.0);
+        self.finished = false;
+    }
+}
+
+impl TimeTrialRecord {
+    /// Keeps `seconds` if it beats the best time, and says whether it did.
+    fn record(&mut self, seconds: f32) -> bool {
+        if self.best.map_or(true, |best| seconds < best) {
+            self.best = Some(seconds);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Component)]
+struct FinishLine {
+    x: f32,
+}
+
+fn reset_run_clock(mut clock: ResMut<RunClock>) {
+    *clock = RunClock::default();
+}
+
+fn tick_run_clock(time: Res<Time>, mut clock: ResMut<RunClock>) {
+    clock.seconds += time.delta_seconds();
+}
+
+fn rewind_run_clock(
+    mut rewinds: EventReader<Rewound>,
+    time: Res<Time<Fixed>>,
+    mut clock: ResMut<RunClock>,
+) {
+    for rewound in rewinds.read() {
+        clock.rewind(rewound.ticks as f32 * time.timestep().as_secs_f32());
+    }
+}
+
+/// Puts the finish line at the end of the course once its platforms are in,
+/// and takes it away in modes without one.
+fn place_finish_line(
+    mode: Res<GameMode>,
+    platforms: Query<(&Platform, &Transform)>,
+    added: Query<(), Added<Platform>>,
+    finish_lines: Query<Entity, With<FinishLine>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if added.is_empty() && !mode.is_changed() {
+        return;
+    }
+
+    for entity in finish_lines.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !mode.rules().timed {
+        return;
+    }
+
+    let course_end = platforms
+        .iter()
+        .filter_map(|(platform, transform)| {
+            let last_point = platform.points.last()?;
+            Some(transform.translation.x + last_point.x)
+        })
+        .max_by(f32::total_cmp);
+
+    let Some(course_end) = course_end else {
+        return;
+    };
+
+    let x = course_end - FINISH_MARGIN;
+    commands.spawn((
+        FinishLine { x },
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Rectangle::new(10.0, 4000.0))),
+            material: materials.add(Color::hsla(0.0, 0.0, 1.0, 0.75)),
+            transform: Transform::from_xyz(x, 0.0, -1.0),
+            ..default()
+        },
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cross_finish_line(
+    players: Query<&Transform, With<Player>>,
+    finish_lines: Query<&FinishLine>,
+    distance: Res<TravelDistanceMeters>,
+    mut clock: ResMut<RunClock>,
+    mut record: ResMut<TimeTrialRecord>,
+    mut next_state: ResMut<NextState<PlayerState>>,
+    mut run_ended: EventWriter<RunEnded>,
+    saves: Res<SaveDirectory>,
+) {
+    let Ok(finish_line) = finish_lines.get_single() else {
+        return;
+    };
+
+    let crossed = players
+        .iter()
+        .any(|transform| transform.translation.x >= finish_line.x);
+
+    if !crossed || matches!(*next_state, NextState::Pending(PlayerState::Dead)) {
+        return;
+    }
+
+    clock.finished = true;
+    next_state.set(PlayerState::Dead);
+    run_ended.send(RunEnded {
+        distance: distance.0,
+        cause: DeathCause::Finished,
+    });
+
+    if record.record(clock.seconds) {
+        saves.store(TIME_TRIAL_FILE, &*record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::GameHarness;
+
+    #[test]
+    fn rewinding_the_clock_takes_back_the_finish() {
+        let mut clock = RunClock {
+            seconds: 10.0,
+            finished: true,
+        };
+
+        clock.rewind(4.0);
+        assert_eq!(clock.seconds, 6.0);
+        assert!(!clock.finished);
+
+        clock.rewind(8.0);
+        assert_eq!(clock.seconds, 0.0);
+    }
+
+    #[test]
+    fn only_faster_finishes_are_records() {
+        let mut record = TimeTrialRecord::default();
+
+        assert!(record.record(42.0));
+        assert!(!record.record(45.0));
+        assert!(!record.record(42.0));
+        assert!(record.record(40.5));
+        assert_eq!(record.best, Some(40.5));
+    }
+
+    #[test]
+    fn the_clock_counts_fixed_ticks() {
+        let mut harness = GameHarness::new();
+
+        harness.start_run();
+        let started = harness.world().resource::<RunClock>().seconds;
+        harness.run_ticks(64);
+
+        let clock = harness.world().resource::<RunClock>();
+        assert!(
+            (clock.seconds - started - 1.0).abs() < 1e-4,
+            "{}",
+            clock.seconds
+        );
+        assert!(!clock.finished);
+    }
+
+    #[test]
+    fn time_trial_runs_on_its_course_until_the_finish_line() {
+        let mut harness = GameHarness::new();
+
+        harness.select_mode(GameMode::TimeTrial);
+        let course_loaded = harness.run_until(5.0, |harness| {
+            let platforms = harness
+                .world_mut()
+                .query::<&Platform>()
+                .iter(harness.world())
+                .count();
+            platforms > 2
+        });
+        assert!(course_loaded);
+
+        harness.start_run();
+        harness.run_seconds(1.0);
+        harness.teleport_player(Vec2::new(12_250.0, 0.0));
+        harness.run_ticks(2);
+
+        assert_eq!(harness.player_state(), PlayerState::Dead);
+        assert_eq!(
+            harness.recorded::<RunEnded>()[0].cause,
+            DeathCause::Finished
+        );
+
+        let clock = harness.world().resource::<RunClock>();
+        assert!(clock.finished);
+        assert!(clock.seconds >= 1.0);
+    }
+}
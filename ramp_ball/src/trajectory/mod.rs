@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    player::{Player, PlayerState},
+    rewind::Rewound,
+    GameState,
+};
+
+pub struct TrajectoryPlugin;
+
+impl Plugin for TrajectoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Trajectory>()
+            .add_systems(OnEnter(GameState::Playing), reset_trajectory)
+            .add_systems(Update, rewind_trajectory)
+            .add_systems(
+                FixedUpdate,
+                record_player_position
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Alive)),
+            );
+    }
+}
+
+/// The path of the ball during a run, sampled once per fixed tick.
+#[derive(Resource, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Trajectory {
+    pub sample_interval: f32,
+    pub samples: Vec<Vec2>,
+}
+
+impl Trajectory {
+    pub fn duration(&self) -> f32 {
+        self.samples.len() as f32 * self.sample_interval
+    }
+
+    /// Interpolated position at `time` seconds into the run. Holds the last
+    /// sample once the recording has ended.
+    pub fn position_at(&self, time: f32) -> Option<Vec2> {
+        let last = *self.samples.last()?;
+
+        if self.sample_interval <= 0.0 {
+            return Some(last);
+        }
+
+        let index = time.max(0.0) / self.sample_interval;
+        let lower = index.floor() as usize;
+
+        match (self.samples.get(lower), self.samples.get(lower + 1)) {
+            (Some(from), Some(to)) => Some(from.lerp(*to, index.fract())),
+            (Some(from), None) => Some(*from),
+            _ => Some(last),
+        }
+    }
+}
+
+fn reset_trajectory(mut trajectory: ResMut<Trajectory>, time: Res<Time<Fixed>>) {
+    trajectory.sample_interval = time.timestep().as_secs_f32();
+    trajectory.samples.clear();
+}
+
+fn record_player_position(
+    player_query: Query<&Transform, With<Player>>,
+    mut trajectory: ResMut<Trajectory>,
+) {
+    if let Ok(player_transform) = player_query.get_single() {
+        trajectory
+            .samples
+            .push(player_transform.translation.truncate());
+    }
+}
+
+fn rewind_trajectory(mut rewinds: EventReader<Rewound>, mut trajectory: ResMut<Trajectory>) {
+    for rewound in rewinds.read() {
+        let length = trajectory
+            .samples
+            .len()
+            .saturating_sub(rewound.ticks as usize);
+        trajectory.samples.truncate(length);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trajectory(sample_interval: f32) -> Trajectory {
+        Trajectory {
+            sample_interval,
+            samples: vec![Vec2::ZERO, Vec2::new(10.0, 0.0), Vec2::new(10.0, 20.0)],
+        }
+    }
+
+    #[test]
+    fn positions_between_samples_are_interpolated() {
+        let trajectory = trajectory(0.5);
+
+        assert_eq!(trajectory.position_at(0.0), Some(Vec2::ZERO));
+        assert_eq!(trajectory.position_at(0.25), Some(Vec2::new(5.0, 0.0)));
+        assert_eq!(trajectory.position_at(0.75), Some(Vec2::new(10.0, 10.0)));
+        assert_eq!(trajectory.position_at(-1.0), Some(Vec2::ZERO));
+    }
+
+    #[test]
+    fn the_last_sample_is_held_after_the_recording_ends() {
+        let trajectory = trajectory(0.5);
+
+        assert_eq!(trajectory.position_at(1.0), Some(Vec2::new(10.0, 20.0)));
+        assert_eq!(trajectory.position_at(60.0), Some(Vec2::new(10.0, 20.0)));
+        assert_eq!(Trajectory::default().position_at(0.0), None);
+    }
+
+    #[test]
+    fn a_zero_interval_holds_the_last_sample() {
+        let trajectory = trajectory(0.0);
+
+        assert_eq!(trajectory.position_at(0.0), Some(Vec2::new(10.0, 20.0)));
+        assert_eq!(trajectory.position_at(1.0), Some(Vec2::new(10.0, 20.0)));
+    }
+}
@@ -0,0 +1,134 @@
+use avian2d::prelude::*;
+use bevy::{asset::LoadState, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::ron_asset::RonAssetLoader;
+
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Tuning>()
+            .register_asset_loader(RonAssetLoader::<Tuning>::new(&["tuning.ron"]))
+            .init_resource::<Tuning>()
+            .add_systems(Startup, load_tuning)
+            .add_systems(
+                PreUpdate,
+                (
+                    apply_tuning_asset,
+                    apply_gravity.run_if(resource_changed::<Tuning>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+const TUNING_PATH: &str = "game.tuning.ron";
+
+/// The numbers that decide how the game feels. Loaded from
+/// `assets/game.tuning.ron` and reloaded whenever that file changes; the live
+/// copy is kept as a resource and each plugin applies it to its entities.
+#[derive(Asset, Resource, TypePath, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tuning {
+    pub gravity: f32,
+    pub player_radius: f32,
+    pub dive_gravity_scale: f32,
+    pub glide_gravity_scale: f32,
+    pub rise_speed: f32,
+    pub sink_speed: f32,
+    /// How far the player has to be past a platform before it sinks.
+    pub sink_distance: f32,
+    pub number_of_spikes: u32,
+    pub spike_speed: f32,
+    /// Size of the stretch of world the course is laid out in: where the ball
+    /// falls off, how tall the spike wall is, where platforms rise from. Kept
+    /// apart from the window so a run plays out the same at any window size.
+    pub course_width: f32,
+    pub course_height: f32,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self {
+            gravity: 200.0,
+            player_radius: 50.0,
+            dive_gravity_scale: 10.0,
+            glide_gravity_scale: 1.0,
+            rise_speed: 500.0,
+            sink_speed: 500.0,
+            sink_distance: 50.0,
+            number_of_spikes: 25,
+            spike_speed: 50.0,
+            course_width: 1280.0,
+            course_height: 720.0,
+        }
+    }
+}
+
+impl Tuning {
+    pub fn course_size(&self) -> Vec2 {
+        Vec2::new(self.course_width, self.course_height)
+    }
+
+    pub fn gravity_scale(&self, diving: bool) -> f32 {
+        if diving {
+            self.dive_gravity_scale
+        } else {
+            self.glide_gravity_scale
+        }
+    }
+}
+
+#[derive(Resource)]
+struct TuningHandle(Handle<Tuning>);
+
+/// Whether the tuning file has been applied, or failed to load, so the live
+/// tuning won't change under a simulation about to start.
+pub fn tuning_settled(world: &World) -> bool {
+    let Some(handle) = world.get_resource::<TuningHandle>() else {
+        return false;
+    };
+
+    match world.resource::<AssetServer>().get_load_state(&handle.0) {
+        Some(LoadState::Loaded) => {
+            world.resource::<Assets<Tuning>>().get(&handle.0) == Some(world.resource::<Tuning>())
+        }
+        Some(LoadState::Failed(_)) => true,
+        _ => false,
+    }
+}
+
+fn load_tuning(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(TuningHandle(asset_server.load(TUNING_PATH)));
+}
+
+/// Copies the asset into the live resource when it first loads and every
+/// time the file is edited.
+fn apply_tuning_asset(
+    mut events: EventReader<AssetEvent<Tuning>>,
+    handle: Option<Res<TuningHandle>>,
+    assets: Res<Assets<Tuning>>,
+    mut tuning: ResMut<Tuning>,
+) {
+    let Some(handle) = handle else {
+        return;
+    };
+
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&handle.0) && !event.is_modified(&handle.0) {
+            continue;
+        }
+
+        if let Some(loaded) = assets.get(&handle.0) {
+            if *loaded != *tuning {
+                info!("applying tuning from {}", TUNING_PATH);
+                *tuning = *loaded;
+            }
+        }
+    }
+}
+
+fn apply_gravity(tuning: Res<Tuning>, mut gravity: ResMut<Gravity>) {
+    gravity.0 = Vec2::NEG_Y * tuning.gravity;
+}
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+use bevy_bsml::prelude::*;
+
+use crate::achievements::AchievementRecord;
+
+pub struct AchievementsMenuPlugin;
+
+impl Plugin for AchievementsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OpenAchievementsMenu>().add_systems(
+            Update,
+            (
+                open_achievements_menu,
+                handle_close_button_pressed,
+                update_achievements.run_if(resource_changed::<AchievementRecord>),
+            ),
+        );
+    }
+}
+
+/// Sent by other menus to list the achievements on top of them.
+#[derive(Event)]
+pub struct OpenAchievementsMenu;
+
+#[derive(Component)]
+pub struct AchievementsMenu {
+    achievements: String,
+}
+
+#[derive(Component)]
+struct CloseButton;
+
+bsml! {AchievementsMenu;
+    (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_CENTER, ITEMS_CENTER, BG_TRANSPARENT]) {
+        (node class=[FLEX_COL, ITEMS_CENTER, gap(12.5)]) {
+            (text class=[FontSize::px(40.0)]) { "Achievements" }
+            (text class=[FontSize::px(24.0)]) { "{}", self.achievements }
+            (node labels=[CloseButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                (text class=[FontSize::px(30.0)]) { "Close" }
+            }
+        }
+    }
+}
+
+fn open_achievements_menu(
+    mut commands: Commands,
+    mut events: EventReader<OpenAchievementsMenu>,
+    menus: Query<(), With<AchievementsMenu>>,
+    record: Res<AchievementRecord>,
+) {
+    if events.read().count() > 0 && menus.is_empty() {
+        commands.spawn_bsml(AchievementsMenu {
+            achievements: record.describe(),
+        });
+    }
+}
+
+fn update_achievements(mut menus: Query<&mut AchievementsMenu>, record: Res<AchievementRecord>) {
+    let text = record.describe();
+    for mut menu in menus.iter_mut() {
+        if menu.achievements != text {
+            menu.achievements = text.clone();
+        }
+    }
+}
+
+fn handle_close_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<CloseButton>)>,
+    menus: Query<Entity, With<AchievementsMenu>>,
+    mut commands: Commands,
+) {
+    if interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        for entity in menus.iter() {
+            commands.despawn_bsml(entity);
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState, InputSystem,
+    },
+    prelude::*,
+};
+use bevy_bsml::prelude::*;
+
+use crate::{
+    actions::ActionSet,
+    challenge::{ActiveChallenge, Challenge, EndChallenge, PlayChallenge, SystemClipboard},
+};
+
+pub struct ChallengeMenuPlugin;
+
+impl Plugin for ChallengeMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OpenChallengeMenu>()
+            .add_systems(
+                PreUpdate,
+                swallow_keyboard
+                    .run_if(challenge_menu_open)
+                    .after(InputSystem)
+                    .before(ActionSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    open_challenge_menu,
+                    type_code,
+                    handle_paste_button_pressed,
+                    handle_play_button_pressed,
+                    handle_clear_button_pressed,
+                    handle_close_button_pressed,
+                ),
+            );
+    }
+}
+
+/// Longest code that can be typed in, with room for dashes and spaces.
+const MAX_INPUT_LENGTH: usize = 48;
+
+/// Sent by the main menu to enter a friend's challenge code.
+#[derive(Event)]
+pub struct OpenChallengeMenu;
+
+#[derive(Component)]
+pub struct ChallengeMenu {
+    input: String,
+    status: String,
+}
+
+#[derive(Component)]
+struct PasteButton;
+
+#[derive(Component)]
+struct PlayButton;
+
+#[derive(Component)]
+struct ClearButton;
+
+#[derive(Component)]
+struct CloseButton;
+
+bsml! {ChallengeMenu;
+    (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_CENTER, ITEMS_CENTER, BG_TRANSPARENT]) {
+        (node class=[FLEX_COL, ITEMS_CENTER, gap(12.5)]) {
+            (text class=[FontSize::px(40.0)]) { "Challenge" }
+            (text class=[FontSize::px(20.0)]) { "Type or paste a friend's code" }
+            (text class=[FontSize::px(30.0)]) { "{}_", self.input }
+            (text class=[FontSize::px(20.0)]) { "{}", self.status }
+            (node class=[gap(12.5)]) {
+                (node labels=[PasteButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Paste" }
+                }
+                (node labels=[PlayButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Play" }
+                }
+                (node labels=[ClearButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Clear" }
+                }
+                (node labels=[CloseButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Close" }
+                }
+            }
+        }
+    }
+}
+
+fn challenge_menu_open(menus: Query<(), With<ChallengeMenu>>) -> bool {
+    !menus.is_empty()
+}
+
+/// Keeps typing a code from also dropping the ball or opening menus.
+fn swallow_keyboard(mut keys: ResMut<ButtonInput<KeyCode>>) {
+    keys.reset_all();
+}
+
+fn open_challenge_menu(
+    mut commands: Commands,
+    mut events: EventReader<OpenChallengeMenu>,
+    menus: Query<(), With<ChallengeMenu>>,
+    active: Option<Res<ActiveChallenge>>,
+) {
+    if events.read().count() > 0 && menus.is_empty() {
+        commands.spawn_bsml(ChallengeMenu {
+            input: String::new(),
+            status: match active {
+                Some(active) => format!("Playing a challenge to beat {}m", active.challenge.target),
+                None => String::new(),
+            },
+        });
+    }
+}
+
+fn type_code(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut menus: Query<(Entity, &mut ChallengeMenu)>,
+    mut play_challenge: EventWriter<PlayChallenge>,
+    mut commands: Commands,
+) {
+    let Ok((entity, mut menu)) = menus.get_single_mut() else {
+        keyboard_events.clear();
+        return;
+    };
+
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Enter => {
+                if submit(&mut menu, &mut play_challenge) {
+                    commands.despawn_bsml(entity);
+                }
+            }
+            Key::Escape => commands.despawn_bsml(entity),
+            Key::Backspace => {
+                menu.input.pop();
+            }
+            Key::Character(text) if menu.input.len() + text.len() <= MAX_INPUT_LENGTH => {
+                menu.input.push_str(text);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Starts the typed challenge. Returns whether the code was good.
+fn submit(menu: &mut ChallengeMenu, play_challenge: &mut EventWriter<PlayChallenge>) -> bool {
+    match menu.input.parse::<Challenge>() {
+        Ok(challenge) => {
+            if challenge.from_other_version() {
+                warn!("Challenge code is from another version of the game");
+            }
+            play_challenge.send(PlayChallenge(challenge));
+            true
+        }
+        Err(error) => {
+            menu.status = format!("Can't read that code: {}", error);
+            false
+        }
+    }
+}
+
+fn handle_paste_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<PasteButton>)>,
+    mut menus: Query<&mut ChallengeMenu>,
+    mut clipboard: NonSendMut<SystemClipboard>,
+) {
+    if !interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+
+    for mut menu in menus.iter_mut() {
+        match clipboard.paste() {
+            Ok(text) => {
+                menu.input = text.trim().chars().take(MAX_INPUT_LENGTH).collect();
+                menu.status = String::new();
+            }
+            Err(error) => menu.status = format!("Nothing to paste: {}", error),
+        }
+    }
+}
+
+fn handle_play_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<PlayButton>)>,
+    mut menus: Query<(Entity, &mut ChallengeMenu)>,
+    mut play_challenge: EventWriter<PlayChallenge>,
+    mut commands: Commands,
+) {
+    if !interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+
+    for (entity, mut menu) in menus.iter_mut() {
+        if submit(&mut menu, &mut play_challenge) {
+            commands.despawn_bsml(entity);
+        }
+    }
+}
+
+fn handle_clear_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<ClearButton>)>,
+    mut menus: Query<&mut ChallengeMenu>,
+    mut end_challenge: EventWriter<EndChallenge>,
+) {
+    if !interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+
+    end_challenge.send(EndChallenge);
+    for mut menu in menus.iter_mut() {
+        menu.input.clear();
+        menu.status = "Back to playing without a target".to_string();
+    }
+}
+
+fn handle_close_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<CloseButton>)>,
+    menus: Query<Entity, With<ChallengeMenu>>,
+    mut commands: Commands,
+) {
+    if interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        for entity in menus.iter() {
+            commands.despawn_bsml(entity);
+        }
+    }
+}
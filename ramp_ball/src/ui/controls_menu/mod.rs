@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+use bevy_bsml::prelude::*;
+
+use crate::actions::{Action, ActionBindings, PendingRebind};
+
+pub struct ControlsMenuPlugin;
+
+impl Plugin for ControlsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OpenControlsMenu>().add_systems(
+            Update,
+            (
+                open_controls_menu,
+                update_controls_menu,
+                rebind_on_press::<RebindDiveButton>(Action::Dive),
+                rebind_on_press::<RebindPauseButton>(Action::Pause),
+                rebind_on_press::<RebindConfirmButton>(Action::Confirm),
+                rebind_on_press::<RebindBackButton>(Action::Back),
+                rebind_on_press::<RebindLeftDiveButton>(Action::LeftDive),
+                rebind_on_press::<RebindRightDiveButton>(Action::RightDive),
+                handle_close_button_pressed,
+            ),
+        );
+    }
+}
+
+/// Sent by other menus to show the controls screen on top of them.
+#[derive(Event)]
+pub struct OpenControlsMenu;
+
+#[derive(Component)]
+pub struct ControlsMenu {
+    dive: String,
+    pause: String,
+    confirm: String,
+    back: String,
+    left_dive: String,
+    right_dive: String,
+    prompt: &'static str,
+}
+
+#[derive(Component)]
+struct RebindDiveButton;
+
+#[derive(Component)]
+struct RebindPauseButton;
+
+#[derive(Component)]
+struct RebindConfirmButton;
+
+#[derive(Component)]
+struct RebindBackButton;
+
+#[derive(Component)]
+struct RebindLeftDiveButton;
+
+#[derive(Component)]
+struct RebindRightDiveButton;
+
+#[derive(Component)]
+struct CloseButton;
+
+bsml! {ControlsMenu;
+    (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_CENTER, ITEMS_CENTER, BG_TRANSPARENT]) {
+        (node class=[FLEX_COL, ITEMS_CENTER, gap(12.5)]) {
+            (text class=[FontSize::px(40.0)]) { "Controls" }
+            (text class=[FontSize::px(24.0)]) { "{}", self.prompt }
+            (node class=[gap(12.5), ITEMS_CENTER]) {
+                (node labels=[RebindDiveButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Dive" }
+                }
+                (text class=[FontSize::px(20.0)]) { "{}", self.dive }
+            }
+            (node class=[gap(12.5), ITEMS_CENTER]) {
+                (node labels=[RebindPauseButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Pause" }
+                }
+                (text class=[FontSize::px(20.0)]) { "{}", self.pause }
+            }
+            (node class=[gap(12.5), ITEMS_CENTER]) {
+                (node labels=[RebindConfirmButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Confirm" }
+                }
+                (text class=[FontSize::px(20.0)]) { "{}", self.confirm }
+            }
+            (node class=[gap(12.5), ITEMS_CENTER]) {
+                (node labels=[RebindBackButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Back" }
+                }
+                (text class=[FontSize::px(20.0)]) { "{}", self.back }
+            }
+            (node class=[gap(12.5), ITEMS_CENTER]) {
+                (node labels=[RebindLeftDiveButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "P1 Dive" }
+                }
+                (text class=[FontSize::px(20.0)]) { "{}", self.left_dive }
+            }
+            (node class=[gap(12.5), ITEMS_CENTER]) {
+                (node labels=[RebindRightDiveButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "P2 Dive" }
+                }
+                (text class=[FontSize::px(20.0)]) { "{}", self.right_dive }
+            }
+            (node labels=[CloseButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                (text class=[FontSize::px(30.0)]) { "Close" }
+            }
+        }
+    }
+}
+
+fn controls_menu(bindings: &ActionBindings, pending: &PendingRebind) -> ControlsMenu {
+    ControlsMenu {
+        dive: bindings.describe(Action::Dive),
+        pause: bindings.describe(Action::Pause),
+        confirm: bindings.describe(Action::Confirm),
+        back: bindings.describe(Action::Back),
+        left_dive: bindings.describe(Action::LeftDive),
+        right_dive: bindings.describe(Action::RightDive),
+        prompt: if pending.0.is_some() {
+            "Press a key or button..."
+        } else {
+            "Pick an action to rebind"
+        },
+    }
+}
+
+fn open_controls_menu(
+    mut commands: Commands,
+    mut events: EventReader<OpenControlsMenu>,
+    menus: Query<(), With<ControlsMenu>>,
+    bindings: Res<ActionBindings>,
+    pending: Res<PendingRebind>,
+) {
+    if events.read().count() > 0 && menus.is_empty() {
+        commands.spawn_bsml(controls_menu(&bindings, &pending));
+    }
+}
+
+fn update_controls_menu(
+    mut menus: Query<&mut ControlsMenu>,
+    bindings: Res<ActionBindings>,
+    pending: Res<PendingRebind>,
+) {
+    if !bindings.is_changed() && !pending.is_changed() {
+        return;
+    }
+
+    for mut menu in menus.iter_mut() {
+        *menu = controls_menu(&bindings, &pending);
+    }
+}
+
+fn rebind_on_press<T: Component>(
+    action: Action,
+) -> impl FnMut(Query<&Interaction, (Changed<Interaction>, With<T>)>, ResMut<PendingRebind>) {
+    move |interactions, mut pending| {
+        if interactions
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed)
+        {
+            pending.0 = Some(action);
+        }
+    }
+}
+
+fn handle_close_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<CloseButton>)>,
+    menus: Query<Entity, With<ControlsMenu>>,
+    mut pending: ResMut<PendingRebind>,
+    mut commands: Commands,
+) {
+    if interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        pending.0 = None;
+        for entity in menus.iter() {
+            commands.despawn_bsml(entity);
+        }
+    }
+}
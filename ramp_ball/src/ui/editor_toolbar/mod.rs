@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use bevy_bsml::prelude::*;
+
+use crate::{
+    editor::{Editor, EditorButton, EditorCommand},
+    GameState,
+};
+
+pub struct EditorToolbarPlugin;
+
+impl Plugin for EditorToolbarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Editor), spawn_editor_toolbar)
+            .add_systems(OnExit(GameState::Editor), despawn_editor_toolbar)
+            .add_systems(
+                Update,
+                (
+                    update_editor_toolbar.run_if(resource_changed::<Editor>),
+                    send_on_press::<NewPlatformButton>(EditorCommand::NewPlatform),
+                    send_on_press::<KindButton>(EditorCommand::CycleKind),
+                    send_on_press::<SaveButton>(EditorCommand::Save),
+                    send_on_press::<LoadButton>(EditorCommand::Load),
+                    send_on_press::<PlayFromHereButton>(EditorCommand::PlayFromHere),
+                    send_on_press::<ExitButton>(EditorCommand::Exit),
+                )
+                    .run_if(in_state(GameState::Editor)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct EditorToolbar {
+    status: String,
+    kind: String,
+}
+
+#[derive(Component)]
+struct NewPlatformButton;
+
+#[derive(Component)]
+struct KindButton;
+
+#[derive(Component)]
+struct SaveButton;
+
+#[derive(Component)]
+struct LoadButton;
+
+#[derive(Component)]
+struct PlayFromHereButton;
+
+#[derive(Component)]
+struct ExitButton;
+
+bsml! {EditorToolbar;
+    (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_END, ITEMS_CENTER, BG_TRANSPARENT]) {
+        (node class=[FLEX_COL, ITEMS_CENTER, gap(12.5)]) {
+            (text class=[FontSize::px(24.0)]) { "{}", self.status }
+            (node class=[gap(12.5)]) {
+                (node labels=[NewPlatformButton, EditorButton] class=[w_px(100.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "New" }
+                }
+                (node labels=[KindButton, EditorButton] class=[w_px(100.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "{}", self.kind }
+                }
+                (node labels=[SaveButton, EditorButton] class=[w_px(100.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "Save" }
+                }
+                (node labels=[LoadButton, EditorButton] class=[w_px(100.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "Load" }
+                }
+                (node labels=[PlayFromHereButton, EditorButton] class=[w_px(160.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "Play from here" }
+                }
+                (node labels=[ExitButton, EditorButton] class=[w_px(100.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "Back" }
+                }
+            }
+        }
+    }
+}
+
+fn spawn_editor_toolbar(mut commands: Commands, editor: Res<Editor>) {
+    commands.spawn_bsml(EditorToolbar {
+        status: editor.status.clone(),
+        kind: editor.kind.name().to_string(),
+    });
+}
+
+fn despawn_editor_toolbar(query: Query<Entity, With<EditorToolbar>>, mut commands: Commands) {
+    if let Ok(entity) = query.get_single() {
+        commands.despawn_bsml(entity);
+    }
+}
+
+fn update_editor_toolbar(mut toolbar_query: Query<&mut EditorToolbar>, editor: Res<Editor>) {
+    let Ok(mut toolbar) = toolbar_query.get_single_mut() else {
+        return;
+    };
+
+    let kind = editor.kind.name();
+
+    if toolbar.status != editor.status {
+        toolbar.status = editor.status.clone();
+    }
+    if toolbar.kind != kind {
+        toolbar.kind = kind.to_string();
+    }
+}
+
+fn send_on_press<T: Component>(
+    command: EditorCommand,
+) -> impl FnMut(Query<&Interaction, (Changed<Interaction>, With<T>)>, EventWriter<EditorCommand>) {
+    move |interactions, mut editor_commands| {
+        if interactions
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed)
+        {
+            editor_commands.send(command);
+        }
+    }
+}
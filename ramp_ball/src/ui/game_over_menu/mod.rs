@@ -1,59 +1,232 @@
 use bevy::prelude::*;
 use bevy_bsml::prelude::*;
 
-use crate::{player::PlayerState, GameState};
+use crate::{
+    actions::{Action, ActionState},
+    challenge::{Challenge, SystemClipboard},
+    coins::RunCoins,
+    game_mode::GameMode,
+    killcam::KillcamState,
+    online::OnlineRace,
+    player::{PlayerState, TravelDistanceMeters},
+    replay::{ReplayCommand, ReplayPlayback},
+    rewind::{RewindAvailable, RewindBuffer, RewindRequest},
+    save::SaveDirectory,
+    seed::CourseSeed,
+    time_trial::RunClock,
+    versus::Versus,
+    wallet::Wallet,
+    GameState,
+};
 
 pub struct GameOverMenuPlugin;
 
 impl Plugin for GameOverMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(PlayerState::Dead), spawn_game_over_menu)
-            .add_systems(
-                Update,
-                (handle_continue_button_pressed, handle_revive_button_pressed)
-                    .run_if(in_state(PlayerState::Dead)),
-            );
+        app.add_systems(
+            OnExit(KillcamState::Playing),
+            spawn_game_over_menu.run_if(not(resource_exists::<ReplayPlayback>)),
+        )
+        // Versus matches have no killcam to wait for.
+        .add_systems(
+            OnEnter(PlayerState::Dead),
+            spawn_game_over_menu.run_if(resource_exists::<Versus>),
+        )
+        .add_systems(
+            Update,
+            (
+                handle_continue_button_pressed,
+                handle_revive_button_pressed,
+                handle_replay_button_pressed,
+                handle_rewind_button_pressed,
+                handle_share_button_pressed,
+                update_wallet.run_if(resource_changed::<Wallet>),
+            )
+                .run_if(in_state(PlayerState::Dead)),
+        );
     }
 }
 
 #[derive(Component)]
-struct GameOverMenu;
+struct GameOverMenu {
+    title: String,
+    wallet: String,
+    rewind_label: &'static str,
+    revive_label: String,
+    challenge_code: String,
+}
 
 #[derive(Component)]
 struct ContinueButton;
 
+/// Spends a revive from the shop to rewind once the free rewind is used.
 #[derive(Component)]
 struct ReviveButton;
 
+#[derive(Component)]
+struct ReplayButton;
+
+#[derive(Component)]
+struct RewindButton;
+
+/// Makes a challenge code for this run's distance on this course.
+#[derive(Component)]
+struct ShareButton;
+
 bsml! {GameOverMenu;
     (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_CENTER, ITEMS_CENTER, BG_TRANSPARENT]) {
         (node class=[FLEX_COL, ITEMS_CENTER, gap(25.0)]) {
-            (text class=[FontSize::px(40.0)]) { "Game Over" }
+            (text class=[FontSize::px(40.0)]) { "{}", self.title }
+            (text class=[FontSize::px(24.0)]) { "{}", self.wallet }
             (node class=[gap(12.5)]) {
                 (node labels=[ContinueButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
                     (text class=[FontSize::px(30.0)]) { "Continue" }
                 }
                 (node labels=[ReviveButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
-                    (text class=[FontSize::px(30.0)]) { "Revive" }
+                    (text class=[FontSize::px(30.0)]) { "{}", self.revive_label }
+                }
+                (node labels=[RewindButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "{}", self.rewind_label }
+                }
+                (node labels=[ReplayButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Replay" }
+                }
+                (node labels=[ShareButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Share" }
                 }
             }
+            (text class=[FontSize::px(24.0)]) { "{}", self.challenge_code }
         }
     }
 }
 
-fn spawn_game_over_menu(mut commands: Commands) {
-    commands.spawn_bsml(GameOverMenu);
+fn spawn_game_over_menu(
+    mut commands: Commands,
+    rewind_available: Res<RewindAvailable>,
+    versus: Option<Res<Versus>>,
+    online_race: Option<Res<OnlineRace>>,
+    clock: Res<RunClock>,
+    wallet: Res<Wallet>,
+    run_coins: Res<RunCoins>,
+) {
+    let title = match versus.as_deref() {
+        Some(Versus {
+            winner: Some(winner),
+        }) => format!("{} wins!", winner.name()),
+        Some(Versus { winner: None }) => "Draw".to_string(),
+        None if clock.finished => format!("Finished in {:.2}s", clock.seconds),
+        None => "Game Over".to_string(),
+    };
+
+    // Rewinding would be cheating against other players.
+    let can_rewind = versus.is_none() && online_race.is_none();
+
+    commands.spawn_bsml(GameOverMenu {
+        title,
+        wallet: describe_wallet(&wallet, &run_coins),
+        rewind_label: if rewind_available.0 && can_rewind {
+            "Rewind"
+        } else {
+            "Used"
+        },
+        revive_label: if can_rewind && wallet.revives() > 0 {
+            format!("Revive ({})", wallet.revives())
+        } else {
+            "No revives".to_string()
+        },
+        challenge_code: String::new(),
+    });
 }
 
 fn handle_continue_button_pressed(
     interactions: Query<&Interaction, (Changed<Interaction>, With<ContinueButton>)>,
     menus: Query<Entity, With<GameOverMenu>>,
+    actions: Res<ActionState>,
     mut next_state: ResMut<NextState<GameState>>,
     mut commands: Commands,
+) {
+    // The menu only exists once the killcam is over, so keyboard and gamepad
+    // presses before that don't skip it.
+    let Ok(game_over_menu) = menus.get_single() else {
+        return;
+    };
+
+    let action_pressed =
+        actions.just_pressed(Action::Confirm) || actions.just_pressed(Action::Back);
+    let button_pressed = interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+
+    if action_pressed || button_pressed {
+        next_state.set(GameState::MainMenu);
+        commands.entity(game_over_menu).despawn_recursive();
+    }
+}
+
+fn describe_wallet(wallet: &Wallet, run_coins: &RunCoins) -> String {
+    format!("+{} coins this run\n{}", run_coins.0, wallet.describe())
+}
+
+fn update_wallet(
+    mut menus: Query<&mut GameOverMenu>,
+    wallet: Res<Wallet>,
+    run_coins: Res<RunCoins>,
+) {
+    let text = describe_wallet(&wallet, &run_coins);
+    for mut menu in menus.iter_mut() {
+        if menu.wallet != text {
+            menu.wallet = text.clone();
+        }
+    }
+}
+
+/// The free rewind goes first; a revive is only spent once it's used up.
+#[allow(clippy::too_many_arguments)]
+fn handle_revive_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<ReviveButton>)>,
+    menus: Query<Entity, With<GameOverMenu>>,
+    mut rewind_available: ResMut<RewindAvailable>,
+    buffer: Res<RewindBuffer>,
+    versus: Option<Res<Versus>>,
+    online_race: Option<Res<OnlineRace>>,
+    mut wallet: ResMut<Wallet>,
+    saves: Res<SaveDirectory>,
+    mut rewind_requests: EventWriter<RewindRequest>,
+    mut commands: Commands,
+) {
+    if versus.is_some() || online_race.is_some() || buffer.is_empty() {
+        return;
+    }
+
+    if !interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+
+    if !rewind_available.0 {
+        if !wallet.use_revive(&saves) {
+            return;
+        }
+        rewind_available.0 = true;
+    }
+
+    rewind_requests.send(RewindRequest);
+    if let Ok(game_over_menu) = menus.get_single() {
+        commands.entity(game_over_menu).despawn_recursive();
+    }
+}
+
+fn handle_replay_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<ReplayButton>)>,
+    menus: Query<Entity, With<GameOverMenu>>,
+    mut replay_commands: EventWriter<ReplayCommand>,
+    mut commands: Commands,
 ) {
     for interaction in interactions.iter() {
         if *interaction == Interaction::Pressed {
-            next_state.set(GameState::MainMenu);
+            replay_commands.send(ReplayCommand::WatchLast);
             if let Ok(game_over_menu) = menus.get_single() {
                 commands.entity(game_over_menu).despawn_recursive();
             }
@@ -62,13 +235,53 @@ fn handle_continue_button_pressed(
     }
 }
 
-fn handle_revive_button_pressed(
-    query: Query<&Interaction, (Changed<Interaction>, With<ReviveButton>)>,
+fn handle_rewind_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<RewindButton>)>,
+    menus: Query<Entity, With<GameOverMenu>>,
+    rewind_available: Res<RewindAvailable>,
+    versus: Option<Res<Versus>>,
+    online_race: Option<Res<OnlineRace>>,
+    mut rewind_requests: EventWriter<RewindRequest>,
+    mut commands: Commands,
 ) {
-    for interaction in query.iter() {
+    if !rewind_available.0 || versus.is_some() || online_race.is_some() {
+        return;
+    }
+
+    for interaction in interactions.iter() {
         if *interaction == Interaction::Pressed {
-            println!("Revive was pressed!");
+            rewind_requests.send(RewindRequest);
+            if let Ok(game_over_menu) = menus.get_single() {
+                commands.entity(game_over_menu).despawn_recursive();
+            }
             break;
         }
     }
 }
+
+fn handle_share_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<ShareButton>)>,
+    mut menus: Query<&mut GameOverMenu>,
+    mode: Res<GameMode>,
+    seed: Res<CourseSeed>,
+    distance: Res<TravelDistanceMeters>,
+    mut clipboard: NonSendMut<SystemClipboard>,
+) {
+    if !interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+
+    let code = Challenge::new(*mode, *seed, distance.0).encode();
+    info!("Challenge code: {}", code);
+
+    let text = match clipboard.copy(&code) {
+        Ok(()) => format!("{} (copied)", code),
+        Err(_) => code,
+    };
+    for mut menu in menus.iter_mut() {
+        menu.challenge_code = text.clone();
+    }
+}
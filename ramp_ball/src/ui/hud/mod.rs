@@ -1,7 +1,17 @@
 use bevy::prelude::*;
 use bevy_bsml::prelude::*;
 
-use crate::{player::TravelDistanceMeters, GameState};
+use crate::{
+    challenge::ActiveChallenge,
+    coins::RunCoins,
+    game_mode::GameMode,
+    ghost::GhostDeltaMeters,
+    missions::MissionBoard,
+    player::{Player, Seat, TravelDistanceMeters},
+    time_trial::{RunClock, TimeTrialRecord},
+    versus::Versus,
+    GameState,
+};
 
 pub struct HudPlugin;
 
@@ -11,27 +21,64 @@ impl Plugin for HudPlugin {
             .add_systems(OnEnter(GameState::MainMenu), despawn_hud)
             .add_systems(
                 Update,
-                update_travel_distance.run_if(in_state(GameState::Playing)),
+                (
+                    update_travel_distance,
+                    update_ghost_delta,
+                    update_run_clock.run_if(|mode: Res<GameMode>| mode.rules().timed),
+                    update_target.run_if(resource_exists::<ActiveChallenge>),
+                    update_coins.run_if(resource_changed::<RunCoins>),
+                    update_missions.run_if(resource_changed::<MissionBoard>),
+                )
+                    .run_if(in_state(GameState::Playing)),
             );
     }
 }
 
 #[derive(Component)]
 struct Hud {
-    travel_distance: f32,
+    travel_distance: String,
+    ghost_delta: String,
+    run_clock: String,
+    target: String,
+    coins: String,
+    missions: String,
 }
 
 bsml! {Hud;
     (node class=[W_FULL, H_FULL]) {
         (node) {
-            (text) { "{}m", self.travel_distance }
+            (text) { "{}", self.travel_distance }
+        }
+        (node) {
+            (text) { "{}", self.ghost_delta }
+        }
+        (node) {
+            (text) { "{}", self.run_clock }
+        }
+        (node) {
+            (text) { "{}", self.target }
+        }
+        (node) {
+            (text) { "{}", self.coins }
+        }
+        (node) {
+            (text class=[FontSize::px(18.0)]) { "{}", self.missions }
         }
     }
 }
 
-fn spawn_hud(mut commands: Commands, travel_distance: Res<TravelDistanceMeters>) {
+fn spawn_hud(
+    mut commands: Commands,
+    travel_distance: Res<TravelDistanceMeters>,
+    board: Res<MissionBoard>,
+) {
     commands.spawn_bsml(Hud {
-        travel_distance: travel_distance.0,
+        travel_distance: format!("{}m", travel_distance.0),
+        ghost_delta: String::new(),
+        run_clock: String::new(),
+        target: String::new(),
+        coins: String::new(),
+        missions: board.describe(true),
     });
 }
 
@@ -41,12 +88,101 @@ fn despawn_hud(query: Query<Entity, With<Hud>>, mut commands: Commands) {
     }
 }
 
+/// Shows how far the run has got, or in versus, how far each ball is.
 fn update_travel_distance(
     mut hud_query: Query<&mut Hud>,
     travel_distance: Res<TravelDistanceMeters>,
+    versus: Option<Res<Versus>>,
+    players: Query<(&Seat, &Transform), With<Player>>,
+) {
+    let Ok(mut hud) = hud_query.get_single_mut() else {
+        return;
+    };
+
+    let text = if versus.is_some() {
+        let mut balls: Vec<_> = players.iter().collect();
+        balls.sort_by_key(|(seat, _)| **seat);
+        balls
+            .into_iter()
+            .map(|(seat, transform)| {
+                format!("{} {:.0}m", seat.name(), transform.translation.x / 100.0)
+            })
+            .collect::<Vec<_>>()
+            .join("    ")
+    } else {
+        format!("{}m", travel_distance.0)
+    };
+
+    if hud.travel_distance != text {
+        hud.travel_distance = text;
+    }
+}
+
+fn update_ghost_delta(mut hud_query: Query<&mut Hud>, ghost_delta: Res<GhostDeltaMeters>) {
+    if let Ok(mut hud) = hud_query.get_single_mut() {
+        hud.ghost_delta = match ghost_delta.0 {
+            Some(delta) => format!("{:+.0}m vs best", delta),
+            None => String::new(),
+        };
+    }
+}
+
+/// Timed modes race the clock, against the best finish so far.
+fn update_run_clock(
+    mut hud_query: Query<&mut Hud>,
+    clock: Res<RunClock>,
+    record: Res<TimeTrialRecord>,
+) {
+    let Ok(mut hud) = hud_query.get_single_mut() else {
+        return;
+    };
+
+    let text = match record.best {
+        Some(best) => format!("{:.2}s (best {:.2}s)", clock.seconds, best),
+        None => format!("{:.2}s", clock.seconds),
+    };
+
+    if hud.run_clock != text {
+        hud.run_clock = text;
+    }
+}
+
+/// A friend's challenge: the distance to beat.
+fn update_target(
+    mut hud_query: Query<&mut Hud>,
+    active: Res<ActiveChallenge>,
+    travel_distance: Res<TravelDistanceMeters>,
 ) {
+    let Ok(mut hud) = hud_query.get_single_mut() else {
+        return;
+    };
+
+    let target = active.challenge.target;
+    let text = if travel_distance.0 > f32::from(target) {
+        format!("Target {}m beaten!", target)
+    } else {
+        format!("Target {}m", target)
+    };
+
+    if hud.target != text {
+        hud.target = text;
+    }
+}
+
+fn update_coins(mut hud_query: Query<&mut Hud>, coins: Res<RunCoins>) {
     if let Ok(mut hud) = hud_query.get_single_mut() {
-        println!("Hello");
-        hud.travel_distance = travel_distance.0;
+        hud.coins = format!("{} coins", coins.0);
+    }
+}
+
+/// The day's missions, with this run's progress.
+fn update_missions(mut hud_query: Query<&mut Hud>, board: Res<MissionBoard>) {
+    let Ok(mut hud) = hud_query.get_single_mut() else {
+        return;
+    };
+
+    let text = board.describe(true);
+    if hud.missions != text {
+        hud.missions = text;
     }
 }
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+use bevy_bsml::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState},
+    killcam::KillcamState,
+};
+
+pub struct KillcamOverlayPlugin;
+
+impl Plugin for KillcamOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(KillcamState::Playing), spawn_killcam_overlay)
+            .add_systems(OnExit(KillcamState::Playing), despawn_killcam_overlay)
+            .add_systems(
+                Update,
+                handle_skip_button_pressed.run_if(in_state(KillcamState::Playing)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct KillcamOverlay;
+
+#[derive(Component)]
+struct SkipButton;
+
+bsml! {KillcamOverlay;
+    (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_END, ITEMS_CENTER, BG_TRANSPARENT]) {
+        (node class=[FLEX_COL, ITEMS_CENTER, gap(12.5)]) {
+            (text class=[FontSize::px(30.0)]) { "How you died" }
+            (node labels=[SkipButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                (text class=[FontSize::px(30.0)]) { "Skip" }
+            }
+        }
+    }
+}
+
+fn spawn_killcam_overlay(mut commands: Commands) {
+    commands.spawn_bsml(KillcamOverlay);
+}
+
+fn despawn_killcam_overlay(query: Query<Entity, With<KillcamOverlay>>, mut commands: Commands) {
+    if let Ok(entity) = query.get_single() {
+        commands.despawn_bsml(entity);
+    }
+}
+
+fn handle_skip_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<SkipButton>)>,
+    actions: Res<ActionState>,
+    mut next_state: ResMut<NextState<KillcamState>>,
+) {
+    if actions.just_pressed(Action::Confirm) || actions.just_pressed(Action::Back) {
+        next_state.set(KillcamState::Idle);
+        return;
+    }
+
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            next_state.set(KillcamState::Idle);
+            break;
+        }
+    }
+}
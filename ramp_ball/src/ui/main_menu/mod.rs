@@ -1,44 +1,500 @@
 use bevy::prelude::*;
 use bevy_bsml::prelude::*;
 
-use crate::GameState;
+use crate::{
+    actions::{Action, ActionState},
+    boost::UseBoosts,
+    challenge::ActiveChallenge,
+    daily::{Daily, DailyRecord},
+    game_mode::{GameMode, SelectGameMode},
+    leaderboard::Leaderboard,
+    missions::MissionBoard,
+    online::{JoinOnlineRace, OnlineRace},
+    replay::{ReplayCommand, ReplayPlayback},
+    seed::CourseSeed,
+    versus::StartVersus,
+    wallet::Wallet,
+    GameState,
+};
+
+use super::{
+    achievements_menu::{AchievementsMenu, OpenAchievementsMenu},
+    challenge_menu::{ChallengeMenu, OpenChallengeMenu},
+    controls_menu::{ControlsMenu, OpenControlsMenu},
+    shop_menu::{OpenShopMenu, ShopMenu},
+    skins_menu::{OpenSkinsMenu, SkinsMenu},
+    stats_menu::{OpenStatsMenu, StatsMenu},
+};
 
 pub struct MainMenuPlugin;
 
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
-            .add_systems(
-                Update,
-                handle_main_menu_pressed.run_if(in_state(GameState::MainMenu)),
-            );
+        app.add_systems(
+            OnEnter(GameState::MainMenu),
+            spawn_main_menu.run_if(not(resource_exists::<ReplayPlayback>)),
+        )
+        .add_systems(OnExit(GameState::MainMenu), despawn_main_menu)
+        .add_systems(
+            Update,
+            (
+                handle_main_menu_pressed,
+                handle_watch_replay_button_pressed,
+                handle_controls_button_pressed,
+                handle_stats_button_pressed,
+                handle_achievements_button_pressed,
+                handle_skins_button_pressed,
+                handle_shop_button_pressed,
+                handle_boost_button_pressed,
+                handle_editor_button_pressed,
+                handle_versus_button_pressed,
+                handle_online_button_pressed,
+                handle_mode_button_pressed,
+                handle_challenge_button_pressed,
+                update_mode.run_if(
+                    resource_changed::<GameMode>
+                        .or_else(resource_exists_and_changed::<Daily>)
+                        .or_else(resource_changed::<DailyRecord>)
+                        .or_else(resource_exists_and_changed::<ActiveChallenge>)
+                        .or_else(resource_removed::<ActiveChallenge>()),
+                ),
+                update_missions.run_if(resource_changed::<MissionBoard>),
+                update_wallet
+                    .run_if(resource_changed::<Wallet>.or_else(resource_changed::<UseBoosts>)),
+                update_leaderboard.run_if(
+                    resource_changed::<Leaderboard>
+                        .or_else(resource_changed::<CourseSeed>)
+                        .or_else(resource_changed::<GameMode>),
+                ),
+            )
+                .run_if(in_state(GameState::MainMenu)),
+        );
     }
 }
 
 #[derive(Component)]
-struct MainMenu;
+struct MainMenu {
+    mode: String,
+    daily: String,
+    leaderboard: String,
+    missions: String,
+    wallet: String,
+    boost: String,
+}
+
+/// Cycles through the game modes.
+#[derive(Component)]
+struct ModeButton;
+
+#[derive(Component)]
+struct WatchReplayButton;
+
+#[derive(Component)]
+struct ControlsButton;
+
+#[derive(Component)]
+struct StatsButton;
+
+#[derive(Component)]
+struct AchievementsButton;
+
+#[derive(Component)]
+struct SkinsButton;
+
+#[derive(Component)]
+struct ShopButton;
+
+/// Turns starting boosts on and off.
+#[derive(Component)]
+struct BoostButton;
+
+#[derive(Component)]
+struct EditorButton;
+
+#[derive(Component)]
+struct VersusButton;
+
+#[derive(Component)]
+struct OnlineButton;
+
+#[derive(Component)]
+struct ChallengeButton;
 
 bsml! {MainMenu;
-    (node class=[W_FULL, H_FULL, JUSTIFY_CENTER, ITEMS_CENTER, BG_TRANSPARENT]) {
+    (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_CENTER, ITEMS_CENTER, BG_TRANSPARENT]) {
         (node class=[h_px(200.0)]) {
             (text) { "Press to drop" }
         }
+        (node labels=[ModeButton] class=[w_px(220.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+            (text class=[FontSize::px(30.0)]) { "{}", self.mode }
+        }
+        (node class=[h_px(30.0), ITEMS_CENTER]) {
+            (text class=[FontSize::px(20.0)]) { "{}", self.daily }
+        }
+        (node class=[h_px(30.0), ITEMS_CENTER]) {
+            (text class=[FontSize::px(20.0)]) { "{}", self.wallet }
+        }
+        (node labels=[BoostButton] class=[w_px(220.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+            (text class=[FontSize::px(30.0)]) { "{}", self.boost }
+        }
+        (node labels=[VersusButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+            (text class=[FontSize::px(30.0)]) { "Versus" }
+        }
+        (node labels=[OnlineButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+            (text class=[FontSize::px(30.0)]) { "Online" }
+        }
+        (node labels=[ChallengeButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+            (text class=[FontSize::px(30.0)]) { "Challenge" }
+        }
+        (node labels=[WatchReplayButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+            (text class=[FontSize::px(30.0)]) { "Replay" }
+        }
+        (node labels=[ControlsButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+            (text class=[FontSize::px(30.0)]) { "Controls" }
+        }
+        (node labels=[StatsButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+            (text class=[FontSize::px(30.0)]) { "Stats" }
+        }
+        (node labels=[AchievementsButton] class=[w_px(220.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+            (text class=[FontSize::px(30.0)]) { "Achievements" }
+        }
+        (node labels=[SkinsButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+            (text class=[FontSize::px(30.0)]) { "Skins" }
+        }
+        (node labels=[ShopButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+            (text class=[FontSize::px(30.0)]) { "Shop" }
+        }
+        (node labels=[EditorButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+            (text class=[FontSize::px(30.0)]) { "Editor" }
+        }
+        (node class=[FLEX_COL, ITEMS_CENTER]) {
+            (text class=[FontSize::px(20.0)]) { "{}", self.missions }
+        }
+        (node class=[FLEX_COL, ITEMS_CENTER]) {
+            (text class=[FontSize::px(20.0)]) { "{}", self.leaderboard }
+        }
     }
 }
 
-fn spawn_main_menu(mut commands: Commands) {
-    commands.spawn_bsml(MainMenu);
+#[allow(clippy::too_many_arguments)]
+fn spawn_main_menu(
+    mut commands: Commands,
+    leaderboard: Res<Leaderboard>,
+    seed: Res<CourseSeed>,
+    mode: Res<GameMode>,
+    daily: Option<Res<Daily>>,
+    record: Res<DailyRecord>,
+    challenge: Option<Res<ActiveChallenge>>,
+    board: Res<MissionBoard>,
+    wallet: Res<Wallet>,
+    use_boosts: Res<UseBoosts>,
+) {
+    let (mode_name, daily) = describe_mode(*mode, daily.as_deref(), &record, challenge.as_deref());
+    commands.spawn_bsml(MainMenu {
+        mode: mode_name,
+        daily,
+        leaderboard: describe_leaderboard(*mode, &leaderboard, *seed),
+        missions: describe_missions(&board),
+        wallet: wallet.describe(),
+        boost: describe_boost(&wallet, &use_boosts),
+    });
+}
+
+fn describe_mode(
+    mode: GameMode,
+    daily: Option<&Daily>,
+    record: &DailyRecord,
+    challenge: Option<&ActiveChallenge>,
+) -> (String, String) {
+    let details = match (challenge, daily) {
+        (Some(active), _) => format!("Challenge: beat {}m", active.challenge.target),
+        (None, Some(daily)) => record.describe(daily.day),
+        (None, None) => String::new(),
+    };
+    (format!("Mode: {}", mode.name()), details)
+}
+
+fn describe_missions(board: &MissionBoard) -> String {
+    format!("Missions\n{}", board.describe(false))
+}
+
+fn describe_boost(wallet: &Wallet, use_boosts: &UseBoosts) -> String {
+    match (wallet.boosts(), use_boosts.0) {
+        (0, _) => "No boosts".to_string(),
+        (_, true) => "Boost: on".to_string(),
+        (_, false) => "Boost: off".to_string(),
+    }
+}
+
+/// Unranked modes have no scores to list.
+fn describe_leaderboard(mode: GameMode, leaderboard: &Leaderboard, seed: CourseSeed) -> String {
+    if mode.rules().ranked {
+        leaderboard.describe(seed)
+    } else {
+        String::new()
+    }
+}
+
+fn update_mode(
+    mut menus: Query<&mut MainMenu>,
+    mode: Res<GameMode>,
+    daily: Option<Res<Daily>>,
+    record: Res<DailyRecord>,
+    challenge: Option<Res<ActiveChallenge>>,
+) {
+    let (mode, daily) = describe_mode(*mode, daily.as_deref(), &record, challenge.as_deref());
+    for mut menu in menus.iter_mut() {
+        if menu.mode != mode || menu.daily != daily {
+            menu.mode = mode.clone();
+            menu.daily = daily.clone();
+        }
+    }
+}
+
+fn update_leaderboard(
+    mut menus: Query<&mut MainMenu>,
+    leaderboard: Res<Leaderboard>,
+    seed: Res<CourseSeed>,
+    mode: Res<GameMode>,
+) {
+    let text = describe_leaderboard(*mode, &leaderboard, *seed);
+    for mut menu in menus.iter_mut() {
+        if menu.leaderboard != text {
+            menu.leaderboard = text.clone();
+        }
+    }
+}
+
+fn update_missions(mut menus: Query<&mut MainMenu>, board: Res<MissionBoard>) {
+    let text = describe_missions(&board);
+    for mut menu in menus.iter_mut() {
+        if menu.missions != text {
+            menu.missions = text.clone();
+        }
+    }
+}
+
+fn update_wallet(mut menus: Query<&mut MainMenu>, wallet: Res<Wallet>, use_boosts: Res<UseBoosts>) {
+    let balance = wallet.describe();
+    let boost = describe_boost(&wallet, &use_boosts);
+    for mut menu in menus.iter_mut() {
+        if menu.wallet != balance || menu.boost != boost {
+            menu.wallet = balance.clone();
+            menu.boost = boost.clone();
+        }
+    }
+}
+
+fn despawn_main_menu(query: Query<Entity, With<MainMenu>>, mut commands: Commands) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
 }
 
 fn handle_main_menu_pressed(
-    query: Query<(Entity, &Interaction), (Changed<Interaction>, With<MainMenu>)>,
+    query: Query<&Interaction, (Changed<Interaction>, With<MainMenu>)>,
+    buttons: Query<
+        &Interaction,
+        Or<(
+            With<ModeButton>,
+            With<WatchReplayButton>,
+            With<ControlsButton>,
+            With<StatsButton>,
+            With<AchievementsButton>,
+            With<SkinsButton>,
+            With<ShopButton>,
+            With<BoostButton>,
+            With<EditorButton>,
+            With<VersusButton>,
+            With<OnlineButton>,
+            With<ChallengeButton>,
+        )>,
+    >,
+    overlays: Query<
+        (),
+        Or<(
+            With<ControlsMenu>,
+            With<StatsMenu>,
+            With<AchievementsMenu>,
+            With<SkinsMenu>,
+            With<ShopMenu>,
+            With<ChallengeMenu>,
+        )>,
+    >,
+    online_race: Option<Res<OnlineRace>>,
+    actions: Res<ActionState>,
     mut next_state: ResMut<NextState<GameState>>,
-    mut commands: Commands,
 ) {
-    for (entity, interaction) in query.iter() {
+    if !overlays.is_empty() {
+        return;
+    }
+
+    // Waiting in a lobby: the server says when to drop.
+    if online_race.is_some_and(|race| race.waiting()) {
+        return;
+    }
+
+    if actions.just_pressed(Action::Confirm) {
+        next_state.set(GameState::Playing);
+        return;
+    }
+
+    // Presses on the menu's buttons shouldn't also drop the ball.
+    if buttons
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+
+    for interaction in query.iter() {
         if *interaction == Interaction::Pressed {
             next_state.set(GameState::Playing);
-            commands.entity(entity).despawn_recursive();
+            break;
+        }
+    }
+}
+
+fn handle_watch_replay_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<WatchReplayButton>)>,
+    mut replay_commands: EventWriter<ReplayCommand>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            replay_commands.send(ReplayCommand::WatchLast);
+            break;
+        }
+    }
+}
+
+fn handle_controls_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<ControlsButton>)>,
+    mut open_controls_menu: EventWriter<OpenControlsMenu>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            open_controls_menu.send(OpenControlsMenu);
+            break;
+        }
+    }
+}
+
+fn handle_stats_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<StatsButton>)>,
+    mut open_stats_menu: EventWriter<OpenStatsMenu>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            open_stats_menu.send(OpenStatsMenu);
+            break;
+        }
+    }
+}
+
+fn handle_editor_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<EditorButton>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            next_state.set(GameState::Editor);
+            break;
+        }
+    }
+}
+
+fn handle_versus_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<VersusButton>)>,
+    mut start_versus: EventWriter<StartVersus>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            start_versus.send(StartVersus);
+            break;
+        }
+    }
+}
+
+fn handle_online_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<OnlineButton>)>,
+    mut join_online_race: EventWriter<JoinOnlineRace>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            join_online_race.send(JoinOnlineRace::from_env());
+            break;
+        }
+    }
+}
+
+fn handle_mode_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<ModeButton>)>,
+    mode: Res<GameMode>,
+    mut select_game_mode: EventWriter<SelectGameMode>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            select_game_mode.send(SelectGameMode(mode.next()));
+            break;
+        }
+    }
+}
+
+fn handle_challenge_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<ChallengeButton>)>,
+    mut open_challenge_menu: EventWriter<OpenChallengeMenu>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            open_challenge_menu.send(OpenChallengeMenu);
+            break;
+        }
+    }
+}
+
+fn handle_achievements_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<AchievementsButton>)>,
+    mut open_achievements_menu: EventWriter<OpenAchievementsMenu>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            open_achievements_menu.send(OpenAchievementsMenu);
+            break;
+        }
+    }
+}
+
+fn handle_skins_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<SkinsButton>)>,
+    mut open_skins_menu: EventWriter<OpenSkinsMenu>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            open_skins_menu.send(OpenSkinsMenu);
+            break;
+        }
+    }
+}
+
+fn handle_shop_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<ShopButton>)>,
+    mut open_shop_menu: EventWriter<OpenShopMenu>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            open_shop_menu.send(OpenShopMenu);
+            break;
+        }
+    }
+}
+
+fn handle_boost_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<BoostButton>)>,
+    mut use_boosts: ResMut<UseBoosts>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            use_boosts.0 = !use_boosts.0;
             break;
         }
     }
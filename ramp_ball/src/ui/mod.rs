@@ -1,17 +1,62 @@
+use achievements_menu::AchievementsMenuPlugin;
 use bevy::prelude::*;
 use bevy_bsml::BsmlPlugin;
+use challenge_menu::ChallengeMenuPlugin;
+use controls_menu::ControlsMenuPlugin;
+use editor_toolbar::EditorToolbarPlugin;
 use game_over_menu::GameOverMenuPlugin;
 use hud::HudPlugin;
+use killcam_overlay::KillcamOverlayPlugin;
 use main_menu::MainMenuPlugin;
+use online_status::OnlineStatusPlugin;
+use pause_menu::PauseMenuPlugin;
+use replay_viewer::ReplayViewerPlugin;
+use shop_menu::ShopMenuPlugin;
+use skins_menu::SkinsMenuPlugin;
+use stats_menu::StatsMenuPlugin;
+use toast::ToastPlugin;
 
+mod achievements_menu;
+mod challenge_menu;
+mod controls_menu;
+mod editor_toolbar;
 mod game_over_menu;
 mod hud;
+mod killcam_overlay;
 mod main_menu;
+mod online_status;
+mod pause_menu;
+mod replay_viewer;
+mod shop_menu;
+mod skins_menu;
+mod stats_menu;
+mod toast;
 
 pub struct GameUiPlugin;
 
 impl Plugin for GameUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((BsmlPlugin, GameOverMenuPlugin, HudPlugin, MainMenuPlugin));
+        app.add_plugins((
+            (
+                AchievementsMenuPlugin,
+                BsmlPlugin,
+                ChallengeMenuPlugin,
+                ControlsMenuPlugin,
+                EditorToolbarPlugin,
+                GameOverMenuPlugin,
+                HudPlugin,
+                KillcamOverlayPlugin,
+            ),
+            (
+                MainMenuPlugin,
+                OnlineStatusPlugin,
+                PauseMenuPlugin,
+                ReplayViewerPlugin,
+                ShopMenuPlugin,
+                SkinsMenuPlugin,
+                StatsMenuPlugin,
+                ToastPlugin,
+            ),
+        ));
     }
 }
@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+use bevy_bsml::prelude::*;
+
+use crate::online::OnlineRace;
+
+pub struct OnlineStatusPlugin;
+
+impl Plugin for OnlineStatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_online_status.run_if(resource_added::<OnlineRace>),
+                update_online_status.run_if(resource_exists::<OnlineRace>),
+                despawn_online_status.run_if(resource_removed::<OnlineRace>()),
+            ),
+        );
+    }
+}
+
+#[derive(Component)]
+struct OnlineStatus {
+    text: String,
+}
+
+bsml! {OnlineStatus;
+    (node class=[W_FULL, H_FULL, JUSTIFY_END, BG_TRANSPARENT]) {
+        (node) {
+            (text class=[FontSize::px(24.0)]) { "{}", self.text }
+        }
+    }
+}
+
+fn spawn_online_status(mut commands: Commands, race: Res<OnlineRace>) {
+    commands.spawn_bsml(OnlineStatus {
+        text: race.status(),
+    });
+}
+
+fn update_online_status(mut query: Query<&mut OnlineStatus>, race: Res<OnlineRace>) {
+    let Ok(mut status) = query.get_single_mut() else {
+        return;
+    };
+
+    let text = race.status();
+    if status.text != text {
+        status.text = text;
+    }
+}
+
+fn despawn_online_status(query: Query<Entity, With<OnlineStatus>>, mut commands: Commands) {
+    if let Ok(entity) = query.get_single() {
+        commands.despawn_bsml(entity);
+    }
+}
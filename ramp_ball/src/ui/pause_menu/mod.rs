@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use bevy_bsml::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState},
+    player::PlayerState,
+    replay::ReplayPlayback,
+    GameState,
+};
+
+pub struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(GameState::Playing), close_pause_menu)
+            .add_systems(
+                Update,
+                (
+                    toggle_pause_menu,
+                    handle_resume_button_pressed,
+                    handle_quit_button_pressed,
+                )
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(PlayerState::Alive))
+                    .run_if(not(resource_exists::<ReplayPlayback>)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component)]
+struct ResumeButton;
+
+#[derive(Component)]
+struct QuitButton;
+
+bsml! {PauseMenu;
+    (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_CENTER, ITEMS_CENTER, BG_TRANSPARENT]) {
+        (node class=[FLEX_COL, ITEMS_CENTER, gap(25.0)]) {
+            (text class=[FontSize::px(40.0)]) { "Paused" }
+            (node class=[gap(12.5)]) {
+                (node labels=[ResumeButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Resume" }
+                }
+                (node labels=[QuitButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Quit" }
+                }
+            }
+        }
+    }
+}
+
+fn open_pause_menu(commands: &mut Commands, time: &mut Time<Virtual>) {
+    time.pause();
+    commands.spawn_bsml(PauseMenu);
+}
+
+fn close_pause_menu(
+    mut commands: Commands,
+    menus: Query<Entity, With<PauseMenu>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    for entity in menus.iter() {
+        commands.despawn_bsml(entity);
+        time.unpause();
+    }
+}
+
+fn toggle_pause_menu(
+    mut commands: Commands,
+    menus: Query<Entity, With<PauseMenu>>,
+    actions: Res<ActionState>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let is_open = !menus.is_empty();
+
+    if actions.just_pressed(Action::Pause) && !is_open {
+        open_pause_menu(&mut commands, &mut time);
+    } else if is_open
+        && (actions.just_pressed(Action::Pause)
+            || actions.just_pressed(Action::Confirm)
+            || actions.just_pressed(Action::Back))
+    {
+        close_pause_menu(commands, menus, time);
+    }
+}
+
+fn handle_resume_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<ResumeButton>)>,
+    commands: Commands,
+    menus: Query<Entity, With<PauseMenu>>,
+    time: ResMut<Time<Virtual>>,
+) {
+    if interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        close_pause_menu(commands, menus, time);
+    }
+}
+
+fn handle_quit_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<QuitButton>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        next_state.set(GameState::MainMenu);
+    }
+}
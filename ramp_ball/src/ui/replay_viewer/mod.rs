@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use bevy_bsml::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState},
+    replay::{ReplayCommand, ReplayPlayback, ReplayState},
+};
+
+pub struct ReplayViewerPlugin;
+
+impl Plugin for ReplayViewerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(ReplayState::Viewing), spawn_replay_viewer)
+            .add_systems(OnExit(ReplayState::Viewing), despawn_replay_viewer)
+            .add_systems(
+                Update,
+                (
+                    update_replay_viewer,
+                    handle_viewer_actions,
+                    send_on_press::<SeekBackButton>(ReplayCommand::SeekBy(-SEEK_STEP)),
+                    send_on_press::<SlowerButton>(ReplayCommand::Slower),
+                    send_on_press::<PauseButton>(ReplayCommand::TogglePause),
+                    send_on_press::<FasterButton>(ReplayCommand::Faster),
+                    send_on_press::<SeekForwardButton>(ReplayCommand::SeekBy(SEEK_STEP)),
+                    send_on_press::<FreeCameraButton>(ReplayCommand::ToggleFreeCamera),
+                    send_on_press::<ExitButton>(ReplayCommand::Exit),
+                )
+                    .run_if(in_state(ReplayState::Viewing)),
+            );
+    }
+}
+
+const SEEK_STEP: f32 = 5.0;
+
+#[derive(Component)]
+struct ReplayViewer {
+    status: String,
+}
+
+#[derive(Component)]
+struct SeekBackButton;
+
+#[derive(Component)]
+struct SlowerButton;
+
+#[derive(Component)]
+struct PauseButton;
+
+#[derive(Component)]
+struct FasterButton;
+
+#[derive(Component)]
+struct SeekForwardButton;
+
+#[derive(Component)]
+struct FreeCameraButton;
+
+#[derive(Component)]
+struct ExitButton;
+
+bsml! {ReplayViewer;
+    (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_END, ITEMS_CENTER, BG_TRANSPARENT]) {
+        (node class=[FLEX_COL, ITEMS_CENTER, gap(12.5)]) {
+            (text class=[FontSize::px(24.0)]) { "{}", self.status }
+            (node class=[gap(12.5)]) {
+                (node labels=[SeekBackButton] class=[w_px(80.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "-5s" }
+                }
+                (node labels=[SlowerButton] class=[w_px(80.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "Slower" }
+                }
+                (node labels=[PauseButton] class=[w_px(80.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "Pause" }
+                }
+                (node labels=[FasterButton] class=[w_px(80.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "Faster" }
+                }
+                (node labels=[SeekForwardButton] class=[w_px(80.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500, pressed(BG_BLUE_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "+5s" }
+                }
+                (node labels=[FreeCameraButton] class=[w_px(80.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "Camera" }
+                }
+                (node labels=[ExitButton] class=[w_px(80.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                    (text class=[FontSize::px(24.0)]) { "Exit" }
+                }
+            }
+        }
+    }
+}
+
+fn spawn_replay_viewer(mut commands: Commands) {
+    commands.spawn_bsml(ReplayViewer {
+        status: String::new(),
+    });
+}
+
+fn despawn_replay_viewer(query: Query<Entity, With<ReplayViewer>>, mut commands: Commands) {
+    if let Ok(entity) = query.get_single() {
+        commands.despawn_bsml(entity);
+    }
+}
+
+fn update_replay_viewer(
+    mut viewer_query: Query<&mut ReplayViewer>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    let (Ok(mut viewer), Some(playback)) = (viewer_query.get_single_mut(), playback) else {
+        return;
+    };
+
+    let status = format!(
+        "{:.1}s / {:.1}s  x{}{}{}",
+        playback.elapsed(),
+        playback.replay.duration(),
+        playback.speed(),
+        if playback.paused { "  paused" } else { "" },
+        if playback.free_camera.is_some() {
+            "  free camera (arrow keys)"
+        } else {
+            ""
+        },
+    );
+
+    if viewer.status != status {
+        viewer.status = status;
+    }
+}
+
+fn send_on_press<T: Component>(
+    command: ReplayCommand,
+) -> impl FnMut(Query<&Interaction, (Changed<Interaction>, With<T>)>, EventWriter<ReplayCommand>) {
+    move |interactions, mut replay_commands| {
+        if interactions
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed)
+        {
+            replay_commands.send(command);
+        }
+    }
+}
+
+fn handle_viewer_actions(
+    actions: Res<ActionState>,
+    mut replay_commands: EventWriter<ReplayCommand>,
+) {
+    if actions.just_pressed(Action::Pause) || actions.just_pressed(Action::Confirm) {
+        replay_commands.send(ReplayCommand::TogglePause);
+    }
+    if actions.just_pressed(Action::Back) {
+        replay_commands.send(ReplayCommand::Exit);
+    }
+}
@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+use bevy_bsml::prelude::*;
+
+use crate::{
+    shop::{BuyItem, PurchaseOutcome, BOOST_PRICE, REVIVE_PRICE},
+    skins::{Skin, SkinCatalogue, SkinCatalogueHandle, Unlock},
+    wallet::{Item, Wallet},
+};
+
+pub struct ShopMenuPlugin;
+
+impl Plugin for ShopMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OpenShopMenu>().add_systems(
+            Update,
+            (
+                open_shop_menu,
+                handle_previous_button_pressed,
+                handle_next_button_pressed,
+                handle_buy_skin_button_pressed,
+                handle_buy_revive_button_pressed,
+                handle_buy_boost_button_pressed,
+                handle_close_button_pressed,
+                show_purchase_outcomes,
+                update_shop_menu,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Sent by the main menu to spend coins.
+#[derive(Event)]
+pub struct OpenShopMenu;
+
+#[derive(Component)]
+pub struct ShopMenu {
+    /// The skin being looked at, as an index into the skins for sale.
+    index: usize,
+    balance: String,
+    skin: String,
+    revive: String,
+    boost: String,
+    status: String,
+}
+
+#[derive(Component)]
+struct PreviousButton;
+
+#[derive(Component)]
+struct NextButton;
+
+#[derive(Component)]
+struct BuySkinButton;
+
+#[derive(Component)]
+struct BuyReviveButton;
+
+#[derive(Component)]
+struct BuyBoostButton;
+
+#[derive(Component)]
+struct CloseButton;
+
+bsml! {ShopMenu;
+    (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_CENTER, ITEMS_CENTER, BG_TRANSPARENT]) {
+        (node class=[FLEX_COL, ITEMS_CENTER, gap(12.5)]) {
+            (text class=[FontSize::px(40.0)]) { "Shop" }
+            (text class=[FontSize::px(24.0)]) { "{}", self.balance }
+            (text class=[FontSize::px(24.0)]) { "{}", self.skin }
+            (node class=[gap(12.5)]) {
+                (node labels=[PreviousButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Previous" }
+                }
+                (node labels=[BuySkinButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Buy" }
+                }
+                (node labels=[NextButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Next" }
+                }
+            }
+            (node class=[gap(12.5), ITEMS_CENTER]) {
+                (text class=[FontSize::px(24.0)]) { "{}", self.revive }
+                (node labels=[BuyReviveButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Buy" }
+                }
+            }
+            (node class=[gap(12.5), ITEMS_CENTER]) {
+                (text class=[FontSize::px(24.0)]) { "{}", self.boost }
+                (node labels=[BuyBoostButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Buy" }
+                }
+            }
+            (text class=[FontSize::px(20.0)]) { "{}", self.status }
+            (node labels=[CloseButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                (text class=[FontSize::px(30.0)]) { "Close" }
+            }
+        }
+    }
+}
+
+/// The skins the catalogue sells, in catalogue order.
+fn skins_for_sale(catalogue: Option<&SkinCatalogue>) -> Vec<&Skin> {
+    catalogue
+        .map(|catalogue| {
+            catalogue
+                .skins
+                .iter()
+                .filter(|skin| matches!(skin.unlock, Unlock::Price(_)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn open_shop_menu(
+    mut commands: Commands,
+    mut events: EventReader<OpenShopMenu>,
+    menus: Query<(), With<ShopMenu>>,
+) {
+    if events.read().count() > 0 && menus.is_empty() {
+        commands.spawn_bsml(ShopMenu {
+            index: 0,
+            balance: String::new(),
+            skin: String::new(),
+            revive: format!("Revive: {} coins", REVIVE_PRICE),
+            boost: format!("Starting boost: {} coins", BOOST_PRICE),
+            status: String::new(),
+        });
+    }
+}
+
+fn handle_previous_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<PreviousButton>)>,
+    mut menus: Query<&mut ShopMenu>,
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+) {
+    let count = skins_for_sale(catalogues.get(&handle.0)).len();
+
+    if count > 0
+        && interactions
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        for mut menu in menus.iter_mut() {
+            menu.index = (menu.index + count - 1) % count;
+        }
+    }
+}
+
+fn handle_next_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<NextButton>)>,
+    mut menus: Query<&mut ShopMenu>,
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+) {
+    let count = skins_for_sale(catalogues.get(&handle.0)).len();
+
+    if count > 0
+        && interactions
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        for mut menu in menus.iter_mut() {
+            menu.index = (menu.index + 1) % count;
+        }
+    }
+}
+
+fn handle_buy_skin_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<BuySkinButton>)>,
+    menus: Query<&ShopMenu>,
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+    mut buy_item: EventWriter<BuyItem>,
+) {
+    if !interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+
+    let skins = skins_for_sale(catalogues.get(&handle.0));
+    for menu in menus.iter() {
+        if let Some(skin) = skins.get(menu.index) {
+            buy_item.send(BuyItem(Item::Skin(skin.id.clone())));
+        }
+    }
+}
+
+fn handle_buy_revive_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<BuyReviveButton>)>,
+    mut buy_item: EventWriter<BuyItem>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            buy_item.send(BuyItem(Item::Revive));
+            break;
+        }
+    }
+}
+
+fn handle_buy_boost_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<BuyBoostButton>)>,
+    mut buy_item: EventWriter<BuyItem>,
+) {
+    for interaction in interactions.iter() {
+        if *interaction == Interaction::Pressed {
+            buy_item.send(BuyItem(Item::Boost));
+            break;
+        }
+    }
+}
+
+fn show_purchase_outcomes(
+    mut outcomes: EventReader<PurchaseOutcome>,
+    mut menus: Query<&mut ShopMenu>,
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+) {
+    let Some(outcome) = outcomes.read().last() else {
+        return;
+    };
+
+    let name = match &outcome.item {
+        Item::Skin(id) => catalogues
+            .get(&handle.0)
+            .and_then(|catalogue| catalogue.skins.iter().find(|skin| skin.id == *id))
+            .map_or(id.clone(), |skin| skin.name.clone()),
+        Item::Revive => "a revive".to_string(),
+        Item::Boost => "a starting boost".to_string(),
+    };
+    let status = match &outcome.result {
+        Ok(()) => format!("Bought {}", name),
+        Err(error) => format!("Can't buy {}: {}", name, error),
+    };
+
+    for mut menu in menus.iter_mut() {
+        menu.status = status.clone();
+    }
+}
+
+fn update_shop_menu(
+    mut menus: Query<&mut ShopMenu>,
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+    wallet: Res<Wallet>,
+) {
+    let balance = wallet.describe();
+    let skins = skins_for_sale(catalogues.get(&handle.0));
+
+    for mut menu in menus.iter_mut() {
+        let skin = match skins.get(menu.index) {
+            Some(skin) if wallet.owns_skin(&skin.id) => format!("{}: owned", skin.name),
+            Some(Skin {
+                name,
+                unlock: Unlock::Price(coins),
+                ..
+            }) => format!("{}: {} coins", name, coins),
+            _ => "No skins for sale".to_string(),
+        };
+
+        if menu.balance != balance {
+            menu.balance = balance.clone();
+        }
+        if menu.skin != skin {
+            menu.skin = skin;
+        }
+    }
+}
+
+fn handle_close_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<CloseButton>)>,
+    menus: Query<Entity, With<ShopMenu>>,
+    mut commands: Commands,
+) {
+    if interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        for entity in menus.iter() {
+            commands.despawn_bsml(entity);
+        }
+    }
+}
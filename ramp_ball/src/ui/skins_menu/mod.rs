@@ -0,0 +1,217 @@
+use bevy::prelude::*;
+use bevy_bsml::prelude::*;
+
+use crate::{
+    achievements::AchievementRecord,
+    player::PlayerLook,
+    skins::{SelectSkin, SkinCatalogue, SkinCatalogueHandle},
+    stats::LifetimeStats,
+    wallet::Wallet,
+};
+
+pub struct SkinsMenuPlugin;
+
+impl Plugin for SkinsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OpenSkinsMenu>().add_systems(
+            Update,
+            (
+                open_skins_menu,
+                handle_previous_button_pressed,
+                handle_next_button_pressed,
+                handle_wear_button_pressed,
+                handle_close_button_pressed,
+                update_skins_menu,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Sent by the main menu to browse and pick ball skins.
+#[derive(Event)]
+pub struct OpenSkinsMenu;
+
+#[derive(Component)]
+pub struct SkinsMenu {
+    /// The skin being looked at, as an index into the catalogue.
+    index: usize,
+    name: String,
+    status: String,
+}
+
+#[derive(Component)]
+struct PreviousButton;
+
+#[derive(Component)]
+struct NextButton;
+
+#[derive(Component)]
+struct WearButton;
+
+#[derive(Component)]
+struct CloseButton;
+
+bsml! {SkinsMenu;
+    (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_CENTER, ITEMS_CENTER, BG_TRANSPARENT]) {
+        (node class=[FLEX_COL, ITEMS_CENTER, gap(12.5)]) {
+            (text class=[FontSize::px(40.0)]) { "Skins" }
+            (text class=[FontSize::px(30.0)]) { "{}", self.name }
+            (text class=[FontSize::px(20.0)]) { "{}", self.status }
+            (node class=[gap(12.5)]) {
+                (node labels=[PreviousButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Previous" }
+                }
+                (node labels=[WearButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GREEN_500, pressed(BG_GREEN_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Wear" }
+                }
+                (node labels=[NextButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                    (text class=[FontSize::px(30.0)]) { "Next" }
+                }
+            }
+            (node labels=[CloseButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                (text class=[FontSize::px(30.0)]) { "Close" }
+            }
+        }
+    }
+}
+
+fn open_skins_menu(
+    mut commands: Commands,
+    mut events: EventReader<OpenSkinsMenu>,
+    menus: Query<(), With<SkinsMenu>>,
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+    look: Res<PlayerLook>,
+) {
+    if events.read().count() == 0 || !menus.is_empty() {
+        return;
+    }
+
+    // Start on the skin being worn.
+    let index = catalogues
+        .get(&handle.0)
+        .and_then(|catalogue| catalogue.skins.iter().position(|skin| skin.look() == *look))
+        .unwrap_or(0);
+
+    commands.spawn_bsml(SkinsMenu {
+        index,
+        name: String::new(),
+        status: String::new(),
+    });
+}
+
+fn handle_previous_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<PreviousButton>)>,
+    mut menus: Query<&mut SkinsMenu>,
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+) {
+    let Some(catalogue) = catalogues.get(&handle.0) else {
+        return;
+    };
+
+    if !catalogue.skins.is_empty()
+        && interactions
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        for mut menu in menus.iter_mut() {
+            menu.index = (menu.index + catalogue.skins.len() - 1) % catalogue.skins.len();
+        }
+    }
+}
+
+fn handle_next_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<NextButton>)>,
+    mut menus: Query<&mut SkinsMenu>,
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+) {
+    let Some(catalogue) = catalogues.get(&handle.0) else {
+        return;
+    };
+
+    if !catalogue.skins.is_empty()
+        && interactions
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        for mut menu in menus.iter_mut() {
+            menu.index = (menu.index + 1) % catalogue.skins.len();
+        }
+    }
+}
+
+fn handle_wear_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<WearButton>)>,
+    menus: Query<&SkinsMenu>,
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+    mut select_skin: EventWriter<SelectSkin>,
+) {
+    if !interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+
+    for menu in menus.iter() {
+        if let Some(skin) = catalogues
+            .get(&handle.0)
+            .and_then(|catalogue| catalogue.skins.get(menu.index))
+        {
+            select_skin.send(SelectSkin(skin.id.clone()));
+        }
+    }
+}
+
+fn update_skins_menu(
+    mut menus: Query<&mut SkinsMenu>,
+    handle: Res<SkinCatalogueHandle>,
+    catalogues: Res<Assets<SkinCatalogue>>,
+    look: Res<PlayerLook>,
+    achievements: Res<AchievementRecord>,
+    stats: Res<LifetimeStats>,
+    wallet: Res<Wallet>,
+) {
+    for mut menu in menus.iter_mut() {
+        let (name, status) = match catalogues
+            .get(&handle.0)
+            .and_then(|catalogue| catalogue.skins.get(menu.index))
+        {
+            Some(skin) if skin.look() == *look => (skin.name.clone(), "Wearing".to_string()),
+            Some(skin) if skin.is_unlocked(&achievements, &stats, &wallet) => {
+                (skin.name.clone(), "Unlocked".to_string())
+            }
+            Some(skin) => (
+                skin.name.clone(),
+                format!("Locked: {}", skin.unlock.describe()),
+            ),
+            None => ("Loading skins...".to_string(), String::new()),
+        };
+
+        if menu.name != name {
+            menu.name = name;
+        }
+        if menu.status != status {
+            menu.status = status;
+        }
+    }
+}
+
+fn handle_close_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<CloseButton>)>,
+    menus: Query<Entity, With<SkinsMenu>>,
+    mut commands: Commands,
+) {
+    if interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        for entity in menus.iter() {
+            commands.despawn_bsml(entity);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+use bevy_bsml::prelude::*;
+
+use crate::{
+    events::DeathCause,
+    stats::{LifetimeStats, RunStats},
+};
+
+pub struct StatsMenuPlugin;
+
+impl Plugin for StatsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OpenStatsMenu>()
+            .add_systems(Update, (open_stats_menu, handle_close_button_pressed));
+    }
+}
+
+/// Sent by other menus to show the stats screen on top of them.
+#[derive(Event)]
+pub struct OpenStatsMenu;
+
+#[derive(Component)]
+pub struct StatsMenu {
+    runs_played: String,
+    total_distance: String,
+    best_distance: String,
+    deaths: String,
+    finishes: String,
+    dive_time: String,
+    max_speed: String,
+    max_altitude: String,
+    last_run: String,
+}
+
+#[derive(Component)]
+struct CloseButton;
+
+bsml! {StatsMenu;
+    (node class=[W_FULL, H_FULL, FLEX_COL, JUSTIFY_CENTER, ITEMS_CENTER, BG_TRANSPARENT]) {
+        (node class=[FLEX_COL, ITEMS_CENTER, gap(12.5)]) {
+            (text class=[FontSize::px(40.0)]) { "Stats" }
+            (text class=[FontSize::px(24.0)]) { "{}", self.runs_played }
+            (text class=[FontSize::px(24.0)]) { "{}", self.total_distance }
+            (text class=[FontSize::px(24.0)]) { "{}", self.best_distance }
+            (text class=[FontSize::px(24.0)]) { "{}", self.deaths }
+            (text class=[FontSize::px(24.0)]) { "{}", self.finishes }
+            (text class=[FontSize::px(24.0)]) { "{}", self.dive_time }
+            (text class=[FontSize::px(24.0)]) { "{}", self.max_speed }
+            (text class=[FontSize::px(24.0)]) { "{}", self.max_altitude }
+            (text class=[FontSize::px(20.0)]) { "{}", self.last_run }
+            (node labels=[CloseButton] class=[w_px(150.0), h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_GRAY_500, pressed(BG_GRAY_400)]) {
+                (text class=[FontSize::px(30.0)]) { "Close" }
+            }
+        }
+    }
+}
+
+fn stats_menu(lifetime: &LifetimeStats, run: &RunStats) -> StatsMenu {
+    StatsMenu {
+        runs_played: format!("Runs played: {}", lifetime.runs_played),
+        total_distance: format!("Total distance: {:.0} m", lifetime.total_distance),
+        best_distance: format!("Best distance: {:.0} m", lifetime.best_distance),
+        deaths: format!(
+            "Deaths: {} by spikes, {} by falling",
+            lifetime.deaths_by(DeathCause::Spikes),
+            lifetime.deaths_by(DeathCause::Fell)
+        ),
+        finishes: format!("Time trials finished: {}", lifetime.finishes),
+        dive_time: format!("Time diving: {:.0} s", lifetime.total_dive_time),
+        max_speed: format!("Top speed: {:.1} m/s", lifetime.max_speed),
+        max_altitude: format!("Highest altitude: {:.1} m", lifetime.max_altitude),
+        last_run: match run.cause {
+            Some(_) => format!(
+                "Last run: {:.0} m, {:.1} s diving, top speed {:.1} m/s",
+                run.distance, run.dive_time, run.max_speed
+            ),
+            None => "No runs yet this session".to_string(),
+        },
+    }
+}
+
+fn open_stats_menu(
+    mut commands: Commands,
+    mut events: EventReader<OpenStatsMenu>,
+    menus: Query<(), With<StatsMenu>>,
+    lifetime: Res<LifetimeStats>,
+    run: Res<RunStats>,
+) {
+    if events.read().count() > 0 && menus.is_empty() {
+        commands.spawn_bsml(stats_menu(&lifetime, &run));
+    }
+}
+
+fn handle_close_button_pressed(
+    interactions: Query<&Interaction, (Changed<Interaction>, With<CloseButton>)>,
+    menus: Query<Entity, With<StatsMenu>>,
+    mut commands: Commands,
+) {
+    if interactions
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        for entity in menus.iter() {
+            commands.despawn_bsml(entity);
+        }
+    }
+}
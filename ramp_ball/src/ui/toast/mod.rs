@@ -0,0 +1,87 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_bsml::prelude::*;
+
+use crate::{achievements::AchievementUnlocked, missions::MissionCompleted};
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Toasts>()
+            .add_systems(Update, (queue_toasts, show_toasts).chain());
+    }
+}
+
+/// How long each toast stays up, in seconds.
+const TOAST_SECONDS: f32 = 3.0;
+
+/// Unlocks and completed missions waiting to be shown, one toast at a time.
+#[derive(Resource)]
+struct Toasts {
+    queue: VecDeque<String>,
+    timer: Timer,
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            timer: Timer::from_seconds(TOAST_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+#[derive(Component)]
+struct Toast {
+    text: String,
+}
+
+bsml! {Toast;
+    (node class=[W_FULL, H_FULL, FLEX_COL, ITEMS_CENTER, BG_TRANSPARENT]) {
+        (node class=[h_px(50.0), JUSTIFY_CENTER, ITEMS_CENTER, BG_BLUE_500]) {
+            (text class=[FontSize::px(24.0)]) { "{}", self.text }
+        }
+    }
+}
+
+fn queue_toasts(
+    mut unlocked: EventReader<AchievementUnlocked>,
+    mut completed: EventReader<MissionCompleted>,
+    mut toasts: ResMut<Toasts>,
+) {
+    for AchievementUnlocked(achievement) in unlocked.read() {
+        toasts.queue.push_back(format!(
+            "Achievement unlocked: {} - {}",
+            achievement.name, achievement.description
+        ));
+    }
+
+    for MissionCompleted(mission) in completed.read() {
+        toasts.queue.push_back(format!(
+            "Mission complete: {}  +{} coins",
+            mission.description, mission.reward
+        ));
+    }
+}
+
+/// Takes the current toast down once its time is up and puts up the next.
+fn show_toasts(
+    time: Res<Time>,
+    mut toasts: ResMut<Toasts>,
+    shown: Query<Entity, With<Toast>>,
+    mut commands: Commands,
+) {
+    if let Ok(entity) = shown.get_single() {
+        if !toasts.timer.tick(time.delta()).finished() {
+            return;
+        }
+        commands.despawn_bsml(entity);
+    }
+
+    if let Some(text) = toasts.queue.pop_front() {
+        toasts.timer.reset();
+        commands.spawn_bsml(Toast { text });
+    }
+}
@@ -0,0 +1,182 @@
+//! Re-simulates a replay without a window to find out how the run really
+//! ended. The leaderboard server runs this to check submitted scores.
+
+use std::fmt;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    events::RunEnded,
+    headless_app,
+    replay::{PhysicsConstants, Replay, ReplayError, ReplayPlayback},
+    seed::CourseSeed,
+    tuning::{tuning_settled, Tuning},
+    HEADLESS_FRAME,
+};
+
+/// Updates to wait for the tuning file before giving up.
+const TUNING_TIMEOUT_UPDATES: u32 = 600;
+
+/// Updates allowed past the end of the recording for the run to end, to
+/// cover the menu handing over to the run.
+const GRACE_UPDATES: u32 = 64;
+
+/// How a re-simulated run ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunOutcome {
+    pub seed: u64,
+    pub distance: f32,
+    pub ticks: u32,
+}
+
+/// One line, read back by the leaderboard server.
+impl fmt::Display for RunOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "seed {} distance {} ticks {}",
+            self.seed, self.distance, self.ticks
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    Replay(ReplayError),
+    TuningNotLoaded,
+    RunDidNotEnd,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Replay(error) => write!(f, "{}", error),
+            VerifyError::TuningNotLoaded => write!(f, "tuning did not finish loading"),
+            VerifyError::RunDidNotEnd => write!(f, "the run did not end when the replay did"),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct EndOfRun(Option<RunOutcome>);
+
+pub fn verify_replay(bytes: &[u8]) -> Result<RunOutcome, VerifyError> {
+    let replay = Replay::decode(bytes).map_err(VerifyError::Replay)?;
+
+    let mut app = headless_app();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(HEADLESS_FRAME))
+        .init_resource::<EndOfRun>()
+        .add_systems(Last, record_end_of_run);
+
+    let mut updates = 0;
+    while !tuning_settled(app.world()) {
+        if updates == TUNING_TIMEOUT_UPDATES {
+            return Err(VerifyError::TuningNotLoaded);
+        }
+        app.update();
+        updates += 1;
+    }
+
+    let world = app.world_mut();
+    let constants =
+        PhysicsConstants::current(world.resource::<Tuning>(), world.resource::<Time<Fixed>>());
+    replay
+        .check_compatible(&constants)
+        .map_err(VerifyError::Replay)?;
+
+    let total_ticks = replay.total_ticks;
+    let previous_seed = *world.resource::<CourseSeed>();
+    *world.resource_mut::<CourseSeed>() = CourseSeed(replay.seed);
+    world.insert_resource(ReplayPlayback::new(replay, previous_seed));
+
+    // One fixed tick per update, so the run gets the same ticks it was
+    // recorded with.
+    for _ in 0..total_ticks + GRACE_UPDATES {
+        app.update();
+        if let Some(outcome) = app.world().resource::<EndOfRun>().0 {
+            return Ok(outcome);
+        }
+    }
+
+    Err(VerifyError::RunDidNotEnd)
+}
+
+fn record_end_of_run(
+    mut run_ended: EventReader<RunEnded>,
+    playback: Option<Res<ReplayPlayback>>,
+    seed: Res<CourseSeed>,
+    mut end: ResMut<EndOfRun>,
+) {
+    let (Some(run_ended), Some(playback)) = (run_ended.read().next(), playback) else {
+        return;
+    };
+
+    if end.0.is_none() {
+        end.0 = Some(RunOutcome {
+            seed: seed.0,
+            distance: run_ended.distance,
+            ticks: playback.tick,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actions::Action,
+        harness::{GameHarness, SPIKES_CATCH_IDLE_PLAYER_SECONDS},
+        player::PlayerState,
+        replay::RunRecorded,
+    };
+
+    #[test]
+    fn a_recorded_run_verifies_to_the_same_distance() {
+        let mut harness = GameHarness::new();
+        harness.record::<RunRecorded>();
+        harness.run_until(5.0, |harness| tuning_settled(harness.world()));
+
+        harness.start_run();
+        harness.hold(Action::Dive);
+        harness.run_seconds(3.0);
+        harness.release(Action::Dive);
+        let died = harness.run_until(SPIKES_CATCH_IDLE_PLAYER_SECONDS, |harness| {
+            harness.player_state() == PlayerState::Dead
+        });
+        assert!(died);
+
+        let replay = harness.recorded::<RunRecorded>()[0].0.clone();
+        let outcome = verify_replay(&replay.encode()).unwrap();
+
+        assert_eq!(outcome.seed, replay.seed);
+        assert_eq!(outcome.distance, harness.recorded::<RunEnded>()[0].distance);
+    }
+
+    #[test]
+    fn a_run_recorded_in_a_resized_window_verifies_to_the_same_distance() {
+        let mut harness = GameHarness::new();
+        harness.record::<RunRecorded>();
+        harness.run_until(5.0, |harness| tuning_settled(harness.world()));
+
+        let world = harness.world_mut();
+        let mut windows = world.query::<&mut Window>();
+        for mut window in windows.iter_mut(world) {
+            window.resolution.set(640.0, 1400.0);
+        }
+
+        harness.start_run();
+        harness.hold(Action::Dive);
+        harness.run_seconds(3.0);
+        harness.release(Action::Dive);
+        let died = harness.run_until(SPIKES_CATCH_IDLE_PLAYER_SECONDS, |harness| {
+            harness.player_state() == PlayerState::Dead
+        });
+        assert!(died);
+
+        let replay = harness.recorded::<RunRecorded>()[0].0.clone();
+        let outcome = verify_replay(&replay.encode()).unwrap();
+
+        assert_eq!(outcome.seed, replay.seed);
+        assert_eq!(outcome.distance, harness.recorded::<RunEnded>()[0].distance);
+    }
+}
@@ -0,0 +1,242 @@
+use bevy::{
+    prelude::*,
+    render::{
+        camera::{Camera as RenderCamera, ClearColorConfig, Viewport},
+        view::RenderLayers,
+    },
+    ui::IsDefaultUiCamera,
+};
+
+use crate::{
+    camera::{Camera, CameraRig},
+    game_mode::ranked_mode,
+    player::{spawn_player_ball, PlayerLook, Seat},
+    tuning::Tuning,
+    GameState,
+};
+
+/// Local split-screen races: two balls on the same course, each followed by
+/// its own half of the screen. The last ball left wins.
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StartVersus>()
+            .add_systems(OnEnter(GameState::MainMenu), end_versus)
+            // Matches are played by the endless rules, so not from zen or
+            // time trial.
+            .add_systems(
+                Update,
+                start_versus
+                    .run_if(in_state(GameState::MainMenu))
+                    .run_if(ranked_mode),
+            )
+            .add_systems(Update, split_viewports.run_if(resource_exists::<Versus>));
+    }
+}
+
+/// Render layer nothing in the world is on, so the UI camera only draws UI.
+const UI_ONLY_LAYER: usize = 31;
+
+/// Sent by the main menu to start a match.
+#[derive(Event)]
+pub struct StartVersus;
+
+/// Present for the length of a versus match.
+#[derive(Resource, Debug, Default)]
+pub struct Versus {
+    /// Set once the match is over. `None` at that point means both balls
+    /// went out together.
+    pub winner: Option<Seat>,
+}
+
+impl Versus {
+    /// Settles the match once at most one ball is left, and says whether it
+    /// is over.
+    pub fn resolve(&mut self, remaining: &[Seat]) -> bool {
+        match remaining {
+            [winner] => self.winner = Some(*winner),
+            [] => self.winner = None,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Cameras that only exist during a match.
+#[derive(Component)]
+struct VersusCamera;
+
+fn start_versus(
+    mut events: EventReader<StartVersus>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    tuning: Res<Tuning>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+
+    commands.insert_resource(Versus::default());
+
+    // The first ball is already waiting in the menu.
+    spawn_player_ball(
+        &mut commands,
+        &asset_server,
+        &mut meshes,
+        &mut materials,
+        &tuning,
+        Seat::Two,
+        &PlayerLook::for_seat(Seat::Two),
+        Vec3::ZERO,
+    );
+
+    commands.spawn((
+        VersusCamera,
+        Camera,
+        Seat::Two,
+        CameraRig::default(),
+        Camera2dBundle {
+            camera: RenderCamera {
+                order: 1,
+                ..default()
+            },
+            ..default()
+        },
+    ));
+
+    // With two viewports the UI would otherwise be squeezed into one of
+    // them, so it gets a full screen camera of its own.
+    commands.spawn((
+        VersusCamera,
+        IsDefaultUiCamera,
+        RenderLayers::layer(UI_ONLY_LAYER),
+        Camera2dBundle {
+            camera: RenderCamera {
+                order: 2,
+                clear_color: ClearColorConfig::None,
+                ..default()
+            },
+            ..default()
+        },
+    ));
+
+    next_state.set(GameState::Playing);
+}
+
+fn split_viewports(
+    windows: Query<&Window>,
+    mut cameras: Query<(&mut RenderCamera, &Seat), With<Camera>>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    let size = UVec2::new(window.physical_width() / 2, window.physical_height());
+
+    for (mut camera, seat) in cameras.iter_mut() {
+        let position = match seat {
+            Seat::One => UVec2::ZERO,
+            Seat::Two => UVec2::new(size.x, 0),
+        };
+
+        let current = camera
+            .viewport
+            .as_ref()
+            .map(|viewport| (viewport.physical_position, viewport.physical_size));
+
+        if current != Some((position, size)) {
+            camera.viewport = Some(Viewport {
+                physical_position: position,
+                physical_size: size,
+                ..default()
+            });
+        }
+    }
+}
+
+fn end_versus(
+    mut commands: Commands,
+    versus_cameras: Query<Entity, With<VersusCamera>>,
+    mut cameras: Query<&mut RenderCamera, (With<Camera>, Without<VersusCamera>)>,
+) {
+    commands.remove_resource::<Versus>();
+
+    for entity in versus_cameras.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for mut camera in cameras.iter_mut() {
+        if camera.viewport.is_some() {
+            camera.viewport = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{actions::Action, harness::GameHarness, player::PlayerState};
+
+    #[test]
+    fn the_last_ball_left_wins() {
+        let mut versus = Versus::default();
+
+        assert!(!versus.resolve(&[Seat::One, Seat::Two]));
+        assert_eq!(versus.winner, None);
+
+        assert!(versus.resolve(&[Seat::Two]));
+        assert_eq!(versus.winner, Some(Seat::Two));
+    }
+
+    #[test]
+    fn going_out_together_is_a_draw() {
+        let mut versus = Versus {
+            winner: Some(Seat::One),
+        };
+
+        assert!(versus.resolve(&[]));
+        assert_eq!(versus.winner, None);
+    }
+
+    #[test]
+    fn versus_puts_a_ball_on_the_course_for_each_player() {
+        let mut harness = GameHarness::new();
+
+        harness.start_versus();
+        harness.run_seconds(1.0);
+
+        assert_eq!(harness.seats(), vec![Seat::One, Seat::Two]);
+        assert_eq!(harness.player_state(), PlayerState::Alive);
+    }
+
+    #[test]
+    fn last_ball_left_wins_versus() {
+        let mut harness = GameHarness::new();
+
+        harness.start_versus();
+        harness.teleport_seat(Seat::Two, Vec2::new(0.0, -2000.0));
+        harness.tick();
+
+        assert_eq!(harness.player_state(), PlayerState::Dead);
+        assert_eq!(harness.world().resource::<Versus>().winner, Some(Seat::One));
+        assert_eq!(harness.game_state(), GameState::Playing);
+    }
+
+    #[test]
+    fn continuing_after_versus_goes_back_to_one_ball() {
+        let mut harness = GameHarness::new();
+
+        harness.start_versus();
+        harness.teleport_seat(Seat::One, Vec2::new(0.0, -2000.0));
+        harness.tick();
+        harness.press(Action::Confirm);
+
+        assert_eq!(harness.game_state(), GameState::MainMenu);
+        assert!(harness.world().get_resource::<Versus>().is_none());
+        assert_eq!(harness.seats(), vec![Seat::One]);
+    }
+}
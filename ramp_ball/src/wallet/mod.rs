@@ -0,0 +1,243 @@
+use std::{collections::BTreeSet, fmt};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::EditedRun,
+    events::CoinCollected,
+    replay::ReplayPlayback,
+    save::{self, SaveDirectory, SaveError},
+    versus::Versus,
+};
+
+/// The coins a player has banked, from pickups and rewards, and what they've
+/// bought with them.
+pub struct WalletPlugin;
+
+impl Plugin for WalletPlugin {
+    fn build(&self, app: &mut App) {
+        let saves = save::directory(app);
+
+        app.insert_resource(saves.load::<Wallet>(WALLET_FILE))
+            .add_systems(
+                Update,
+                bank_collected_coins
+                    .run_if(not(resource_exists::<ReplayPlayback>))
+                    .run_if(not(resource_exists::<EditedRun>))
+                    .run_if(not(resource_exists::<Versus>)),
+            );
+    }
+}
+
+const WALLET_FILE: &str = "wallet.ron";
+
+/// Coins and purchases live in one save file, so a purchase is written as a
+/// whole or not at all.
+#[derive(Resource, Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Wallet {
+    coins: u64,
+    /// Ids of the skins bought in the shop.
+    skins: BTreeSet<String>,
+    revives: u32,
+    boosts: u32,
+}
+
+/// Something the shop sells.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    /// A skin from the catalogue, by id.
+    Skin(String),
+    /// Another go after the run's free rewind is used up.
+    Revive,
+    /// A push forward at the start of a run.
+    Boost,
+}
+
+#[derive(Debug)]
+pub enum PurchaseError {
+    NotForSale,
+    AlreadyOwned,
+    NotEnoughCoins { price: u64, coins: u64 },
+    Save(SaveError),
+}
+
+impl fmt::Display for PurchaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PurchaseError::NotForSale => write!(f, "that isn't for sale"),
+            PurchaseError::AlreadyOwned => write!(f, "you already own that"),
+            PurchaseError::NotEnoughCoins { price, coins } => {
+                write!(f, "it costs {} coins and you have {}", price, coins)
+            }
+            PurchaseError::Save(error) => write!(f, "the wallet couldn't be saved: {}", error),
+        }
+    }
+}
+
+impl Wallet {
+    pub fn coins(&self) -> u64 {
+        self.coins
+    }
+
+    pub fn owns_skin(&self, id: &str) -> bool {
+        self.skins.contains(id)
+    }
+
+    pub fn revives(&self) -> u32 {
+        self.revives
+    }
+
+    pub fn boosts(&self) -> u32 {
+        self.boosts
+    }
+
+    /// Adds coins and saves. Nothing changes unless the save went through.
+    pub fn deposit(&mut self, coins: u64, saves: &SaveDirectory) -> Result<(), SaveError> {
+        let mut after = self.clone();
+        after.coins += coins;
+        self.commit(after, saves)
+    }
+
+    /// Pays for `item` and saves. Nothing changes unless the save went
+    /// through. The price has to come from the shop's catalogue.
+    pub fn buy(
+        &mut self,
+        item: &Item,
+        price: u64,
+        saves: &SaveDirectory,
+    ) -> Result<(), PurchaseError> {
+        let mut after = self.clone();
+        match item {
+            Item::Skin(id) => {
+                if !after.skins.insert(id.clone()) {
+                    return Err(PurchaseError::AlreadyOwned);
+                }
+            }
+            Item::Revive => after.revives += 1,
+            Item::Boost => after.boosts += 1,
+        }
+
+        after.coins = self
+            .coins
+            .checked_sub(price)
+            .ok_or(PurchaseError::NotEnoughCoins {
+                price,
+                coins: self.coins,
+            })?;
+
+        self.commit(after, saves).map_err(PurchaseError::Save)
+    }
+
+    /// Uses up a revive, if there is one, and saves.
+    pub fn use_revive(&mut self, saves: &SaveDirectory) -> bool {
+        self.take(saves, |wallet| &mut wallet.revives)
+    }
+
+    /// Uses up a boost, if there is one, and saves.
+    pub fn use_boost(&mut self, saves: &SaveDirectory) -> bool {
+        self.take(saves, |wallet| &mut wallet.boosts)
+    }
+
+    fn take(&mut self, saves: &SaveDirectory, count: impl Fn(&mut Wallet) -> &mut u32) -> bool {
+        let mut after = self.clone();
+        let left = count(&mut after);
+        if *left == 0 {
+            return false;
+        }
+        *left -= 1;
+
+        match self.commit(after, saves) {
+            Ok(()) => true,
+            Err(error) => {
+                error!("Failed to save {}: {}", WALLET_FILE, error);
+                false
+            }
+        }
+    }
+
+    /// Saves `after` and only then makes it the wallet.
+    fn commit(&mut self, after: Wallet, saves: &SaveDirectory) -> Result<(), SaveError> {
+        saves.try_store(WALLET_FILE, &after)?;
+        *self = after;
+        Ok(())
+    }
+
+    /// The balance and what's been bought but not used yet, for the menus.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} coins  {} revives  {} boosts",
+            self.coins, self.revives, self.boosts
+        )
+    }
+}
+
+/// Coins picked up along the course go into the wallet, saved as they're
+/// collected.
+fn bank_collected_coins(
+    mut coin_collected: EventReader<CoinCollected>,
+    mut wallet: ResMut<Wallet>,
+    saves: Res<SaveDirectory>,
+) {
+    let collected = coin_collected.read().count() as u64;
+    if collected == 0 {
+        return;
+    }
+
+    if let Err(error) = wallet.deposit(collected, &saves) {
+        error!("Failed to bank {} coins: {}", collected, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn purchases_pay_once_and_leave_the_wallet_alone_when_refused() {
+        let saves = SaveDirectory::default();
+        let mut wallet = Wallet {
+            coins: 250,
+            ..default()
+        };
+
+        wallet
+            .buy(&Item::Skin("japan".to_string()), 200, &saves)
+            .unwrap();
+        assert_eq!(wallet.coins(), 50);
+        assert!(wallet.owns_skin("japan"));
+
+        wallet.deposit(200, &saves).unwrap();
+        assert!(matches!(
+            wallet.buy(&Item::Skin("japan".to_string()), 200, &saves),
+            Err(PurchaseError::AlreadyOwned)
+        ));
+        assert!(matches!(
+            wallet.buy(&Item::Revive, 500, &saves),
+            Err(PurchaseError::NotEnoughCoins {
+                price: 500,
+                coins: 250
+            })
+        ));
+        assert_eq!(wallet.coins(), 250);
+        assert_eq!(wallet.revives(), 0);
+
+        wallet.buy(&Item::Boost, 100, &saves).unwrap();
+        assert!(wallet.use_boost(&saves));
+        assert!(!wallet.use_boost(&saves));
+        assert_eq!(wallet.coins(), 150);
+    }
+
+    #[test]
+    fn deposits_are_saved_as_they_are_made() {
+        let saves = SaveDirectory::default();
+        let mut wallet = Wallet::default();
+
+        wallet.deposit(3, &saves).unwrap();
+        wallet.deposit(1, &saves).unwrap();
+
+        assert_eq!(wallet.coins(), 4);
+        assert_eq!(saves.load::<Wallet>(WALLET_FILE).coins(), 4);
+    }
+}